@@ -0,0 +1,16 @@
+use solana_program::program_error::ProgramError;
+
+/// Returned as `ProgramError::Custom(code)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfitGuardError {
+    /// The token account holds less than the asserted minimum
+    BelowMinimum = 0,
+    /// The checked account is not an initialized SPL token account
+    InvalidTokenAccount = 1,
+}
+
+impl From<ProfitGuardError> for ProgramError {
+    fn from(e: ProfitGuardError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
@@ -0,0 +1,26 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum ProfitGuardInstruction {
+    /// Fail unless the token account's balance is at least `min_amount`.
+    ///
+    /// Accounts:
+    /// 0. `[]` SPL token account to check
+    AssertMinBalance { min_amount: u64 },
+}
+
+pub fn assert_min_balance(
+    program_id: &Pubkey,
+    token_account: &Pubkey,
+    min_amount: u64,
+) -> Instruction {
+    Instruction::new_with_borsh(
+        *program_id,
+        &ProfitGuardInstruction::AssertMinBalance { min_amount },
+        vec![AccountMeta::new_readonly(*token_account, false)],
+    )
+}
@@ -0,0 +1,9 @@
+//! Balance assertion appended to an arbitrage transaction: the whole transaction fails
+//! unless the checked token account ends with at least the expected amount.
+
+pub mod error;
+pub mod instruction;
+pub mod processor;
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(processor::process_instruction);
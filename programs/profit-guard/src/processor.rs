@@ -0,0 +1,48 @@
+use borsh::BorshDeserialize;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_token::state::Account as TokenAccount;
+
+use crate::{error::ProfitGuardError, instruction::ProfitGuardInstruction};
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let instruction = ProfitGuardInstruction::try_from_slice(data)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+    match instruction {
+        ProfitGuardInstruction::AssertMinBalance { min_amount } => {
+            assert_min_balance(accounts, min_amount)
+        }
+    }
+}
+
+fn assert_min_balance(accounts: &[AccountInfo], min_amount: u64) -> ProgramResult {
+    let iter = &mut accounts.iter();
+    let token_account = next_account_info(iter)?;
+    // Only the token program can have written the balance we are about to trust
+    if token_account.owner != &spl_token::id() {
+        msg!("{} is not owned by the token program", token_account.key);
+        return Err(ProfitGuardError::InvalidTokenAccount.into());
+    }
+    let account = TokenAccount::unpack(&token_account.data.borrow())
+        .map_err(|_| ProfitGuardError::InvalidTokenAccount)?;
+    if account.amount < min_amount {
+        msg!(
+            "{} holds {}, below the required {}",
+            token_account.key,
+            account.amount,
+            min_amount
+        );
+        return Err(ProfitGuardError::BelowMinimum.into());
+    }
+    Ok(())
+}
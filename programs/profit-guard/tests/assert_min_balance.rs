@@ -0,0 +1,92 @@
+use profit_guard::{
+    error::ProfitGuardError, instruction::assert_min_balance, processor::process_instruction,
+};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    instruction::InstructionError,
+    program_option::COption,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState};
+
+fn token_account(amount: u64) -> Account {
+    let state = TokenAccount {
+        mint: Pubkey::new_unique(),
+        owner: Pubkey::new_unique(),
+        amount,
+        delegate: COption::None,
+        state: AccountState::Initialized,
+        is_native: COption::None,
+        delegated_amount: 0,
+        close_authority: COption::None,
+    };
+    let mut data = vec![0; TokenAccount::LEN];
+    TokenAccount::pack(state, &mut data).unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(TokenAccount::LEN),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// Runs `AssertMinBalance { min_amount }` against `account`.
+async fn assert_balance(account: Account, min_amount: u64) -> Result<(), TransactionError> {
+    let program_id = Pubkey::new_unique();
+    let checked = Pubkey::new_unique();
+    let mut test = ProgramTest::new("profit_guard", program_id, processor!(process_instruction));
+    test.add_account(checked, account);
+    let (banks, payer, blockhash) = test.start().await;
+
+    let tx = Transaction::new_signed_with_payer(
+        &[assert_min_balance(&program_id, &checked, min_amount)],
+        Some(&payer.pubkey()),
+        &[&payer],
+        blockhash,
+    );
+    banks.process_transaction(tx).await.map_err(|e| e.unwrap())
+}
+
+fn custom(e: ProfitGuardError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(e as u32))
+}
+
+#[tokio::test]
+async fn passes_at_or_above_minimum() {
+    assert_eq!(assert_balance(token_account(1_000), 1_000).await, Ok(()));
+    assert_eq!(assert_balance(token_account(1_001), 1_000).await, Ok(()));
+}
+
+#[tokio::test]
+async fn fails_below_minimum() {
+    assert_eq!(
+        assert_balance(token_account(999), 1_000).await,
+        Err(custom(ProfitGuardError::BelowMinimum))
+    );
+}
+
+#[tokio::test]
+async fn rejects_accounts_not_owned_by_the_token_program() {
+    let mut forged = token_account(u64::MAX);
+    forged.owner = Pubkey::new_unique();
+    assert_eq!(
+        assert_balance(forged, 1).await,
+        Err(custom(ProfitGuardError::InvalidTokenAccount))
+    );
+}
+
+#[tokio::test]
+async fn rejects_uninitialized_token_accounts() {
+    let mut empty = token_account(0);
+    empty.data = vec![0; TokenAccount::LEN];
+    assert_eq!(
+        assert_balance(empty, 0).await,
+        Err(custom(ProfitGuardError::InvalidTokenAccount))
+    );
+}
@@ -0,0 +1,146 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::{fmt, str::FromStr};
+
+use crate::error::ArbError;
+
+/// A non-negative decimal amount as typed by the user (e.g. `0.00001`), kept exact until
+/// it is scaled to a mint's raw units. `f64` is only produced for display.
+///
+/// Stored as `digits / 10^scale` with trailing fractional zeros removed, so equal amounts
+/// compare equal however they were written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecimalAmount {
+    digits: u128,
+    scale: u8,
+}
+
+impl DecimalAmount {
+    fn normalized(mut digits: u128, mut scale: u8) -> Self {
+        while scale > 0 && digits % 10 == 0 {
+            digits /= 10;
+            scale -= 1;
+        }
+        if digits == 0 {
+            scale = 0;
+        }
+        Self { digits, scale }
+    }
+
+    /// The amount `raw / 10^decimals`, e.g. for showing a raw balance.
+    pub fn from_raw(raw: u64, decimals: u8) -> Self {
+        Self::normalized(raw as u128, decimals)
+    }
+
+    /// Raw units of a mint with `decimals`. Fails when the amount has more decimal places
+    /// than the mint (it would have to be rounded) or does not fit in a u64.
+    pub fn to_raw(&self, decimals: u8) -> Result<u64, ArbError> {
+        if self.digits == 0 {
+            return Ok(0);
+        }
+        if self.scale > decimals {
+            return Err(ArbError::Config(format!(
+                "amount {self} has more decimal places than the mint's {decimals}"
+            )));
+        }
+        10u128
+            .checked_pow((decimals - self.scale) as u32)
+            .and_then(|factor| self.digits.checked_mul(factor))
+            .and_then(|raw| u64::try_from(raw).ok())
+            .ok_or_else(|| {
+                ArbError::math(format!(
+                    "amount {self} with {decimals} decimals does not fit in u64"
+                ))
+            })
+    }
+
+    /// Approximate value, for logs and reports only.
+    pub fn to_f64(&self) -> f64 {
+        self.digits as f64 / 10f64.powi(self.scale as i32)
+    }
+}
+
+impl FromStr for DecimalAmount {
+    type Err = ArbError;
+
+    /// Plain decimal notation only: digits with an optional `.`; no sign or exponent.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |why: &str| ArbError::Config(format!("invalid amount `{s}`: {why}"));
+        let (int, frac) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        if int.is_empty() && frac.is_empty() {
+            return Err(invalid("expected a decimal number such as 0.5"));
+        }
+        if !int.chars().chain(frac.chars()).all(|c| c.is_ascii_digit()) {
+            return Err(invalid(
+                "expected only digits and one `.` (amounts are >= 0)",
+            ));
+        }
+        // Trailing fractional zeros carry no precision
+        let frac = frac.trim_end_matches('0');
+        let scale = u8::try_from(frac.len()).map_err(|_| invalid("too many decimal places"))?;
+        let digits = int
+            .chars()
+            .chain(frac.chars())
+            .try_fold(0u128, |acc, c| {
+                acc.checked_mul(10)?
+                    .checked_add(c.to_digit(10).unwrap() as u128)
+            })
+            .ok_or_else(|| invalid("too many digits"))?;
+        Ok(Self::normalized(digits, scale))
+    }
+}
+
+impl fmt::Display for DecimalAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.digits.to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+        let padded = format!("{digits:0>width$}", width = scale + 1);
+        let (int, frac) = padded.split_at(padded.len() - scale);
+        write!(f, "{int}.{frac}")
+    }
+}
+
+// Written as a string so no precision is lost in state files and reports
+impl Serialize for DecimalAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+// Also accepts numbers, as written by older state files and TOML configs
+impl<'de> Deserialize<'de> for DecimalAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl de::Visitor<'_> for Visitor {
+            type Value = DecimalAmount;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a non-negative decimal amount")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(DecimalAmount::normalized(v as u128, 0))
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+                u64::try_from(v)
+                    .map(|v| DecimalAmount::normalized(v as u128, 0))
+                    .map_err(|_| E::custom(format!("amount must be >= 0, got {v}")))
+            }
+
+            // Rust prints the shortest decimal that round-trips, i.e. what was typed
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+                v.to_string().parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
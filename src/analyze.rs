@@ -0,0 +1,332 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use std::fmt::Write as _;
+
+use crate::{
+    amount::DecimalAmount,
+    arbitrage::{FeeConversion, calculate_pnl, calculate_swap_output_raw},
+    pool::{FEE_RATE_DENOMINATOR, PoolValues},
+};
+
+/// Price impact levels of the depth table, in bps: 0.1%, 0.5%, 1% and 2%.
+pub const IMPACT_LEVELS_BPS: &[u32] = &[10, 50, 100, 200];
+
+// Default size ladder as fractions of `amount_in`: 0.1x .. 100x
+const LADDER_MULTIPLES: &[(u64, u64)] = &[
+    (1, 10),
+    (1, 2),
+    (1, 1),
+    (2, 1),
+    (5, 1),
+    (10, 1),
+    (50, 1),
+    (100, 1),
+];
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AnalyzeFormat {
+    /// Aligned tables
+    #[default]
+    Table,
+    /// One CSV with a `section` column (impact, ladder, round_trip)
+    Csv,
+}
+
+/// One quoted swap. `impact_bps` is how far the average price falls short of the pool's
+/// mid price after fees, so it only measures depth, not the fee itself.
+pub struct Fill {
+    pub amount_in_raw: u64,
+    pub amount_out_raw: u64,
+    pub impact_bps: f64,
+}
+
+/// Largest input whose impact stays within `level_bps`.
+pub struct ImpactLimit {
+    pub level_bps: u32,
+    pub fill: Fill,
+}
+
+/// Depth of one pool in one direction. The ladder is only quoted from mint_in.
+pub struct SideDepth {
+    pub direction: String, // "mint_in→mint_out" or "mint_out→mint_in"
+    pub limits: Vec<ImpactLimit>,
+    pub ladder: Vec<Fill>,
+}
+
+pub struct PoolDepth {
+    pub label: String,
+    pub pool: String,
+    pub sides: Vec<SideDepth>,
+}
+
+pub struct RoundTripPoint {
+    pub amount_in_raw: u64,
+    pub amount_out_1_raw: u64,
+    pub amount_out_2_raw: u64,
+    pub gross_profit_raw: i64,
+    pub pnl_raw: Option<i64>, // unknown without a fee price in mint_in
+}
+
+pub struct RoundTrip {
+    pub direction: String, // "A→B" or "B→A"
+    pub points: Vec<RoundTripPoint>,
+}
+
+pub struct Analysis {
+    pub pools: Vec<PoolDepth>,
+    pub round_trips: Vec<RoundTrip>,
+}
+
+/// `amount_in` scaled by the default multiples, skipping duplicates and zero.
+pub fn ladder_sizes(amount_in_raw: u64) -> Vec<u64> {
+    let mut sizes: Vec<u64> = LADDER_MULTIPLES
+        .iter()
+        .map(|(num, den)| {
+            (amount_in_raw as u128 * *num as u128 / *den as u128).min(u64::MAX as u128) as u64
+        })
+        .filter(|s| *s > 0)
+        .collect();
+    sizes.dedup();
+    sizes
+}
+
+// Output per unit of input for an infinitely small swap, after trade and creator fees
+fn spot_after_fees(pool: &PoolValues, zero_for_one: bool) -> f64 {
+    let (reserve_in, reserve_out) = if zero_for_one {
+        (pool.reserve0, pool.reserve1)
+    } else {
+        (pool.reserve1, pool.reserve0)
+    };
+    if reserve_in == 0 {
+        return 0.0;
+    }
+    let fee_rate = (pool.trade_fee_rate + pool.effective_creator_fee_rate()) as f64
+        / FEE_RATE_DENOMINATOR as f64;
+    reserve_out as f64 / reserve_in as f64 * (1.0 - fee_rate)
+}
+
+fn fill(pool: &PoolValues, zero_for_one: bool, amount_in_raw: u64) -> Result<Fill> {
+    let amount_out_raw = calculate_swap_output_raw(amount_in_raw, pool, zero_for_one)?;
+    let ideal = amount_in_raw as f64 * spot_after_fees(pool, zero_for_one);
+    let impact_bps = if amount_in_raw == 0 || ideal <= 0.0 {
+        0.0
+    } else {
+        ((1.0 - amount_out_raw as f64 / ideal) * 10_000.0).max(0.0)
+    };
+    Ok(Fill {
+        amount_in_raw,
+        amount_out_raw,
+        impact_bps,
+    })
+}
+
+// Impact grows with size on a constant-product curve, so bisect over the input
+fn max_within_impact(pool: &PoolValues, zero_for_one: bool, level_bps: u32) -> Result<Fill> {
+    let (mut lo, mut hi) = (0u64, u64::MAX);
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        if fill(pool, zero_for_one, mid)?.impact_bps <= level_bps as f64 {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    fill(pool, zero_for_one, lo)
+}
+
+/// Impact limits in both directions and the mint_in ladder of a pool normalized so that
+/// token0 == mint_in.
+pub fn pool_depth(
+    label: &str,
+    pool_id: &str,
+    pool: &PoolValues,
+    sizes: &[u64],
+) -> Result<PoolDepth> {
+    let side = |zero_for_one: bool, direction: &str, sizes: &[u64]| -> Result<SideDepth> {
+        Ok(SideDepth {
+            direction: direction.to_string(),
+            limits: IMPACT_LEVELS_BPS
+                .iter()
+                .map(|level| {
+                    Ok(ImpactLimit {
+                        level_bps: *level,
+                        fill: max_within_impact(pool, zero_for_one, *level)?,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            ladder: sizes
+                .iter()
+                .map(|s| fill(pool, zero_for_one, *s))
+                .collect::<Result<_>>()?,
+        })
+    };
+    Ok(PoolDepth {
+        label: label.to_string(),
+        pool: pool_id.to_string(),
+        sides: vec![
+            side(true, "mint_in→mint_out", sizes)?,
+            side(false, "mint_out→mint_in", &[])?,
+        ],
+    })
+}
+
+/// Round-trip PnL for every size, buying on `pool_in` and selling on `pool_out`. Fees cover
+/// the priority fee only: ATA rent depends on the wallet and is left out.
+pub fn round_trip(
+    direction: &str,
+    pool_in: &PoolValues,
+    pool_out: &PoolValues,
+    sizes: &[u64],
+    priority_fee: u64,
+    reference: Option<&FeeConversion>,
+) -> Result<RoundTrip> {
+    let points = sizes
+        .iter()
+        .map(|raw| {
+            let amount = DecimalAmount::from_raw(*raw, pool_in.token0_decimals);
+            let arb = calculate_pnl(&amount, pool_in, pool_out, 0, priority_fee, reference)?;
+            Ok(RoundTripPoint {
+                amount_in_raw: arb.amount_in_raw,
+                amount_out_1_raw: arb.amount_out_1_raw,
+                amount_out_2_raw: arb.amount_out_2_raw,
+                gross_profit_raw: arb.gross_profit_raw,
+                pnl_raw: arb.pnl_raw,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(RoundTrip {
+        direction: direction.to_string(),
+        points,
+    })
+}
+
+fn opt(v: Option<i64>) -> String {
+    v.map_or("-".to_string(), |v| v.to_string())
+}
+
+impl Analysis {
+    pub fn render(&self, format: AnalyzeFormat) -> String {
+        match format {
+            AnalyzeFormat::Table => self.table(),
+            AnalyzeFormat::Csv => self.csv(),
+        }
+    }
+
+    fn table(&self) -> String {
+        let mut out = String::new();
+        for pool in &self.pools {
+            for side in &pool.sides {
+                let _ = writeln!(
+                    out,
+                    "Pool {} {} ({})",
+                    pool.label, pool.pool, side.direction
+                );
+                let _ = writeln!(
+                    out,
+                    "  {:>10} {:>22} {:>22} {:>12}",
+                    "impact", "max_amount_in_raw", "amount_out_raw", "impact_bps"
+                );
+                for l in &side.limits {
+                    let _ = writeln!(
+                        out,
+                        "  {:>9.1}% {:>22} {:>22} {:>12.2}",
+                        l.level_bps as f64 / 100.0,
+                        l.fill.amount_in_raw,
+                        l.fill.amount_out_raw,
+                        l.fill.impact_bps
+                    );
+                }
+                if !side.ladder.is_empty() {
+                    let _ = writeln!(
+                        out,
+                        "  {:>22} {:>22} {:>12}",
+                        "amount_in_raw", "amount_out_raw", "impact_bps"
+                    );
+                    for f in &side.ladder {
+                        let _ = writeln!(
+                            out,
+                            "  {:>22} {:>22} {:>12.2}",
+                            f.amount_in_raw, f.amount_out_raw, f.impact_bps
+                        );
+                    }
+                }
+                out.push('\n');
+            }
+        }
+        for rt in &self.round_trips {
+            let _ = writeln!(out, "Round trip {}", rt.direction);
+            let _ = writeln!(
+                out,
+                "  {:>22} {:>22} {:>22} {:>20} {:>20}",
+                "amount_in_raw",
+                "amount_out_1_raw",
+                "amount_out_2_raw",
+                "gross_profit_raw",
+                "pnl_raw"
+            );
+            for p in &rt.points {
+                let _ = writeln!(
+                    out,
+                    "  {:>22} {:>22} {:>22} {:>20} {:>20}",
+                    p.amount_in_raw,
+                    p.amount_out_1_raw,
+                    p.amount_out_2_raw,
+                    p.gross_profit_raw,
+                    opt(p.pnl_raw)
+                );
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    fn csv(&self) -> String {
+        let mut out = String::from(
+            "section,pool,direction,impact_level_bps,amount_in_raw,amount_out_raw,impact_bps,gross_profit_raw,pnl_raw\n",
+        );
+        for pool in &self.pools {
+            for side in &pool.sides {
+                for l in &side.limits {
+                    let _ = writeln!(
+                        out,
+                        "impact,{},{},{},{},{},{:.4},,",
+                        pool.pool,
+                        side.direction,
+                        l.level_bps,
+                        l.fill.amount_in_raw,
+                        l.fill.amount_out_raw,
+                        l.fill.impact_bps
+                    );
+                }
+                for f in &side.ladder {
+                    let _ = writeln!(
+                        out,
+                        "ladder,{},{},,{},{},{:.4},,",
+                        pool.pool, side.direction, f.amount_in_raw, f.amount_out_raw, f.impact_bps
+                    );
+                }
+            }
+        }
+        let pair = self
+            .pools
+            .iter()
+            .map(|p| p.pool.as_str())
+            .collect::<Vec<_>>()
+            .join("-");
+        for rt in &self.round_trips {
+            for p in &rt.points {
+                let _ = writeln!(
+                    out,
+                    "round_trip,{},{},,{},{},,{},{}",
+                    pair,
+                    rt.direction,
+                    p.amount_in_raw,
+                    p.amount_out_2_raw,
+                    p.gross_profit_raw,
+                    p.pnl_raw.map_or(String::new(), |v| v.to_string())
+                );
+            }
+        }
+        out
+    }
+}
@@ -1,15 +1,76 @@
+use anyhow::Result;
 use solana_sdk::pubkey::Pubkey;
 
-use crate::pool::PoolValues;
+use crate::{
+    amount::DecimalAmount,
+    error::ArbError,
+    policy::{Rule, Signals},
+    pool::PoolValues,
+    twap::TwapBaseline,
+};
 
 const ESTIMATED_COMPUTE_UNITS: u64 = 100_000;
 const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
 const MICRO_LAMPORTS_PER_LAMPORTS: u64 = 1_000_000;
 pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
 const UNITS_PER_TRADE_FEE_RATE: u128 = 1_000_000;
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Outcome of a single swap leg with every fee component charged by the CPMM program.
+/// `trade_fee`, `protocol_fee` and `fund_fee` are in the input token; `creator_fee` is in
+/// the input token when `creator_fee_on_input` is set, otherwise in the output token.
+/// `protocol_fee` and `fund_fee` are carved out of `trade_fee`, not charged on top of it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub trade_fee: u64,
+    pub protocol_fee: u64,
+    pub fund_fee: u64,
+    pub creator_fee: u64,
+    pub creator_fee_on_input: bool,
+}
+
+/// Rate used to express SOL-denominated costs (priority fee, rent) in raw `mint_in` units:
+/// `mint_in_raw = lamports * mint_in_per / lamports_per`.
+#[derive(Clone, Debug)]
+pub struct FeeConversion {
+    pub mint_in_per: u128,
+    pub lamports_per: u128,
+    pub source: String, // "sol", "pool_mid" or "reference_pool:<address>"
+}
+
+impl FeeConversion {
+    pub fn identity() -> Self {
+        Self {
+            mint_in_per: 1,
+            lamports_per: 1,
+            source: "sol".to_string(),
+        }
+    }
+
+    /// Mid price of a pool normalized so that token0 == mint_in and token1 == SOL.
+    pub fn from_mid(values: &PoolValues, source: &str) -> Option<Self> {
+        let sol_mint = SOL_MINT.parse::<Pubkey>().unwrap();
+        if values.mint1 != sol_mint || values.reserve1 == 0 {
+            return None;
+        }
+        Some(Self {
+            mint_in_per: values.reserve0 as u128,
+            lamports_per: values.reserve1 as u128,
+            source: source.to_string(),
+        })
+    }
+
+    // Rounded up so that converted costs are never understated
+    pub fn to_mint_in_raw(&self, lamports: u64) -> u64 {
+        let v = (lamports as u128 * self.mint_in_per).div_ceil(self.lamports_per);
+        v.min(u64::MAX as u128) as u64
+    }
+}
 
 pub struct Arbitrage {
-    pub amount_in: f64,
+    pub amount_in: f64, // display only; every computation uses the raw amounts
     pub amount_in_raw: u64,
     pub amount_out_1: f64,
     pub amount_out_1_raw: u64,
@@ -22,34 +83,51 @@ pub struct Arbitrage {
     pub rent: f64,
     pub rent_raw: u64,
     pub pnl: Option<f64>,
+    pub pnl_raw: Option<i64>,                // in mint_in raw units
+    pub total_fees_in_mint_raw: Option<u64>, // total_fees_raw converted into mint_in
+    pub fee_conversion: Option<FeeConversion>,
+    pub leg_1: SwapQuote,
+    pub leg_2: SwapQuote,
+}
+
+fn to_i64(v: i128, what: &str) -> Result<i64, ArbError> {
+    i64::try_from(v).map_err(|_| ArbError::math(format!("{what} {v} does not fit in i64")))
+}
+
+/// Lamports spent by one arbitrage transaction: rent for new ATAs plus the priority fee.
+pub fn estimated_fees_raw(rent_raw: u64, priority_fee: u64) -> Result<u64, ArbError> {
+    (priority_fee as u128 * ESTIMATED_COMPUTE_UNITS as u128 / MICRO_LAMPORTS_PER_LAMPORTS as u128)
+        .try_into()
+        .ok()
+        .and_then(|fee: u64| fee.checked_add(rent_raw))
+        .ok_or_else(|| {
+            ArbError::math(format!(
+                "fees overflow: rent {rent_raw} + priority fee {priority_fee} µlamports/CU"
+            ))
+        })
 }
 
 pub fn calculate_pnl(
-    amount_in: f64,
+    amount_in: &DecimalAmount,
     pool_in: &PoolValues,
     pool_out: &PoolValues,
     rent_raw: u64,
     priority_fee: u64,
-) -> Arbitrage {
-    let total_fees_raw =
-        rent_raw + priority_fee * ESTIMATED_COMPUTE_UNITS / MICRO_LAMPORTS_PER_LAMPORTS;
-    let amount_in_raw = (amount_in * 10_f64.powi(pool_in.token0_decimals as i32)) as u64;
-
-    let amount_out_raw_1 = calculate_swap_output_raw(
-        amount_in_raw,
-        pool_in.reserve0,
-        pool_in.reserve1,
-        pool_in.trade_fee_rate,
-    );
+    reference: Option<&FeeConversion>,
+) -> Result<Arbitrage> {
+    let total_fees_raw = estimated_fees_raw(rent_raw, priority_fee)?;
+    let amount_in_raw = amount_in.to_raw(pool_in.token0_decimals)?;
 
-    let amount_out_raw_2 = calculate_swap_output_raw(
-        amount_out_raw_1,
-        pool_out.reserve1,
-        pool_out.reserve0,
-        pool_out.trade_fee_rate,
-    );
+    // Both pools are normalized so that token0 == mint_in
+    let leg_1 = quote_swap(amount_in_raw, pool_in, true)?;
+    let leg_2 = quote_swap(leg_1.amount_out, pool_out, false)?;
+    let amount_out_raw_1 = leg_1.amount_out;
+    let amount_out_raw_2 = leg_2.amount_out;
 
-    let gross_profit_raw = (amount_out_raw_2 as i128 - amount_in_raw as i128) as i64;
+    let gross_profit_raw = to_i64(
+        amount_out_raw_2 as i128 - amount_in_raw as i128,
+        "gross profit",
+    )?;
 
     let total_fees = total_fees_raw as f64 / LAMPORTS_PER_SOL as f64;
     let amount_out_1 = amount_out_raw_1 as f64 / 10_f64.powi(pool_in.token1_decimals as i32);
@@ -57,15 +135,25 @@ pub fn calculate_pnl(
     let gross_profit = gross_profit_raw as f64 / 10_f64.powi(pool_out.token0_decimals as i32);
     let rent = rent_raw as f64 / LAMPORTS_PER_SOL as f64;
 
-    let mut pnl = None;
-
+    // Fees are paid in SOL; express them in mint_in via the entry pool when it quotes
+    // mint_in against SOL, otherwise via the configured reference pool.
     let sol_mint = SOL_MINT.parse::<Pubkey>().unwrap();
-    if pool_out.mint0 == sol_mint {
-        pnl = Some(gross_profit - total_fees);
-    }
+    let fee_conversion = if pool_in.mint0 == sol_mint {
+        Some(FeeConversion::identity())
+    } else {
+        FeeConversion::from_mid(pool_in, "pool_mid").or_else(|| reference.cloned())
+    };
 
-    Arbitrage {
-        amount_in,
+    let total_fees_in_mint_raw = fee_conversion
+        .as_ref()
+        .map(|c| c.to_mint_in_raw(total_fees_raw));
+    let pnl_raw = total_fees_in_mint_raw
+        .map(|fees| to_i64(gross_profit_raw as i128 - fees as i128, "pnl"))
+        .transpose()?;
+    let pnl = pnl_raw.map(|p| p as f64 / 10_f64.powi(pool_out.token0_decimals as i32));
+
+    Ok(Arbitrage {
+        amount_in: amount_in.to_f64(),
         amount_in_raw,
         amount_out_1,
         amount_out_1_raw: amount_out_raw_1,
@@ -78,24 +166,108 @@ pub fn calculate_pnl(
         rent,
         rent_raw,
         pnl,
+        pnl_raw,
+        total_fees_in_mint_raw,
+        fee_conversion,
+        leg_1,
+        leg_2,
+    })
+}
+
+// Raydium `Fees::trading_fee` / `Fees::creator_fee` round up.
+// `amount` never exceeds u64::MAX, so `amount * rate` fits in u128.
+fn ceil_fee(amount: u128, rate: u64) -> u128 {
+    (amount * rate as u128).div_ceil(UNITS_PER_TRADE_FEE_RATE)
+}
+
+fn to_u64(v: u128, what: &str) -> Result<u64, ArbError> {
+    u64::try_from(v).map_err(|_| ArbError::math(format!("{what} {v} does not fit in u64")))
+}
+
+// The program fails the swap when a fee exceeds the amount it is charged on
+fn deduct_fee(amount: u128, fee: u128, what: &str) -> Result<u128, ArbError> {
+    amount
+        .checked_sub(fee)
+        .ok_or_else(|| ArbError::math(format!("fee {fee} exceeds {what} {amount}")))
+}
+
+// Raydium `Fees::protocol_fee` / `Fees::fund_fee` round down
+fn floor_fee(amount: u128, rate: u64) -> u128 {
+    amount * rate as u128 / UNITS_PER_TRADE_FEE_RATE
+}
+
+// Raydium `Fees::split_creator_fee`: the creator's share of a fee charged at the combined
+// trade + creator rate, rounded down
+fn split_creator_fee(total_fee: u128, trade_fee_rate: u64, creator_fee_rate: u64) -> u128 {
+    let combined = trade_fee_rate as u128 + creator_fee_rate as u128;
+    if combined == 0 {
+        return 0;
     }
+    total_fee * creator_fee_rate as u128 / combined
 }
 
-// Raw token calculation using exact Raydium math
-pub fn calculate_swap_output_raw(
+// Raw token calculation using exact Raydium math (`CurveCalculator::swap_base_input`)
+pub fn quote_swap(
     amount_in: u64,
-    reserve_in: u64,
-    reserve_out: u64,
-    trade_fee_rate: u64,
-) -> u64 {
-    let fees: u128 = (amount_in as u128) * (trade_fee_rate as u128) / UNITS_PER_TRADE_FEE_RATE;
-    let net_in: u128 = (amount_in as u128) - fees;
-
-    let numerator = net_in * (reserve_out as u128);
+    pool: &PoolValues,
+    zero_for_one: bool,
+) -> Result<SwapQuote, ArbError> {
+    let (reserve_in, reserve_out) = if zero_for_one {
+        (pool.reserve0, pool.reserve1)
+    } else {
+        (pool.reserve1, pool.reserve0)
+    };
+    let creator_fee_rate = pool.effective_creator_fee_rate();
+    let creator_fee_on_input = pool.is_creator_fee_on_input(zero_for_one);
+
+    let input = amount_in as u128;
+    let mut creator_fee = 0;
+    let (trade_fee, net_in) = if creator_fee_on_input {
+        // One fee at the combined rate, rounded up once, then split
+        let total_fee = ceil_fee(input, pool.trade_fee_rate.saturating_add(creator_fee_rate));
+        creator_fee = split_creator_fee(total_fee, pool.trade_fee_rate, creator_fee_rate);
+        (
+            total_fee - creator_fee,
+            deduct_fee(input, total_fee, "amount_in")?,
+        )
+    } else {
+        let trade_fee = ceil_fee(input, pool.trade_fee_rate);
+        (trade_fee, deduct_fee(input, trade_fee, "amount_in")?)
+    };
+    let protocol_fee = floor_fee(trade_fee, pool.protocol_fee_rate);
+    let fund_fee = floor_fee(trade_fee, pool.fund_fee_rate);
+
     let denominator = (reserve_in as u128) + net_in;
-    let amount_out = numerator / denominator;
+    let swapped = if denominator == 0 {
+        0
+    } else {
+        net_in * (reserve_out as u128) / denominator
+    };
+
+    let amount_out = if creator_fee_on_input {
+        swapped
+    } else {
+        creator_fee = ceil_fee(swapped, creator_fee_rate);
+        deduct_fee(swapped, creator_fee, "amount_out")?
+    };
+
+    Ok(SwapQuote {
+        amount_in,
+        amount_out: to_u64(amount_out, "amount_out")?,
+        trade_fee: to_u64(trade_fee, "trade_fee")?,
+        protocol_fee: to_u64(protocol_fee, "protocol_fee")?,
+        fund_fee: to_u64(fund_fee, "fund_fee")?,
+        creator_fee: to_u64(creator_fee, "creator_fee")?,
+        creator_fee_on_input,
+    })
+}
 
-    amount_out as u64
+pub fn calculate_swap_output_raw(
+    amount_in: u64,
+    pool: &PoolValues,
+    zero_for_one: bool,
+) -> Result<u64, ArbError> {
+    Ok(quote_swap(amount_in, pool, zero_for_one)?.amount_out)
 }
 
 pub fn calculate_price(reserve0: u64, reserve1: u64, decimals0: u8, decimals1: u8) -> f64 {
@@ -117,7 +289,61 @@ pub fn spread_bps(price_a: f64, price_b: f64) -> f64 {
     spread * 10000.0
 }
 
+/// Worth trading: a known, positive expected PnL of at least `min_pnl_raw` (mint_in units).
+pub fn clears_pnl_floor(arb: &Arbitrage, min_pnl_raw: u64) -> bool {
+    arb.pnl_raw
+        .is_some_and(|p| p > 0 && p as i128 >= min_pnl_raw as i128)
+}
+
+/// Mid price of a pool normalized as mint_in -> mint_out (token0 -> token1).
+pub fn mid_price(v: &PoolValues) -> f64 {
+    calculate_price(v.reserve0, v.reserve1, v.token0_decimals, v.token1_decimals)
+}
+
+/// What a quote taken right before signing (the final quote and every re-quote) is held to.
+pub struct QuoteGuard<'a> {
+    pub min_pnl_raw: u64,
+    pub policy: &'a Rule,
+    /// TWAPs of the chosen direction's pools, when the TWAP guard is on
+    pub twap: Option<&'a TwapBaseline>,
+}
+
+impl QuoteGuard<'_> {
+    /// Why `arb`, quoted on freshly read `first` and `second` pools, must not be traded:
+    /// expected PnL below `min_pnl_raw`, the decision policy not met, or fresh spot prices
+    /// too far from their TWAPs. `None` while it is still worth trading.
+    pub fn violation(
+        &self,
+        arb: &Arbitrage,
+        first: &PoolValues,
+        second: &PoolValues,
+    ) -> Option<String> {
+        if !clears_pnl_floor(arb, self.min_pnl_raw) {
+            return Some(format!(
+                "expected pnl(raw) {:?} below min_pnl_raw {}",
+                arb.pnl_raw, self.min_pnl_raw
+            ));
+        }
+        let (mid_first, mid_second) = (mid_price(first), mid_price(second));
+        let policy = self
+            .policy
+            .evaluate(&Signals::new(arb, spread_bps(mid_first, mid_second)));
+        if !policy.passed {
+            return Some(format!(
+                "decision policy not met: {}",
+                policy.failures().join("; ")
+            ));
+        }
+        let report = self.twap?.check(mid_first, mid_second);
+        report
+            .blocked
+            .then(|| format!("spot deviates from TWAP: {}", report.breaches().join("; ")))
+    }
+}
+
+/// `amount_out` less `slippage_bps`, rounded down; 0 from 10_000 bps up.
 pub fn calculate_min_out(amount_out: u64, slippage_bps: u32) -> u64 {
-    let slippage_factor = 1.0 - (slippage_bps as f64 / 10000.0);
-    (amount_out as f64 * slippage_factor) as u64
+    let keep_bps = BPS_DENOMINATOR.saturating_sub(slippage_bps as u128);
+    // amount_out * keep_bps / 10_000 <= amount_out, so the result fits in u64
+    (amount_out as u128 * keep_bps / BPS_DENOMINATOR) as u64
 }
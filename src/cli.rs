@@ -1,65 +1,127 @@
 use anyhow::{Context, Result, bail};
 use carbon_raydium_cpmm_decoder::RaydiumCpmmDecoder;
-use clap::{Parser, Subcommand};
-use dialoguer::{Confirm, Input, Select};
+use clap::{Parser, Subcommand, ValueEnum};
+use dialoguer::{Confirm, Input, MultiSelect, Select};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
 use shellexpand;
 use solana_client::rpc_client::RpcClient;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::{Signer, keypair::Keypair},
+    transaction::Transaction,
+};
 use std::{
     fs,
     io::Write,
     path::{Path, PathBuf},
 };
 
-use crate::{pool::PoolData, utils::load_keypair};
+use crate::{
+    amount::DecimalAmount,
+    analyze::AnalyzeFormat,
+    arbitrage::SOL_MINT,
+    endpoints::{
+        DEFAULT_HEALTH_INTERVAL_MS, DEFAULT_MAX_SLOT_LAG, EndpointRole, Endpoints, RpcEndpoint,
+    },
+    error::ArbError,
+    keystore,
+    migrate::{STATE_SCHEMA_VERSION, backup_path, parse_state, to_stored_json},
+    nonce::{DurableNonce, create_nonce_instructions, nonce_rent},
+    notify::{EventKind, Notifier, NotifierConfig, SinkConfig},
+    policy::Rule,
+    pool::PoolData,
+    profile::{
+        DEFAULT_PROFILE, active_profile, dependents, list_profiles, load_base, profile_state_path,
+        resolve_inheritance, set_active_profile, strip_inherited,
+    },
+    report::OutputFormat,
+    sender::{DEFAULT_MAX_REQUOTES, DEFAULT_REBROADCAST_MS, SendOptions, Sender},
+    settings::PROFILE_ENV,
+    signer::SignerSource,
+    transaction::{
+        BuiltTransaction, decode_transaction, encode_transaction, missing_signers, sign_missing,
+    },
+    twap::TwapGuard,
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct AppState {
+    // Profile whose values fill every field left unset here
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+
     // Pools & mints
     pub pool_a: Option<String>,
     pub pool_b: Option<String>,
     pub mint_in: Option<String>, // e.g., So11111111111111111111111111111111111111112
     pub mint_out: Option<String>,
+    // CPMM pool pairing mint_in with SOL, used to price SOL fees when neither pool does
+    #[serde(default)]
+    pub reference_pool: Option<String>,
 
     // Trading params
-    pub amount_in: Option<f64>, // decimal units of chosen mint
+    pub amount_in: Option<DecimalAmount>, // decimal units of chosen mint, kept exact
     pub spread_threshold_bps: Option<u32>,
     pub slippage_bps: Option<u32>,
+    // Smallest expected PnL (raw mint_in units) worth sending, checked again right before signing
+    #[serde(default)]
+    pub min_pnl_raw: Option<u64>,
+    // Rule tree deciding whether a quote is traded; unset: mid spread >= spread_threshold_bps
+    #[serde(default)]
+    pub decision_policy: Option<Rule>,
+    // Spot vs observation-account TWAP limit per pool; unset: not checked
+    #[serde(default)]
+    pub twap_guard: Option<TwapGuard>,
     pub priority_fee_microlamports: Option<u64>,
     pub simulate_only: Option<bool>,
 
     // Infra
     pub rpc_url: Option<String>,
-    pub keypair_path: Option<PathBuf>,
+    // Extra endpoints with roles (read, send, websocket); rpc_url always reads and sends
+    #[serde(default)]
+    pub rpc_endpoints: Vec<RpcEndpoint>,
+    pub keypair_path: Option<PathBuf>, // trading authority: owns the token accounts
+    // Signer paying network and priority fees; the keypair itself when unset
+    #[serde(default)]
+    pub fee_payer: Option<PathBuf>,
+    // Durable nonce account (authority: the fee payer or keypair) replacing the blockhash
+    #[serde(default)]
+    pub nonce_account: Option<String>,
+    // Deployed profit-guard program; when set every arbitrage ends with a balance assertion
+    #[serde(default)]
+    pub profit_guard_program: Option<String>,
+
+    // Alerts
+    #[serde(default)]
+    pub notifiers: Vec<NotifierConfig>,
 }
 
 // ======== Programmer-editable defaults (initial install state) ========
 pub fn default_state() -> AppState {
     // Edit to your desired shipped defaults
     AppState {
+        base: None,
         pool_a: Some("4jgpwmuwaUrZgTvUjio8aBVNQJ6HcsF3YKAekpwwxTou".to_string()),
         pool_b: Some("7JuwJuNU88gurFnyWeiyGKbFmExMWcmRZntn9imEzdny".to_string()),
         mint_in: Some("So11111111111111111111111111111111111111112".to_string()),
         mint_out: Some("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v".to_string()),
-        amount_in: Some(0.00001),
+        reference_pool: None,
+        amount_in: Some("0.00001".parse().unwrap()),
         spread_threshold_bps: Some(100),
         slippage_bps: Some(500),
+        min_pnl_raw: Some(0),
+        decision_policy: None,
+        twap_guard: None,
         priority_fee_microlamports: Some(100000),
         simulate_only: Some(true),
         rpc_url: Some("https://api.mainnet-beta.solana.com".to_string()),
-        keypair_path: Some("/home/coolman/solana-amm-arb-cli/keypair.json".into()),
-    }
-}
-
-pub fn parse_non_negative_f64(s: &str) -> Result<f64, String> {
-    let v: f64 = s
-        .parse()
-        .map_err(|e: std::num::ParseFloatError| e.to_string())?;
-    if v >= 0.0 {
-        Ok(v)
-    } else {
-        Err("amount-in must be >= 0".into())
+        rpc_endpoints: Vec::new(),
+        keypair_path: Some("~/.config/solana/id.json".into()),
+        fee_payer: None,
+        nonce_account: None,
+        profit_guard_program: None,
+        notifiers: Vec::new(),
     }
 }
 
@@ -72,22 +134,92 @@ pub fn parse_non_negative_f64(s: &str) -> Result<f64, String> {
     about = "Stateful Solana CPMM arbitrage CLI (skeleton)"
 )]
 pub struct Cli {
+    /// Configuration profile to use (default: the one selected with `config profile use`)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// TOML file layered over the profile state (also `SOLANA_ARB_CONFIG_FILE`)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config_file: Option<PathBuf>,
+
     // Runtime flags (no subcommand) — main path prints ONLY requested params
     #[arg(long)]
     pub rpc_url: Option<String>,
+    /// Extra RPC endpoint as `[ROLES=]URL`, roles `read`, `send`, `websocket`
+    /// (default `read,send`); repeatable
+    #[arg(long = "rpc-endpoint", value_name = "[ROLES=]URL")]
+    pub rpc_endpoints: Vec<RpcEndpoint>,
+    /// Also ask the next read endpoint when one has not answered within N milliseconds
+    #[arg(long, value_name = "MS")]
+    pub hedge_after_ms: Option<u64>,
+    /// Skip endpoints more than N slots behind the most advanced one
+    #[arg(long, value_name = "SLOTS", default_value_t = DEFAULT_MAX_SLOT_LAG)]
+    pub max_slot_lag: u64,
+    /// Health-check every endpoint this often (watch mode)
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_HEALTH_INTERVAL_MS)]
+    pub health_interval_ms: u64,
     #[arg(long)]
     pub keypair: Option<PathBuf>,
-    #[arg(long, value_parser = parse_non_negative_f64)]
-    pub amount_in: Option<f64>,
+    /// Signer paying transaction fees (default: the keypair)
+    #[arg(long, value_name = "SOURCE")]
+    pub fee_payer: Option<PathBuf>,
+    /// Durable nonce account to build transactions with (instead of a recent blockhash)
+    #[arg(long, value_name = "PUBKEY")]
+    pub nonce_account: Option<String>,
+    /// Deployed profit-guard program: revert unless the input token account gains at
+    /// least the fees plus `min_pnl_raw`
+    #[arg(long, value_name = "PUBKEY")]
+    pub profit_guard_program: Option<String>,
+    /// Build and sign with the available signers, print the base64 transaction and
+    /// stop; finish it later with `sign` / `submit` (needs a nonce account)
+    #[arg(long)]
+    pub sign_only: bool,
+    /// Decimal amount of mint_in (e.g. 0.5); must not have more decimals than the mint
+    #[arg(long, value_name = "DECIMAL")]
+    pub amount_in: Option<DecimalAmount>,
     #[arg(long, value_name = "U32")]
     pub spread_threshold_bps: Option<u32>,
     #[arg(long, value_name = "U32")]
     pub slippage_bps: Option<u32>,
+    /// Smallest expected PnL, in raw mint_in units, worth trading (default 0: any profit)
+    #[arg(long, value_name = "U64")]
+    pub min_pnl_raw: Option<u64>,
+    /// Rule tree as JSON, e.g. '{"any":[{"pnl_raw":5000},{"mid_spread_bps":100}]}'
+    /// (default: mid spread >= spread-threshold-bps)
+    #[arg(long, value_name = "JSON")]
+    pub decision_policy: Option<Rule>,
+    /// Spot vs TWAP limit as JSON, e.g. '{"window_secs":300,"max_deviation_bps":150}'
+    /// (action "block" by default, or "flag")
+    #[arg(long, value_name = "JSON")]
+    pub twap_guard: Option<TwapGuard>,
     /// Priority fee in MICRO-lamports (1_000 µlamports = 1 lamport)
     #[arg(long, value_name = "U64")]
     pub priority_fee: Option<u64>,
     #[arg(long, value_name = "BOOL")]
     pub simulate_only: Option<bool>,
+    /// CPMM pool (mint_in/SOL) used to convert SOL fees into mint_in
+    #[arg(long)]
+    pub reference_pool: Option<String>,
+    /// Skip the RPC node's preflight simulation on the first broadcast
+    #[arg(long)]
+    pub skip_preflight: bool,
+    /// Rebroadcast an unconfirmed transaction every N milliseconds until it lands or expires
+    #[arg(long, value_name = "MS", default_value_t = DEFAULT_REBROADCAST_MS)]
+    pub rebroadcast_interval_ms: u64,
+    /// Re-quote and re-sign at most N times when the blockhash expires unconfirmed
+    #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_REQUOTES)]
+    pub max_requotes: u32,
+    /// Keep running: re-evaluate the pools every N milliseconds
+    #[arg(long, value_name = "MS")]
+    pub watch_interval_ms: Option<u64>,
+    /// Serve Prometheus metrics on this address (e.g. 127.0.0.1:9100), at `/metrics`
+    #[arg(long, value_name = "ADDR")]
+    pub metrics_addr: Option<String>,
+    /// What to print on stdout after each evaluation
+    #[arg(long, value_enum, default_value_t = OutputFormat::None)]
+    pub output: OutputFormat,
+    /// Where to write the full JSON report of the last evaluation
+    #[arg(long, value_name = "PATH", default_value = "arbitrage_result.json")]
+    pub report_path: PathBuf,
 
     #[command(subcommand)]
     pub cmd: Option<Command>,
@@ -100,12 +232,83 @@ pub enum Command {
         #[command(subcommand)]
         cmd: ConfigCmd,
     },
+    /// Show past evaluations, decisions and sends with aggregated PnL
+    History {
+        /// Only entries at or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+        /// Only entries before this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        until: Option<String>,
+        /// Only entries involving this pool (as PoolA or PoolB)
+        #[arg(long)]
+        pool: Option<String>,
+        /// Only entries with this outcome (skipped, simulated, simulation_failed, sent, send_failed,
+        /// signed, aborted)
+        #[arg(long)]
+        outcome: Option<String>,
+        /// Show at most this many of the most recent entries
+        #[arg(long, default_value_t = 50)]
+        limit: usize,
+        /// Print matching entries as JSON lines instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the JSON Schema of the run report (`--output json|jsonl`, `--report-path`)
+    ReportSchema,
+    /// Depth of both pools (max size per price impact level, size ladder) and the
+    /// round-trip PnL curve in each direction
+    Analyze {
+        /// Sizes of mint_in to quote, comma-separated (default: 0.1x to 100x amount-in)
+        #[arg(long, value_name = "DECIMAL", value_delimiter = ',')]
+        sizes: Vec<DecimalAmount>,
+        #[arg(long, value_enum, default_value_t = AnalyzeFormat::Table)]
+        format: AnalyzeFormat,
+    },
+    /// Live dashboard: both pools, spread history, both candidates, the decision and recent
+    /// sends; keys pause, toggle simulate-only and adjust thresholds
+    Tui {
+        /// Re-evaluate the pools every N milliseconds
+        #[arg(long, value_name = "MS", default_value_t = 2000)]
+        refresh_ms: u64,
+    },
+    /// Create, import and export signer keypairs (encrypted keystores)
+    Keypair {
+        #[command(subcommand)]
+        cmd: KeypairCmd,
+    },
+    /// Durable nonce accounts for delayed or offline signing
+    Nonce {
+        #[command(subcommand)]
+        cmd: NonceCmd,
+    },
+    /// Add signatures to a base64 transaction (from `--sign-only`) and print it
+    Sign {
+        /// Base64 transaction, `@<FILE>` or `-` for stdin
+        tx: String,
+        /// Signer source to sign with (repeatable)
+        #[arg(long = "signer", value_name = "SOURCE")]
+        signers: Vec<String>,
+    },
+    /// Sign with `--signer` if still needed, then send a base64 transaction
+    Submit {
+        /// Base64 transaction, `@<FILE>` or `-` for stdin
+        tx: String,
+        /// Signer source to sign with (repeatable)
+        #[arg(long = "signer", value_name = "SOURCE")]
+        signers: Vec<String>,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 pub enum ConfigCmd {
     /// Show persisted state
-    Show,
+    Show {
+        /// Show the values a run would use and where each comes from
+        /// (default, profile, file, env or flag)
+        #[arg(long)]
+        effective: bool,
+    },
     /// Reset persisted state to programmer-defined defaults
     ResetDefaults,
     /// Interactively set pools; recompute mints; if mints changed, ask amount-in & which mint
@@ -124,6 +327,161 @@ pub enum ConfigCmd {
     SetPriorityFee,
     /// Interactively set simulate-only flag
     SetSimulate,
+    /// Interactively set the mint_in/SOL reference pool used to price fees
+    SetReferencePool,
+    /// Interactively add an alert sink (webhook, Slack, Discord or local command)
+    AddNotifier,
+    /// Remove all alert sinks
+    ClearNotifiers,
+    /// Send a test message to every configured alert sink
+    TestNotifiers,
+    /// Print one setting (resolved through the profile's base)
+    Get { key: ConfigKey },
+    /// Set one setting without prompts, e.g. `config set pools <A> <B> --mint-in <MINT>`
+    Set {
+        key: ConfigKey,
+        #[arg(required = true, num_args = 1..)]
+        values: Vec<String>,
+        /// With `pools` / `amount-in`: which of the pool mints is the input
+        #[arg(long, value_name = "MINT")]
+        mint_in: Option<String>,
+    },
+    /// Clear one setting (it falls back to the base profile, if any)
+    Unset { key: ConfigKey },
+    /// Replace the profile's state with a JSON state file (validated first)
+    Import { path: PathBuf },
+    /// Write the resolved state as JSON to a file, or stdout
+    Export { path: Option<PathBuf> },
+    /// Manage named configuration profiles
+    Profile {
+        #[command(subcommand)]
+        cmd: ProfileCmd,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigKey {
+    RpcUrl,
+    /// Extra endpoints: `[ROLES=]URL ...` (roles: read, send, websocket)
+    RpcEndpoints,
+    Keypair,
+    /// Signer source paying transaction fees (defaults to `keypair`)
+    FeePayer,
+    /// Durable nonce account address
+    NonceAccount,
+    /// Program id of the deployed profit-guard program
+    ProfitGuardProgram,
+    /// `<POOL_A> <POOL_B>`; mints are recomputed
+    Pools,
+    /// One of the two pool mints; the other becomes mint_out
+    MintIn,
+    /// Read-only: follows from `pools` and `mint-in`
+    MintOut,
+    AmountIn,
+    SpreadThresholdBps,
+    SlippageBps,
+    /// Raw mint_in units
+    MinPnlRaw,
+    /// JSON rule tree: all, any, mid_spread_bps, effective_spread_bps, pnl_raw, pnl_bps,
+    /// expected_value_raw (see README)
+    DecisionPolicy,
+    /// JSON object: window_secs, max_deviation_bps, action (block | flag)
+    TwapGuard,
+    /// Micro-lamports
+    PriorityFee,
+    SimulateOnly,
+    ReferencePool,
+    /// JSON array of notifier objects (see README)
+    Notifiers,
+    /// Base profile name
+    Base,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ProfileCmd {
+    /// Create a profile from the shipped defaults, or inheriting everything from `--base`
+    Create {
+        name: String,
+        /// Profile whose values fill every field this one leaves unset
+        #[arg(long)]
+        base: Option<String>,
+    },
+    /// List profiles (`*` marks the active one)
+    List,
+    /// Copy a profile's stored settings (including its base) under a new name
+    Copy { from: String, to: String },
+    /// Delete a profile and its history
+    Delete {
+        name: String,
+        /// Do not ask for confirmation
+        #[arg(long)]
+        yes: bool,
+    },
+    /// Make a profile the default for subsequent runs
+    Use { name: String },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum KeypairCmd {
+    /// Generate a new keypair into an encrypted keystore
+    New {
+        /// Keystore file to create
+        out: PathBuf,
+        /// Write a plaintext Solana CLI JSON byte array instead of a keystore
+        #[arg(long)]
+        plaintext: bool,
+        /// Overwrite `out` if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Encrypt an existing secret key into a keystore
+    Import {
+        /// Signer source to read: a JSON keypair file, `env:<VAR>` or `stdin`
+        /// (JSON byte array or base58)
+        #[arg(long, default_value = "stdin")]
+        from: String,
+        /// Keystore file to create
+        out: PathBuf,
+        /// Overwrite `out` if it exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Decrypt a signer source and print (or write) its plaintext secret key
+    Export {
+        /// Signer source, e.g. `keystore:~/arb.keystore`
+        from: String,
+        #[arg(long, value_enum, default_value_t = SecretFormat::Json)]
+        format: SecretFormat,
+        /// Write to this file (owner-readable only) instead of stdout
+        #[arg(long)]
+        out: Option<PathBuf>,
+        /// Overwrite `out` if it exists
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum NonceCmd {
+    /// Create a nonce account controlled and funded by the fee payer (or keypair)
+    Create {
+        /// Store the new account as this profile's `nonce_account`
+        #[arg(long)]
+        save: bool,
+    },
+    /// Show a nonce account's authority and stored blockhash
+    Show {
+        /// Defaults to the configured `nonce_account`
+        address: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SecretFormat {
+    /// Solana CLI JSON byte array
+    Json,
+    /// Base58 string (wallet import format)
+    Base58,
 }
 
 // ======================= Config flows =======================
@@ -232,21 +590,11 @@ pub fn config_set_pools(state_path: &Path, state: &mut AppState) -> Result<()> {
         }
 
         // Ask for amount-in
-        let amt: f64 = Input::new()
+        let amt: DecimalAmount = Input::new()
             .with_prompt("Enter amount-in (decimal)")
-            .validate_with(|v: &String| {
-                v.parse::<f64>()
-                    .map(|x| {
-                        if x >= 0.0 {
-                            Ok(())
-                        } else {
-                            Err("must be >= 0.0")
-                        }
-                    })
-                    .unwrap_or(Err("invalid number"))
-            })
+            .validate_with(|v: &String| v.parse::<DecimalAmount>().map(|_| ()))
             .interact_text()?
-            .parse::<f64>()?;
+            .parse()?;
         state.amount_in = Some(amt);
 
         println!(
@@ -285,15 +633,15 @@ pub fn config_set_rpc(state_path: &Path, state: &mut AppState) -> Result<()> {
 
 pub fn config_set_keypair(state_path: &Path, state: &mut AppState) -> Result<()> {
     let cur = state.keypair_path.clone().unwrap_or("-unset-".into());
-    println!("Current keypair path: {:?}", cur);
+    println!("Current keypair: {:?}", cur);
     let path_str: String = Input::new()
-        .with_prompt("Enter keypair file path")
+        .with_prompt("Enter keypair source (path, keystore:<path>, env:<VAR> or stdin)")
         .interact_text()?;
     let expanded = shellexpand::tilde(&path_str).to_string();
     validate_keypair_path(Path::new(&expanded))?; // plug your real validator
     state.keypair_path = Some(expanded.clone().into());
     save_state(state_path, state)?;
-    println!("Saved keypair path = {}", expanded);
+    println!("Saved keypair = {}", expanded);
     Ok(())
 }
 
@@ -348,21 +696,11 @@ pub fn config_set_amount_in(state_path: &Path, state: &mut AppState) -> Result<(
     }
 
     // Ask for amount
-    let amt: f64 = Input::new()
+    let amt: DecimalAmount = Input::new()
         .with_prompt("Enter amount-in (decimal)")
-        .validate_with(|v: &String| {
-            v.parse::<f64>()
-                .map(|x| {
-                    if x >= 0.0 {
-                        Ok(())
-                    } else {
-                        Err("must be >= 0.0")
-                    }
-                })
-                .unwrap_or(Err("invalid number"))
-        })
+        .validate_with(|v: &String| v.parse::<DecimalAmount>().map(|_| ()))
         .interact_text()?
-        .parse::<f64>()?;
+        .parse()?;
     state.amount_in = Some(amt);
 
     save_state(state_path, state)?;
@@ -430,20 +768,816 @@ pub fn config_set_simulate(state_path: &Path, state: &mut AppState) -> Result<()
     Ok(())
 }
 
-// ======================= Helpers =======================
+pub fn config_set_reference_pool(state_path: &Path, state: &mut AppState) -> Result<()> {
+    let cur = state.reference_pool.clone().unwrap_or("-unset-".into());
+    println!("Current reference-pool: {cur}");
+    let addr: String = Input::new()
+        .with_prompt("Enter mint_in/SOL CPMM pool address (empty to unset)")
+        .allow_empty(true)
+        .interact_text()?;
+    let addr = addr.trim().to_string();
+    if addr.is_empty() {
+        state.reference_pool = None;
+        save_state(state_path, state)?;
+        println!("Unset reference-pool");
+        return Ok(());
+    }
+
+    if let (Some(rpc_url), Some(mint_in)) = (&state.rpc_url, &state.mint_in) {
+        check_reference_pool(rpc_url, &addr, mint_in)?;
+    } else {
+        println!("rpc-url or mint_in not set; skipping reference pool validation.");
+    }
+    state.reference_pool = Some(addr.clone());
+    save_state(state_path, state)?;
+    println!("Saved reference-pool = {addr}");
+    Ok(())
+}
+
+pub fn config_add_notifier(state_path: &Path, state: &mut AppState) -> Result<()> {
+    let kinds = ["webhook", "slack", "discord", "command"];
+    let kind = Select::new()
+        .with_prompt("Sink kind")
+        .items(&kinds)
+        .default(0)
+        .interact()?;
+
+    let sink = if kinds[kind] == "command" {
+        let line: String = Input::new()
+            .with_prompt("Command (program and arguments, space separated)")
+            .interact_text()?;
+        let mut parts = line.split_whitespace().map(str::to_string);
+        let program = parts.next().context("command cannot be empty")?;
+        SinkConfig::Command {
+            program,
+            args: parts.collect(),
+        }
+    } else {
+        let url: String = Input::new()
+            .with_prompt("Webhook URL")
+            .validate_with(|s: &String| {
+                if s.starts_with("http://") || s.starts_with("https://") {
+                    Ok(())
+                } else {
+                    Err("must start with http:// or https://")
+                }
+            })
+            .interact_text()?;
+        match kinds[kind] {
+            "slack" => SinkConfig::Slack { url },
+            "discord" => SinkConfig::Discord { url },
+            _ => SinkConfig::Webhook { url },
+        }
+    };
+
+    let names: Vec<&str> = EventKind::ALL.iter().map(|e| e.as_str()).collect();
+    let defaults: Vec<bool> = EventKind::ALL
+        .iter()
+        .map(|e| *e != EventKind::Opportunity)
+        .collect();
+    let picked = MultiSelect::new()
+        .with_prompt("Events to send (space to toggle)")
+        .items(&names)
+        .defaults(&defaults)
+        .interact()?;
+    let events: Vec<EventKind> = picked.into_iter().map(|i| EventKind::ALL[i]).collect();
+
+    let template: String = Input::new()
+        .with_prompt("Message template (empty = default; e.g. `{event} {direction} pnl={pnl}`)")
+        .allow_empty(true)
+        .interact_text()?;
+    let min_interval_secs: u64 = Input::new()
+        .with_prompt("Minimum seconds between messages (0 = no rate limit)")
+        .default("0".to_string())
+        .validate_with(|s: &String| s.parse::<u64>().map(|_| ()).map_err(|_| "invalid u64"))
+        .interact_text()?
+        .parse::<u64>()?;
+
+    state.notifiers.push(NotifierConfig {
+        sink,
+        events,
+        template: if template.trim().is_empty() {
+            None
+        } else {
+            Some(template)
+        },
+        min_interval_secs,
+    });
+    save_state(state_path, state)?;
+    println!("Saved notifier #{}", state.notifiers.len() - 1);
+    Ok(())
+}
+
+pub fn config_clear_notifiers(state_path: &Path, state: &mut AppState) -> Result<()> {
+    let n = state.notifiers.len();
+    state.notifiers.clear();
+    save_state(state_path, state)?;
+    println!("Removed {n} notifier(s)");
+    Ok(())
+}
+
+pub fn config_test_notifiers(state: &AppState) -> Result<()> {
+    if state.notifiers.is_empty() {
+        bail!("No notifiers configured. Add one with `config add-notifier`.");
+    }
+    let results = Notifier::new(state.notifiers.clone()).send_test();
+    let mut failed = 0;
+    for (i, r) in results.iter().enumerate() {
+        match r {
+            Ok(()) => println!("notifier #{i}: OK"),
+            Err(e) => {
+                failed += 1;
+                println!("notifier #{i}: FAILED: {e}");
+            }
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} of {} notifier(s) failed", results.len());
+    }
+    Ok(())
+}
+
+// ======================= Non-interactive config =======================
+
+fn single<'a>(key: ConfigKey, values: &'a [String]) -> Result<&'a str> {
+    match values {
+        [v] => Ok(v.as_str()),
+        _ => bail!("`{}` takes exactly one value", key_name(key)),
+    }
+}
+
+fn key_name(key: ConfigKey) -> String {
+    key.to_possible_value()
+        .map(|v| v.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// Make `mint_in` the input mint of the pool pair `(m0, m1)`.
+fn assign_mint_in(state: &mut AppState, m0: &str, m1: &str, mint_in: &str) -> Result<()> {
+    if mint_in == m0 {
+        state.mint_in = Some(m0.to_string());
+        state.mint_out = Some(m1.to_string());
+    } else if mint_in == m1 {
+        state.mint_in = Some(m1.to_string());
+        state.mint_out = Some(m0.to_string());
+    } else {
+        bail!("mint-in {mint_in} is not one of the pool mints ({m0}, {m1})");
+    }
+    Ok(())
+}
+
+fn current_pair(state: &AppState) -> Result<(String, String)> {
+    state
+        .mint_in
+        .clone()
+        .zip(state.mint_out.clone())
+        .context("Mint addresses are unknown. Set pools first: `config set pools <A> <B>`")
+}
+
+pub fn config_get(state: &AppState, key: ConfigKey) -> Result<()> {
+    fn show<T: ToString>(key: ConfigKey, v: &Option<T>) -> Result<()> {
+        match v {
+            Some(v) => {
+                println!("{}", v.to_string());
+                Ok(())
+            }
+            None => bail!("`{}` is not set", key_name(key)),
+        }
+    }
+    match key {
+        ConfigKey::RpcUrl => show(key, &state.rpc_url),
+        ConfigKey::RpcEndpoints => {
+            for endpoint in &state.rpc_endpoints {
+                println!("{endpoint}");
+            }
+            Ok(())
+        }
+        ConfigKey::Keypair => show(key, &state.keypair_path.as_ref().map(|p| p.display())),
+        ConfigKey::FeePayer => show(key, &state.fee_payer.as_ref().map(|p| p.display())),
+        ConfigKey::NonceAccount => show(key, &state.nonce_account),
+        ConfigKey::ProfitGuardProgram => show(key, &state.profit_guard_program),
+        ConfigKey::Pools => show(
+            key,
+            &state
+                .pool_a
+                .as_ref()
+                .zip(state.pool_b.as_ref())
+                .map(|(a, b)| format!("{a} {b}")),
+        ),
+        ConfigKey::MintIn => show(key, &state.mint_in),
+        ConfigKey::MintOut => show(key, &state.mint_out),
+        ConfigKey::AmountIn => show(key, &state.amount_in),
+        ConfigKey::SpreadThresholdBps => show(key, &state.spread_threshold_bps),
+        ConfigKey::SlippageBps => show(key, &state.slippage_bps),
+        ConfigKey::MinPnlRaw => show(key, &state.min_pnl_raw),
+        ConfigKey::DecisionPolicy => show(key, &state.decision_policy),
+        ConfigKey::TwapGuard => show(key, &state.twap_guard),
+        ConfigKey::PriorityFee => show(key, &state.priority_fee_microlamports),
+        ConfigKey::SimulateOnly => show(key, &state.simulate_only),
+        ConfigKey::ReferencePool => show(key, &state.reference_pool),
+        ConfigKey::Notifiers => {
+            println!("{}", serde_json::to_string_pretty(&state.notifiers)?);
+            Ok(())
+        }
+        ConfigKey::Base => show(key, &state.base),
+    }
+}
 
-pub fn take_or_panic<T: Clone>(flag: Option<T>, stored: Option<T>, name: &str) -> T {
-    if let Some(v) = flag {
-        return v;
+pub fn config_set(
+    profile: &str,
+    state_path: &Path,
+    state: &mut AppState,
+    key: ConfigKey,
+    values: &[String],
+    mint_in: Option<&str>,
+) -> Result<()> {
+    if mint_in.is_some() && !matches!(key, ConfigKey::Pools | ConfigKey::AmountIn) {
+        bail!("--mint-in only applies to `pools` and `amount-in`");
     }
-    if let Some(v) = stored {
-        return v;
+    match key {
+        ConfigKey::RpcUrl => {
+            let url = single(key, values)?;
+            check_rpc_url(url)?;
+            state.rpc_url = Some(url.to_string());
+        }
+        ConfigKey::RpcEndpoints => {
+            let endpoints = values
+                .iter()
+                .map(|v| v.parse::<RpcEndpoint>().map_err(ArbError::Config))
+                .collect::<Result<Vec<_>, _>>()?;
+            check_rpc_endpoints(&endpoints)?;
+            state.rpc_endpoints = endpoints;
+        }
+        ConfigKey::Keypair => {
+            let expanded = shellexpand::tilde(single(key, values)?).to_string();
+            validate_keypair_path(Path::new(&expanded))?;
+            state.keypair_path = Some(expanded.into());
+        }
+        ConfigKey::FeePayer => {
+            let expanded = shellexpand::tilde(single(key, values)?).to_string();
+            validate_keypair_path(Path::new(&expanded))?;
+            state.fee_payer = Some(expanded.into());
+        }
+        ConfigKey::NonceAccount => {
+            let addr = single(key, values)?;
+            addr.parse::<Pubkey>()
+                .map_err(|e| ArbError::Config(format!("invalid nonce account {addr}: {e}")))?;
+            state.nonce_account = Some(addr.to_string());
+        }
+        ConfigKey::ProfitGuardProgram => {
+            let addr = single(key, values)?;
+            addr.parse::<Pubkey>().map_err(|e| {
+                ArbError::Config(format!("invalid profit guard program {addr}: {e}"))
+            })?;
+            state.profit_guard_program = Some(addr.to_string());
+        }
+        ConfigKey::Pools => {
+            let [a, b] = values else {
+                bail!("`pools` takes two values: <POOL_A> <POOL_B>");
+            };
+            let rpc = state
+                .rpc_url
+                .clone()
+                .context("rpc-url must be set to validate pools: `config set rpc-url <URL>`")?;
+            let (m0, m1) = compute_mints(&rpc, a, b)?;
+            // Keep the current input mint when the pair is unchanged
+            let keep = state
+                .mint_in
+                .clone()
+                .filter(|mi| (mi == &m0 || mi == &m1) && state.mint_out.is_some());
+            let chosen = match (mint_in, keep) {
+                (Some(mi), _) => mi.to_string(),
+                (None, Some(mi)) => mi,
+                (None, None) => bail!(
+                    "Pool mints changed ({m0}, {m1}); pass --mint-in to choose the input mint"
+                ),
+            };
+            assign_mint_in(state, &m0, &m1, &chosen)?;
+            state.pool_a = Some(a.clone());
+            state.pool_b = Some(b.clone());
+        }
+        ConfigKey::MintIn => {
+            let (m0, m1) = current_pair(state)?;
+            assign_mint_in(state, &m0, &m1, single(key, values)?)?;
+        }
+        ConfigKey::MintOut => bail!("mint-out follows from pools; set `mint-in` instead"),
+        ConfigKey::AmountIn => {
+            let amt: DecimalAmount = single(key, values)?.parse()?;
+            if let Some(mi) = mint_in {
+                let (m0, m1) = current_pair(state)?;
+                assign_mint_in(state, &m0, &m1, mi)?;
+            }
+            state.amount_in = Some(amt);
+        }
+        ConfigKey::SpreadThresholdBps => {
+            state.spread_threshold_bps = Some(
+                single(key, values)?
+                    .parse()
+                    .context("spread-threshold-bps must be a u32")?,
+            );
+        }
+        ConfigKey::SlippageBps => {
+            state.slippage_bps = Some(
+                single(key, values)?
+                    .parse()
+                    .context("slippage-bps must be a u32")?,
+            );
+        }
+        ConfigKey::MinPnlRaw => {
+            state.min_pnl_raw = Some(
+                single(key, values)?
+                    .parse()
+                    .context("min-pnl-raw must be a u64 (raw mint_in units)")?,
+            );
+        }
+        ConfigKey::DecisionPolicy => {
+            state.decision_policy = Some(single(key, values)?.parse().map_err(ArbError::Config)?);
+        }
+        ConfigKey::TwapGuard => {
+            state.twap_guard = Some(single(key, values)?.parse().map_err(ArbError::Config)?);
+        }
+        ConfigKey::PriorityFee => {
+            state.priority_fee_microlamports = Some(
+                single(key, values)?
+                    .parse()
+                    .context("priority-fee must be a u64 (micro-lamports)")?,
+            );
+        }
+        ConfigKey::SimulateOnly => {
+            state.simulate_only = Some(
+                single(key, values)?
+                    .parse()
+                    .context("simulate-only must be true or false")?,
+            );
+        }
+        ConfigKey::ReferencePool => {
+            let addr = single(key, values)?;
+            if let (Some(rpc_url), Some(mint_in)) = (&state.rpc_url, &state.mint_in) {
+                check_reference_pool(rpc_url, addr, mint_in)?;
+            } else {
+                println!("rpc-url or mint_in not set; skipping reference pool validation.");
+            }
+            state.reference_pool = Some(addr.to_string());
+        }
+        ConfigKey::Notifiers => {
+            state.notifiers = serde_json::from_str(single(key, values)?)
+                .context("notifiers must be a JSON array of notifier objects")?;
+        }
+        ConfigKey::Base => {
+            // Re-base the stored layer, not the resolved view, so inherited values keep following.
+            // Resolving first rejects a self-base or a cycle before anything is saved.
+            let mut raw = load_raw_state(state_path)?;
+            raw.base = Some(single(key, values)?.to_string());
+            *state = resolve_inheritance(profile, raw)?;
+        }
     }
-    panic!(
-        "Missing required parameter `{name}`: not provided as a flag and not found in state. (Set defaults in `default_state()` or configure via `config` commands.)"
+    save_state(state_path, state)?;
+    println!("Saved {} to {}", key_name(key), state_path.display());
+    Ok(())
+}
+
+pub fn config_unset(
+    profile: &str,
+    state_path: &Path,
+    state: &mut AppState,
+    key: ConfigKey,
+) -> Result<()> {
+    match key {
+        ConfigKey::MintIn | ConfigKey::MintOut => {
+            bail!("mints follow from pools; use `config unset pools`")
+        }
+        // Detaching keeps the currently inherited values as the profile's own
+        ConfigKey::Base => {
+            state.base = None;
+            save_state(state_path, state)?;
+        }
+        // Clear the stored layer: `save_state` on the resolved view would keep the base's value
+        _ => {
+            let mut raw = load_raw_state(state_path)?;
+            clear_field(&mut raw, key);
+            write_raw_state(state_path, &raw)?;
+            *state = resolve_inheritance(profile, raw)?;
+        }
+    }
+    println!("Unset {} in {}", key_name(key), state_path.display());
+    Ok(())
+}
+
+fn clear_field(st: &mut AppState, key: ConfigKey) {
+    match key {
+        ConfigKey::RpcUrl => st.rpc_url = None,
+        ConfigKey::RpcEndpoints => st.rpc_endpoints.clear(),
+        ConfigKey::Keypair => st.keypair_path = None,
+        ConfigKey::FeePayer => st.fee_payer = None,
+        ConfigKey::NonceAccount => st.nonce_account = None,
+        ConfigKey::ProfitGuardProgram => st.profit_guard_program = None,
+        ConfigKey::Pools | ConfigKey::MintIn | ConfigKey::MintOut => {
+            st.pool_a = None;
+            st.pool_b = None;
+            st.mint_in = None;
+            st.mint_out = None;
+        }
+        ConfigKey::AmountIn => st.amount_in = None,
+        ConfigKey::SpreadThresholdBps => st.spread_threshold_bps = None,
+        ConfigKey::SlippageBps => st.slippage_bps = None,
+        ConfigKey::MinPnlRaw => st.min_pnl_raw = None,
+        ConfigKey::DecisionPolicy => st.decision_policy = None,
+        ConfigKey::TwapGuard => st.twap_guard = None,
+        ConfigKey::PriorityFee => st.priority_fee_microlamports = None,
+        ConfigKey::SimulateOnly => st.simulate_only = None,
+        ConfigKey::ReferencePool => st.reference_pool = None,
+        ConfigKey::Notifiers => st.notifiers.clear(),
+        ConfigKey::Base => st.base = None,
+    }
+}
+
+/// Run the same checks as the interactive setters against a complete state.
+pub fn validate_state(st: &AppState) -> Result<()> {
+    if let Some(path) = &st.keypair_path {
+        validate_keypair_path(path)?;
+    }
+    if let Some(path) = &st.fee_payer {
+        validate_keypair_path(path)?;
+    }
+    check_rpc_endpoints(&st.rpc_endpoints)?;
+    if let Some(policy) = &st.decision_policy {
+        policy.check().map_err(ArbError::Config)?;
+    }
+    if let Some(guard) = &st.twap_guard {
+        guard.check().map_err(ArbError::Config)?;
+    }
+    let Some(rpc_url) = &st.rpc_url else {
+        return Ok(());
+    };
+    check_rpc_url(rpc_url)?;
+    if let (Some(a), Some(b)) = (&st.pool_a, &st.pool_b) {
+        let (m0, m1) = compute_mints(rpc_url, a, b)?;
+        let (mi, mo) = current_pair(st)?;
+        if !((mi == m0 && mo == m1) || (mi == m1 && mo == m0)) {
+            bail!("mint_in/mint_out ({mi}, {mo}) do not match the pool mints ({m0}, {m1})");
+        }
+    }
+    if let (Some(pool), Some(mint_in)) = (&st.reference_pool, &st.mint_in) {
+        check_reference_pool(rpc_url, pool, mint_in)?;
+    }
+    Ok(())
+}
+
+pub fn config_import(
+    profile: &str,
+    state_path: &Path,
+    state: &mut AppState,
+    path: &Path,
+) -> Result<()> {
+    let data = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+    let (imported, _) = parse_state(&data, path)?;
+    let resolved = resolve_inheritance(profile, imported)?;
+    validate_state(&resolved)?;
+    *state = resolved;
+    save_state(state_path, state)?;
+    println!("Imported {} into {}", path.display(), state_path.display());
+    Ok(())
+}
+
+/// Export the resolved state without `base`, so the file stands on its own.
+pub fn config_export(state: &AppState, path: Option<&Path>) -> Result<()> {
+    let out = AppState {
+        base: None,
+        ..state.clone()
+    };
+    let json = to_stored_json(&out)?;
+    match path {
+        Some(p) => {
+            fs::write(p, format!("{json}\n")).with_context(|| format!("write {}", p.display()))?;
+            eprintln!("Exported state to {}", p.display());
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+// ======================= Keypairs =======================
+
+pub fn run_keypair_cmd(cmd: KeypairCmd) -> Result<()> {
+    match cmd {
+        KeypairCmd::New {
+            out,
+            plaintext,
+            force,
+        } => keypair_new(&out, plaintext, force),
+        KeypairCmd::Import { from, out, force } => keypair_import(&from, &out, force),
+        KeypairCmd::Export {
+            from,
+            format,
+            out,
+            force,
+        } => keypair_export(&from, format, out.as_deref(), force),
+    }
+}
+
+fn keypair_new(out: &Path, plaintext: bool, force: bool) -> Result<()> {
+    let keypair = Keypair::new();
+    if plaintext {
+        keystore::write_secret_file(out, &format!("{}\n", secret_json(&keypair)), force)?;
+        println!(
+            "Wrote plaintext keypair {} to {}",
+            keypair.pubkey(),
+            out.display()
+        );
+        return Ok(());
+    }
+    let ks = keystore::encrypt(&keypair, &keystore::password(true)?)?;
+    keystore::write(out, &ks, force)?;
+    println!("Wrote keystore {} to {}", keypair.pubkey(), out.display());
+    println!("Use it with: --keypair keystore:{}", out.display());
+    Ok(())
+}
+
+fn keypair_import(from: &str, out: &Path, force: bool) -> Result<()> {
+    let source = SignerSource::parse(from)?;
+    if let SignerSource::Keystore(_) = source {
+        bail!("`{from}` is already a keystore; use `keypair export` to decrypt it");
+    }
+    let keypair = source.load()?;
+    let ks = keystore::encrypt(&keypair, &keystore::password(true)?)?;
+    keystore::write(out, &ks, force)?;
+    println!(
+        "Imported {} into keystore {}",
+        keypair.pubkey(),
+        out.display()
+    );
+    Ok(())
+}
+
+fn keypair_export(from: &str, format: SecretFormat, out: Option<&Path>, force: bool) -> Result<()> {
+    let keypair = SignerSource::parse(from)?.load()?;
+    let secret = match format {
+        SecretFormat::Json => secret_json(&keypair),
+        SecretFormat::Base58 => keypair.to_base58_string(),
+    };
+    match out {
+        Some(path) => {
+            keystore::write_secret_file(path, &format!("{secret}\n"), force)?;
+            eprintln!(
+                "Exported plaintext secret of {} to {}",
+                keypair.pubkey(),
+                path.display()
+            );
+        }
+        None => {
+            eprintln!(
+                "Plaintext secret key of {} follows; keep it private",
+                keypair.pubkey()
+            );
+            println!("{secret}");
+        }
+    }
+    Ok(())
+}
+
+fn secret_json(keypair: &Keypair) -> String {
+    serde_json::to_string(&keypair.to_bytes().to_vec()).expect("byte array serializes")
+}
+
+// ======================= Nonce & offline transactions =======================
+
+/// Create a nonce account whose authority is `authority`, which also pays for it.
+pub fn nonce_create(rpc_url: &str, authority: &dyn Signer) -> Result<Pubkey> {
+    let rpc = RpcClient::new(rpc_url);
+    let nonce_keypair = Keypair::new();
+    let lamports = nonce_rent(&rpc)?;
+    let instructions = create_nonce_instructions(
+        &authority.pubkey(),
+        &nonce_keypair.pubkey(),
+        &authority.pubkey(),
+        lamports,
     );
+    let blockhash = rpc
+        .get_latest_blockhash()
+        .map_err(|e| ArbError::Rpc(format!("fetch blockhash: {e}")))?;
+    let signers: [&dyn Signer; 2] = [authority, &nonce_keypair];
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&authority.pubkey()),
+        &signers,
+        blockhash,
+    );
+    let sig = rpc
+        .send_and_confirm_transaction(&tx)
+        .map_err(|e| ArbError::Send(format!("create nonce account: {e}")))?;
+    println!("Nonce account: {}", nonce_keypair.pubkey());
+    println!("  authority: {}", authority.pubkey());
+    println!("  rent:      {} lamports", lamports);
+    println!("  signature: {}", sig);
+    Ok(nonce_keypair.pubkey())
+}
+
+pub fn nonce_show(rpc_url: &str, address: &str) -> Result<()> {
+    let account = address
+        .parse::<Pubkey>()
+        .map_err(|e| ArbError::Config(format!("invalid nonce account {address}: {e}")))?;
+    let nonce = DurableNonce::fetch(&RpcClient::new(rpc_url), &account)?;
+    println!("Nonce account: {}", nonce.account);
+    println!("  authority:              {}", nonce.authority);
+    println!("  blockhash:              {}", nonce.blockhash);
+    println!("  lamports_per_signature: {}", nonce.lamports_per_signature);
+    Ok(())
+}
+
+/// Transaction argument of `sign`/`submit`: base64, `@<FILE>` or `-` for stdin.
+fn read_tx_arg(arg: &str) -> Result<Transaction> {
+    let encoded = if arg == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else if let Some(path) = arg.strip_prefix('@') {
+        fs::read_to_string(path).with_context(|| format!("read {path}"))?
+    } else {
+        arg.to_string()
+    };
+    decode_transaction(&encoded)
+}
+
+fn load_signers(sources: &[String]) -> Result<Vec<Box<dyn Signer>>> {
+    sources
+        .iter()
+        .map(|s| SignerSource::parse(s)?.signer())
+        .collect()
+}
+
+fn report_signatures(signed: &[Pubkey], missing: &[Pubkey]) {
+    for key in signed {
+        eprintln!("Signed by {key}");
+    }
+    for key in missing {
+        eprintln!("Still missing a signature from {key}");
+    }
 }
 
+pub fn sign_offline(tx_arg: &str, signers: &[String]) -> Result<()> {
+    let mut tx = read_tx_arg(tx_arg)?;
+    let signed = sign_missing(&mut tx, &load_signers(signers)?)?;
+    report_signatures(&signed, &missing_signers(&tx));
+    println!("{}", encode_transaction(&tx)?);
+    Ok(())
+}
+
+pub fn submit(rpc_url: &str, tx_arg: &str, signers: &[String]) -> Result<()> {
+    let mut tx = read_tx_arg(tx_arg)?;
+    let signed = sign_missing(&mut tx, &load_signers(signers)?)?;
+    let missing = missing_signers(&tx);
+    report_signatures(&signed, &missing);
+    if !missing.is_empty() {
+        return Err(ArbError::Config(format!(
+            "transaction still needs {} signature(s); pass them with --signer",
+            missing.len()
+        ))
+        .into());
+    }
+    tx.verify()
+        .map_err(|e| ArbError::Config(format!("signature verification failed: {e}")))?;
+    // A prepared transaction cannot be re-quoted; it is rebroadcast until it lands or expires
+    let rpcs = Endpoints::single(rpc_url);
+    let opts = SendOptions {
+        max_requotes: 0,
+        ..SendOptions::default()
+    };
+    let mut sender = Sender::new(&rpcs, &opts);
+    let built = BuiltTransaction {
+        tx,
+        last_valid_block_height: None,
+    };
+    let sig = sender.send(built, &mut || Ok(None))?;
+    eprintln!(
+        "Confirmed in slot {} after {} broadcast(s)",
+        sender.landed_slot.unwrap_or_default(),
+        sender.attempts.len()
+    );
+    println!("{sig}");
+    Ok(())
+}
+
+// ======================= Profiles =======================
+
+pub fn run_profile_cmd(cmd: ProfileCmd) -> Result<()> {
+    match cmd {
+        ProfileCmd::Create { name, base } => profile_create(&name, base),
+        ProfileCmd::List => profile_list(),
+        ProfileCmd::Copy { from, to } => profile_copy(&from, &to),
+        ProfileCmd::Delete { name, yes } => profile_delete(&name, yes),
+        ProfileCmd::Use { name } => profile_use(&name),
+    }
+}
+
+fn require_profile(name: &str) -> Result<PathBuf> {
+    let path = profile_state_path(name)?;
+    if name != DEFAULT_PROFILE && !path.exists() {
+        return Err(ArbError::Config(format!(
+            "profile `{name}` does not exist; create it with `config profile create {name}`"
+        ))
+        .into());
+    }
+    Ok(path)
+}
+
+fn profile_create(name: &str, base: Option<String>) -> Result<()> {
+    let path = profile_state_path(name)?;
+    if path.exists() || name == DEFAULT_PROFILE {
+        bail!("Profile `{name}` already exists");
+    }
+    let state = match base {
+        Some(base) => {
+            require_profile(&base)?;
+            AppState {
+                base: Some(base),
+                ..AppState::default()
+            }
+        }
+        None => default_state(),
+    };
+    save_state(&path, &state)?;
+    println!("Created profile `{name}` at {}", path.display());
+    Ok(())
+}
+
+fn profile_list() -> Result<()> {
+    let active = active_profile()?;
+    for name in list_profiles()? {
+        let st = load_raw_state(&profile_state_path(&name)?)?;
+        let marker = if name == active { "*" } else { " " };
+        match st.base {
+            Some(base) => println!("{marker} {name} (base: {base})"),
+            None => println!("{marker} {name}"),
+        }
+    }
+    Ok(())
+}
+
+fn profile_copy(from: &str, to: &str) -> Result<()> {
+    let src = require_profile(from)?;
+    let dst = profile_state_path(to)?;
+    if dst.exists() || to == DEFAULT_PROFILE {
+        bail!("Profile `{to}` already exists");
+    }
+    let st = load_raw_state(&src)?;
+    if st.base.as_deref() == Some(to) {
+        bail!("Profile `{from}` inherits from `{to}`; cannot copy onto its base");
+    }
+    // Write the stored layer as-is so the copy keeps following the same base
+    write_raw_state(&dst, &st)?;
+    println!("Copied profile `{from}` to `{to}`");
+    Ok(())
+}
+
+fn profile_delete(name: &str, yes: bool) -> Result<()> {
+    if name == DEFAULT_PROFILE {
+        bail!("The `{DEFAULT_PROFILE}` profile cannot be deleted; use `config reset-defaults`");
+    }
+    let path = require_profile(name)?;
+    if active_profile()? == name {
+        bail!("Profile `{name}` is active; `config profile use` another one first");
+    }
+    let deps = dependents(name)?;
+    if !deps.is_empty() {
+        bail!(
+            "Profile `{name}` is the base of: {}. Delete or re-base them first.",
+            deps.join(", ")
+        );
+    }
+    if !yes
+        && !Confirm::new()
+            .with_prompt(format!("Delete profile `{name}` and its history?"))
+            .default(false)
+            .interact()?
+    {
+        println!("Aborted");
+        return Ok(());
+    }
+    let dir = path.parent().context("profile path has no parent")?;
+    fs::remove_dir_all(dir).with_context(|| format!("remove {}", dir.display()))?;
+    println!("Deleted profile `{name}`");
+    Ok(())
+}
+
+fn profile_use(name: &str) -> Result<()> {
+    require_profile(name)?;
+    set_active_profile(name)?;
+    println!("Active profile: {name}");
+    Ok(())
+}
+
+/// Profile selected by `--profile`, else `SOLANA_ARB_PROFILE`, else the active one;
+/// named profiles must exist.
+pub fn selected_profile(flag: Option<&str>) -> Result<(String, PathBuf)> {
+    let env_profile = std::env::var(PROFILE_ENV).ok().filter(|v| !v.is_empty());
+    let name = match flag.map(str::to_string).or(env_profile) {
+        Some(n) => n,
+        None => active_profile()?,
+    };
+    let path = require_profile(&name)?;
+    Ok((name, path))
+}
+
+// ======================= Helpers =======================
+
 pub fn state_file_path() -> Result<PathBuf> {
     let pd = ProjectDirs::from("com", "yourorg", "solana-amm-arb-cli")
         .context("cannot determine platform-specific dirs")?;
@@ -451,25 +1585,55 @@ pub fn state_file_path() -> Result<PathBuf> {
     Ok(dir.join("state.json"))
 }
 
-pub fn load_state(path: &Path) -> Result<AppState> {
+/// Load a profile's state with inherited values from its base profile(s) filled in.
+pub fn load_state(profile: &str, path: &Path) -> Result<AppState> {
+    resolve_inheritance(profile, load_raw_state(path)?)
+}
+
+/// Load exactly what is stored in `path`, without resolving `base`.
+pub fn load_raw_state(path: &Path) -> Result<AppState> {
     if !path.exists() {
         let s = default_state();
         save_state(path, &s)?;
         return Ok(s);
     }
     let data = fs::read(path).with_context(|| format!("read {}", path.display()))?;
-    let st: AppState = serde_json::from_slice(&data)
-        .with_context(|| format!("parse JSON in {}", path.display()))?;
+    let (st, migrated_from) = parse_state(&data, path)?;
+    if let Some(from) = migrated_from {
+        // Keep the original bytes; older builds can still read the backup
+        let backup = backup_path(path, from);
+        fs::write(&backup, &data).with_context(|| format!("write {}", backup.display()))?;
+        write_raw_state(path, &st)?;
+        eprintln!(
+            "Migrated {} from schema v{from} to v{STATE_SCHEMA_VERSION} (backup: {})",
+            path.display(),
+            backup.display()
+        );
+    }
     Ok(st)
 }
 
+/// Save a (resolved) state. Values equal to the base profile's are stored unset so they
+/// keep following the base.
 pub fn save_state(path: &Path, st: &AppState) -> Result<()> {
+    let stored = match load_base(st)? {
+        Some(base) => strip_inherited(st, &base),
+        None => st.clone(),
+    };
+    write_raw_state(path, &stored)
+}
+
+/// Atomically write `st` exactly as given.
+pub fn write_raw_state(path: &Path, st: &AppState) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
     let tmp = path.with_extension("json.tmp");
-    let data = serde_json::to_vec_pretty(st)?;
+    let data = to_stored_json(st)?;
     {
         let mut f =
             fs::File::create(&tmp).with_context(|| format!("create temp {}", tmp.display()))?;
-        f.write_all(&data)?;
+        f.write_all(data.as_bytes())?;
         f.sync_all()?;
     }
     fs::rename(&tmp, path)
@@ -511,13 +1675,45 @@ pub fn compute_mints(rpc_url: &str, pool_a: &str, pool_b: &str) -> Result<(Strin
     }
 }
 
+/// Ensure the reference pool pairs `mint_in` with SOL.
+pub fn check_reference_pool(rpc_url: &str, pool: &str, mint_in: &str) -> Result<()> {
+    let rpc = RpcClient::new(rpc_url);
+    let data = PoolData::new(&rpc, pool, &RaydiumCpmmDecoder)?;
+    let mints = [
+        data.state.token0_mint.to_string(),
+        data.state.token1_mint.to_string(),
+    ];
+    if !(mints.contains(&mint_in.to_string()) && mints.contains(&SOL_MINT.to_string())) {
+        bail!(
+            "Reference pool {} must pair mint_in {} with SOL, found {} / {}",
+            pool,
+            mint_in,
+            mints[0],
+            mints[1]
+        );
+    }
+    Ok(())
+}
+
 pub fn check_rpc_url(rpc_url: &str) -> Result<()> {
     let rpc = RpcClient::new(rpc_url);
     rpc.get_health()?;
     Ok(())
 }
 
-pub fn validate_keypair_path(path: &Path) -> Result<()> {
-    load_keypair(path)?;
+/// Read and send endpoints must answer `get_health`; websocket URLs are only checked for shape.
+pub fn check_rpc_endpoints(endpoints: &[RpcEndpoint]) -> Result<()> {
+    for endpoint in endpoints {
+        endpoint.check().map_err(ArbError::Config)?;
+        if endpoint.has_role(EndpointRole::Read) || endpoint.has_role(EndpointRole::Send) {
+            check_rpc_url(&endpoint.url)
+                .with_context(|| format!("endpoint {} is not healthy", endpoint.url))?;
+        }
+    }
     Ok(())
 }
+
+/// Accepts any signer source (see `SignerSource`); plain paths must hold a valid keypair.
+pub fn validate_keypair_path(path: &Path) -> Result<()> {
+    SignerSource::parse(&path.to_string_lossy())?.validate()
+}
@@ -0,0 +1,477 @@
+use anyhow::Result;
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, RecvTimeoutError},
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::error::{ArbError, classify};
+
+pub const DEFAULT_MAX_SLOT_LAG: u64 = 50;
+pub const DEFAULT_HEALTH_INTERVAL_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EndpointRole {
+    /// Account reads, quotes, simulation and confirmation polling
+    Read,
+    /// Every signed transaction is broadcast through all send endpoints
+    Send,
+    /// Pubsub URL (`ws://`/`wss://`); sends watch for confirmation through a signature
+    /// subscription here instead of waiting for the next status poll
+    Websocket,
+}
+
+impl FromStr for EndpointRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "read" => Ok(EndpointRole::Read),
+            "send" => Ok(EndpointRole::Send),
+            "websocket" | "ws" => Ok(EndpointRole::Websocket),
+            other => Err(format!(
+                "unknown endpoint role `{other}` (read, send, websocket)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EndpointRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EndpointRole::Read => "read",
+            EndpointRole::Send => "send",
+            EndpointRole::Websocket => "websocket",
+        })
+    }
+}
+
+fn default_roles() -> Vec<EndpointRole> {
+    vec![EndpointRole::Read, EndpointRole::Send]
+}
+
+/// An RPC endpoint in addition to `rpc_url`, which always reads and sends.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RpcEndpoint {
+    pub url: String,
+    #[serde(default = "default_roles")]
+    pub roles: Vec<EndpointRole>,
+}
+
+impl RpcEndpoint {
+    pub fn has_role(&self, role: EndpointRole) -> bool {
+        self.roles.contains(&role)
+    }
+
+    /// URL scheme matches the roles: `http(s)://` to read or send, `ws(s)://` for websocket.
+    pub fn check(&self) -> Result<(), String> {
+        let ws = self.url.starts_with("ws://") || self.url.starts_with("wss://");
+        let http = self.url.starts_with("http://") || self.url.starts_with("https://");
+        if self.roles.is_empty() {
+            return Err(format!("endpoint {} has no role", self.url));
+        }
+        if self.has_role(EndpointRole::Websocket) && !ws {
+            return Err(format!(
+                "websocket endpoint {} must be a ws:// or wss:// URL",
+                self.url
+            ));
+        }
+        if (self.has_role(EndpointRole::Read) || self.has_role(EndpointRole::Send)) && !http {
+            return Err(format!(
+                "read/send endpoint {} must be an http:// or https:// URL",
+                self.url
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// `[ROLES=]URL`, e.g. `https://rpc.example`, `send=https://relay.example` or
+/// `read,send=https://rpc.example`. Without roles an endpoint reads and sends.
+impl FromStr for RpcEndpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // Only a prefix made of role names is a role list; URLs may contain `=` too
+        let parsed = s.split_once('=').and_then(|(prefix, url)| {
+            prefix
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<Vec<EndpointRole>, _>>()
+                .ok()
+                .map(|roles| (roles, url))
+        });
+        let endpoint = match parsed {
+            Some((roles, url)) => RpcEndpoint {
+                url: url.to_string(),
+                roles,
+            },
+            None => RpcEndpoint {
+                url: s.to_string(),
+                roles: default_roles(),
+            },
+        };
+        endpoint.check()?;
+        Ok(endpoint)
+    }
+}
+
+impl fmt::Display for RpcEndpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let roles: Vec<String> = self.roles.iter().map(ToString::to_string).collect();
+        write!(f, "{}={}", roles.join(","), self.url)
+    }
+}
+
+/// Per-endpoint counters since the process started, logged and written to the report.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct EndpointStats {
+    pub url: String,
+    pub roles: Vec<EndpointRole>,
+    pub healthy: bool,
+    pub calls: u64,
+    pub errors: u64,
+    pub avg_latency_ms: f64,
+    pub max_latency_ms: u64,
+    /// From the last health check
+    pub slot: Option<u64>,
+    /// Behind the most advanced endpoint at the last health check
+    pub slot_lag: Option<u64>,
+    pub last_error: Option<String>,
+    #[serde(skip)]
+    total_latency_ms: u64,
+}
+
+struct Entry {
+    endpoint: RpcEndpoint,
+    client: Option<RpcClient>, // None for websocket-only endpoints
+    stats: Mutex<EndpointStats>,
+}
+
+/// The configured RPC endpoints: reads fail over (and optionally hedge) across the
+/// healthy read endpoints in configured order, sends fan out to every send endpoint.
+pub struct Endpoints {
+    entries: Vec<Entry>,
+    /// Ask the next read endpoint too when the current one has not answered by then
+    pub hedge_after: Option<Duration>,
+    /// Endpoints further behind the best slot are considered unhealthy
+    pub max_slot_lag: u64,
+}
+
+/// Whether a failed call says something about the endpoint. Decode, config or math errors
+/// say nothing about the endpoint that served the data, and neither does a transaction the
+/// node rejected (`ArbError::Send`).
+pub fn endpoint_fault(e: &anyhow::Error) -> bool {
+    classify(e).is_none_or(|k| matches!(k, ArbError::Rpc(_)))
+}
+
+/// How far `slot` is behind `best`, the most advanced slot any endpoint reported, and
+/// whether that is within `max_slot_lag`.
+pub fn slot_lag(slot: u64, best: Option<u64>, max_slot_lag: u64) -> (u64, bool) {
+    let lag = best.unwrap_or(slot).saturating_sub(slot);
+    (lag, lag <= max_slot_lag)
+}
+
+impl Endpoints {
+    /// `primary` is `rpc_url`; `extra` are the `rpc_endpoints`.
+    pub fn new(
+        primary: &str,
+        extra: &[RpcEndpoint],
+        hedge_after: Option<Duration>,
+        max_slot_lag: u64,
+    ) -> Arc<Self> {
+        let primary = RpcEndpoint {
+            url: primary.to_string(),
+            roles: default_roles(),
+        };
+        let entries = std::iter::once(primary)
+            .chain(extra.iter().cloned())
+            .map(|endpoint| Entry {
+                client: (endpoint.has_role(EndpointRole::Read)
+                    || endpoint.has_role(EndpointRole::Send))
+                .then(|| RpcClient::new(endpoint.url.clone())),
+                stats: Mutex::new(EndpointStats {
+                    url: endpoint.url.clone(),
+                    roles: endpoint.roles.clone(),
+                    healthy: true,
+                    calls: 0,
+                    errors: 0,
+                    avg_latency_ms: 0.0,
+                    max_latency_ms: 0,
+                    slot: None,
+                    slot_lag: None,
+                    last_error: None,
+                    total_latency_ms: 0,
+                }),
+                endpoint,
+            })
+            .collect();
+        Arc::new(Self {
+            entries,
+            hedge_after,
+            max_slot_lag,
+        })
+    }
+
+    /// Just `rpc_url`, for one-off commands.
+    pub fn single(url: &str) -> Arc<Self> {
+        Self::new(url, &[], None, DEFAULT_MAX_SLOT_LAG)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn healthy(&self, i: usize) -> bool {
+        self.entries[i].stats.lock().unwrap().healthy
+    }
+
+    // Healthy endpoints first, each group in configured order
+    fn candidates(&self, role: EndpointRole) -> Vec<usize> {
+        let mut ids: Vec<usize> = (0..self.entries.len())
+            .filter(|&i| self.entries[i].endpoint.has_role(role))
+            .collect();
+        ids.sort_by_key(|&i| !self.healthy(i));
+        ids
+    }
+
+    fn client(&self, i: usize) -> &RpcClient {
+        self.entries[i]
+            .client
+            .as_ref()
+            .expect("read and send endpoints have a client")
+    }
+
+    pub fn url(&self, i: usize) -> &str {
+        &self.entries[i].endpoint.url
+    }
+
+    /// Every send endpoint, healthy ones first.
+    pub fn send_targets(&self) -> Vec<(usize, &RpcClient)> {
+        self.candidates(EndpointRole::Send)
+            .into_iter()
+            .map(|i| (i, self.client(i)))
+            .collect()
+    }
+
+    /// Every websocket endpoint, healthy ones first.
+    pub fn websocket_urls(&self) -> Vec<(usize, &str)> {
+        self.candidates(EndpointRole::Websocket)
+            .into_iter()
+            .map(|i| (i, self.url(i)))
+            .collect()
+    }
+
+    /// Count a call and its error, if any; an `endpoint_fault` also marks the endpoint
+    /// unhealthy until the next health check.
+    pub fn record(&self, i: usize, elapsed: Duration, error: Option<&anyhow::Error>) {
+        let mut s = self.entries[i].stats.lock().unwrap();
+        let ms = elapsed.as_millis() as u64;
+        s.calls += 1;
+        s.total_latency_ms += ms;
+        s.avg_latency_ms = s.total_latency_ms as f64 / s.calls as f64;
+        s.max_latency_ms = s.max_latency_ms.max(ms);
+        if let Some(e) = error {
+            s.errors += 1;
+            s.healthy &= !endpoint_fault(e);
+            s.last_error = Some(format!("{e:#}"));
+        }
+    }
+
+    fn run<T>(&self, i: usize, f: &impl Fn(&RpcClient) -> Result<T>) -> Result<T> {
+        let start = Instant::now();
+        let out = f(self.client(i));
+        self.record(i, start.elapsed(), out.as_ref().err());
+        out
+    }
+
+    /// Run a read on the preferred endpoint, failing over to the next one on RPC errors.
+    pub fn call<T>(&self, method: &str, f: impl Fn(&RpcClient) -> Result<T>) -> Result<T> {
+        let mut last_err = None;
+        for i in self.candidates(EndpointRole::Read) {
+            match self.run(i, &f) {
+                Err(e) if endpoint_fault(&e) => {
+                    warn!("{} via {} failed: {:#}", method, self.url(i), e);
+                    last_err = Some(e);
+                }
+                out => return out,
+            }
+        }
+        Err(last_err.unwrap_or_else(|| ArbError::Rpc("no read endpoint configured".into()).into()))
+    }
+
+    /// Like `call`, but when `hedge_after` passes without an answer the same read is also
+    /// sent to the next endpoint and the first success wins.
+    pub fn hedged<T, F>(self: &Arc<Self>, method: &'static str, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: Fn(&RpcClient) -> Result<T> + Send + Sync + 'static,
+    {
+        let candidates = self.candidates(EndpointRole::Read);
+        let Some(delay) = self.hedge_after.filter(|_| candidates.len() > 1) else {
+            return self.call(method, f);
+        };
+        let f = Arc::new(f);
+        let (tx, rx) = mpsc::channel();
+        // Late answers from losing endpoints are still counted in their stats
+        let launch = |i: usize| {
+            let (me, f, tx) = (Arc::clone(self), Arc::clone(&f), tx.clone());
+            thread::spawn(move || {
+                let _ = tx.send((i, me.run(i, &*f)));
+            });
+        };
+        let mut next = candidates.into_iter();
+        let first = next.next().expect("at least two candidates");
+        launch(first);
+        let mut pending = 1;
+        let mut last_err = None;
+        loop {
+            match rx.recv_timeout(delay) {
+                Ok((i, Ok(v))) => {
+                    if i != first {
+                        info!("{} answered by {} (hedged)", method, self.url(i));
+                    }
+                    return Ok(v);
+                }
+                Ok((i, Err(e))) => {
+                    pending -= 1;
+                    if !endpoint_fault(&e) {
+                        return Err(e);
+                    }
+                    warn!("{} via {} failed: {:#}", method, self.url(i), e);
+                    last_err = Some(e);
+                    match next.next() {
+                        Some(j) => {
+                            launch(j);
+                            pending += 1;
+                        }
+                        None if pending == 0 => return Err(last_err.expect("just set")),
+                        None => {}
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some(j) = next.next() {
+                        info!(
+                            "{} via {}: no answer after {:?}, also asking {}",
+                            method,
+                            self.url(first),
+                            delay,
+                            self.url(j)
+                        );
+                        launch(j);
+                        pending += 1;
+                    }
+                }
+                // `tx` is still held here, so the channel never disconnects
+                Err(RecvTimeoutError::Disconnected) => unreachable!(),
+            }
+        }
+    }
+
+    /// `get_health` and `get_slot` on every endpoint with a client, in parallel; marks
+    /// failing or lagging endpoints unhealthy and logs every change.
+    pub fn check_health(&self) {
+        let results: Vec<(usize, Result<u64, String>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..self.entries.len())
+                .filter(|&i| self.entries[i].client.is_some())
+                .map(|i| {
+                    scope.spawn(move || {
+                        let rpc = self.client(i);
+                        let slot = rpc
+                            .get_health()
+                            .and_then(|_| rpc.get_slot())
+                            .map_err(|e| e.to_string());
+                        (i, slot)
+                    })
+                })
+                .collect();
+            handles.into_iter().filter_map(|h| h.join().ok()).collect()
+        });
+        let best = results
+            .iter()
+            .filter_map(|(_, r)| r.as_ref().ok().copied())
+            .max();
+        for (i, result) in results {
+            let mut s = self.entries[i].stats.lock().unwrap();
+            let was_healthy = s.healthy;
+            match result {
+                Ok(slot) => {
+                    let (lag, healthy) = slot_lag(slot, best, self.max_slot_lag);
+                    s.slot = Some(slot);
+                    s.slot_lag = Some(lag);
+                    s.healthy = healthy;
+                    if !s.healthy {
+                        s.last_error = Some(format!("{lag} slots behind"));
+                    }
+                }
+                Err(e) => {
+                    s.healthy = false;
+                    s.errors += 1;
+                    s.last_error = Some(e);
+                }
+            }
+            if was_healthy && !s.healthy {
+                warn!(
+                    "RPC {} unhealthy: {}",
+                    s.url,
+                    s.last_error.as_deref().unwrap_or("-")
+                );
+            } else if !was_healthy && s.healthy {
+                info!("RPC {} healthy again (slot {:?})", s.url, s.slot);
+            }
+        }
+    }
+
+    /// Re-run `check_health` every `interval` in the background (watch mode).
+    pub fn spawn_monitor(self: &Arc<Self>, interval: Duration) {
+        let me = Arc::clone(self);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                me.check_health();
+            }
+        });
+    }
+
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.entries
+            .iter()
+            .map(|e| e.stats.lock().unwrap().clone())
+            .collect()
+    }
+
+    pub fn log_stats(&self) {
+        for s in self.stats() {
+            info!(
+                "RPC {} [{}] healthy={} calls={} errors={} avg={:.1}ms max={}ms slot_lag={:?}",
+                s.url,
+                s.roles
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                s.healthy,
+                s.calls,
+                s.errors,
+                s.avg_latency_ms,
+                s.max_latency_ms,
+                s.slot_lag
+            );
+        }
+    }
+}
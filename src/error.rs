@@ -0,0 +1,107 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use thiserror::Error;
+
+/// Failure classes surfaced to the caller: each maps to its own process exit code and is
+/// written into the run report.
+#[derive(Debug, Error)]
+pub enum ArbError {
+    /// Missing or invalid configuration (flags, state, profiles)
+    #[error("config error: {0}")]
+    Config(String),
+    /// RPC request failed or returned nothing usable
+    #[error("rpc error: {0}")]
+    Rpc(String),
+    /// On-chain account could not be decoded as expected
+    #[error("decode error: {0}")]
+    Decode(String),
+    /// Arithmetic overflow/underflow or out-of-range values in quoting
+    #[error("math error: {0}")]
+    Math(String),
+    /// Transaction simulation failed
+    #[error("simulation failed: {0}")]
+    Simulation(String),
+    /// Transaction send or confirmation failed
+    #[error("send failed: {0}")]
+    Send(String),
+    /// A risk guard refused to trade
+    #[error("risk limit: {0}")]
+    Risk(String),
+}
+
+pub const EXIT_OK: u8 = 0;
+pub const EXIT_INTERNAL: u8 = 1;
+pub const EXIT_CONFIG: u8 = 2;
+pub const EXIT_RPC: u8 = 3;
+pub const EXIT_DECODE: u8 = 4;
+pub const EXIT_MATH: u8 = 5;
+pub const EXIT_SIMULATION: u8 = 6;
+pub const EXIT_SEND: u8 = 7;
+pub const EXIT_RISK: u8 = 8;
+
+impl ArbError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ArbError::Config(_) => "config",
+            ArbError::Rpc(_) => "rpc",
+            ArbError::Decode(_) => "decode",
+            ArbError::Math(_) => "math",
+            ArbError::Simulation(_) => "simulation",
+            ArbError::Send(_) => "send",
+            ArbError::Risk(_) => "risk",
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            ArbError::Config(_) => EXIT_CONFIG,
+            ArbError::Rpc(_) => EXIT_RPC,
+            ArbError::Decode(_) => EXIT_DECODE,
+            ArbError::Math(_) => EXIT_MATH,
+            ArbError::Simulation(_) => EXIT_SIMULATION,
+            ArbError::Send(_) => EXIT_SEND,
+            ArbError::Risk(_) => EXIT_RISK,
+        }
+    }
+
+    pub fn math(what: impl Into<String>) -> Self {
+        ArbError::Math(what.into())
+    }
+}
+
+/// First `ArbError` in an error chain, if any (errors often carry extra `context`).
+pub fn classify(err: &anyhow::Error) -> Option<&ArbError> {
+    err.chain().find_map(|e| e.downcast_ref::<ArbError>())
+}
+
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    classify(err).map_or(EXIT_INTERNAL, ArbError::exit_code)
+}
+
+/// Serialized form of an error in reports.
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+pub struct ErrorReport {
+    pub kind: String, // "config" | "rpc" | "decode" | "math" | "simulation" | "send" | "risk" | "internal"
+    pub exit_code: u8,
+    pub message: String,
+}
+
+impl From<&anyhow::Error> for ErrorReport {
+    fn from(err: &anyhow::Error) -> Self {
+        Self {
+            kind: classify(err).map_or("internal", ArbError::kind).to_string(),
+            exit_code: exit_code(err),
+            message: format!("{:#}", err),
+        }
+    }
+}
+
+impl From<&ArbError> for ErrorReport {
+    fn from(err: &ArbError) -> Self {
+        Self {
+            kind: err.kind().to_string(),
+            exit_code: err.exit_code(),
+            message: err.to_string(),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, NaiveDate, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+pub const OUTCOME_SKIPPED: &str = "skipped";
+pub const OUTCOME_SIMULATED: &str = "simulated";
+pub const OUTCOME_SIMULATION_FAILED: &str = "simulation_failed";
+pub const OUTCOME_SENT: &str = "sent";
+pub const OUTCOME_SEND_FAILED: &str = "send_failed";
+pub const OUTCOME_SIGNED: &str = "signed"; // --sign-only: prepared, not sent
+pub const OUTCOME_ABORTED: &str = "aborted"; // final re-quote no longer worth trading
+
+/// One line of the append-only trade history (`history.jsonl` next to `state.json`).
+/// Raw amounts are in `mint_in` units unless the field name says otherwise.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub pool_a: String,
+    pub pool_b: String,
+    pub mint_in: String,
+    pub mint_out: String,
+    pub mint_in_decimals: u8,
+    pub direction: String,
+    pub spread_bps: f64,
+    pub should_execute: bool,
+    pub simulate_only: bool,
+    pub outcome: String,
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    pub amount_in_raw: u64,
+    pub expected_amount_out_raw: u64,
+    pub min_out_raw: u64,
+    pub expected_pnl_raw: Option<i64>,
+    pub total_fees_lamports: u64,
+    // Filled by post-trade reconciliation when the transaction landed
+    #[serde(default)]
+    pub realized_amount_out_raw: Option<u64>,
+    #[serde(default)]
+    pub realized_pnl_raw: Option<i64>,
+    #[serde(default)]
+    pub realized_fees_lamports: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub pool: Option<String>,
+    pub outcome: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, e: &HistoryEntry) -> bool {
+        if self.since.is_some_and(|t| e.timestamp < t) {
+            return false;
+        }
+        if self.until.is_some_and(|t| e.timestamp >= t) {
+            return false;
+        }
+        if self
+            .pool
+            .as_ref()
+            .is_some_and(|p| &e.pool_a != p && &e.pool_b != p)
+        {
+            return false;
+        }
+        if self.outcome.as_ref().is_some_and(|o| &e.outcome != o) {
+            return false;
+        }
+        true
+    }
+}
+
+/// Aggregates per `mint_in`, since raw PnL in different mints cannot be summed.
+#[derive(Debug, Default)]
+pub struct MintSummary {
+    pub decimals: u8,
+    pub expected_pnl_raw: i128,
+    pub realized_pnl_raw: i128,
+    pub realized_count: usize,
+}
+
+#[derive(Debug, Default)]
+pub struct HistorySummary {
+    pub total: usize,
+    pub by_outcome: BTreeMap<String, usize>,
+    pub by_mint: BTreeMap<String, MintSummary>,
+}
+
+pub fn history_file_path(state_path: &Path) -> PathBuf {
+    state_path
+        .parent()
+        .map(|d| d.join(HISTORY_FILE))
+        .unwrap_or_else(|| PathBuf::from(HISTORY_FILE))
+}
+
+pub fn append_entry(path: &Path, entry: &HistoryEntry) -> Result<()> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("open {}", path.display()))?;
+    f.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Load every entry; malformed lines are logged and skipped so one bad write cannot hide the rest.
+pub fn load_entries(path: &Path) -> Result<Vec<HistoryEntry>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+    let mut entries = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<HistoryEntry>(line) {
+            Ok(e) => entries.push(e),
+            Err(e) => warn!(
+                "{}:{}: skipping malformed entry: {}",
+                path.display(),
+                i + 1,
+                e
+            ),
+        }
+    }
+    Ok(entries)
+}
+
+pub fn summarize<'a>(entries: impl IntoIterator<Item = &'a HistoryEntry>) -> HistorySummary {
+    let mut summary = HistorySummary::default();
+    for e in entries {
+        summary.total += 1;
+        *summary.by_outcome.entry(e.outcome.clone()).or_default() += 1;
+        let m = summary.by_mint.entry(e.mint_in.clone()).or_default();
+        m.decimals = e.mint_in_decimals;
+        if e.outcome == OUTCOME_SENT {
+            m.expected_pnl_raw += e.expected_pnl_raw.unwrap_or(0) as i128;
+        }
+        if let Some(p) = e.realized_pnl_raw {
+            m.realized_pnl_raw += p as i128;
+            m.realized_count += 1;
+        }
+    }
+    summary
+}
+
+/// Accepts `YYYY-MM-DD` (midnight UTC) or a full RFC 3339 timestamp.
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(d.and_hms_opt(0, 0, 0).unwrap().and_utc());
+    }
+    bail!("invalid date `{s}`: expected YYYY-MM-DD or RFC 3339")
+}
+
+/// Backs the `history` command: matching entries (most recent `limit`) plus aggregates.
+pub fn print_history(path: &Path, filter: &HistoryFilter, limit: usize, json: bool) -> Result<()> {
+    let entries = load_entries(path)?;
+    let matched: Vec<&HistoryEntry> = entries.iter().filter(|e| filter.matches(e)).collect();
+    let shown = &matched[matched.len().saturating_sub(limit)..];
+
+    if json {
+        for e in shown {
+            println!("{}", serde_json::to_string(e)?);
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{:<25} {:<17} {:<13} {:>10} {:>16} {:>16}  signature",
+        "timestamp", "outcome", "direction", "spread", "expected_pnl", "realized_pnl"
+    );
+    for e in shown {
+        let scale = 10f64.powi(e.mint_in_decimals as i32);
+        let fmt =
+            |v: Option<i64>| v.map_or("-".to_string(), |p| format!("{:.9}", p as f64 / scale));
+        println!(
+            "{:<25} {:<17} {:<13} {:>10.2} {:>16} {:>16}  {}",
+            e.timestamp.format("%Y-%m-%d %H:%M:%S UTC"),
+            e.outcome,
+            e.direction,
+            e.spread_bps,
+            fmt(e.expected_pnl_raw),
+            fmt(e.realized_pnl_raw),
+            e.signature.as_deref().unwrap_or("-")
+        );
+    }
+
+    let summary = summarize(matched.iter().copied());
+    println!();
+    println!(
+        "{} matching entries ({} shown) in {}",
+        summary.total,
+        shown.len(),
+        path.display()
+    );
+    for (outcome, n) in &summary.by_outcome {
+        println!("  {outcome}: {n}");
+    }
+    for (mint, m) in &summary.by_mint {
+        let scale = 10f64.powi(m.decimals as i32);
+        println!(
+            "  PnL in {}: expected (sent) {:.9}, realized {:.9} over {} reconciled trades",
+            mint,
+            m.expected_pnl_raw as f64 / scale,
+            m.realized_pnl_raw as f64 / scale,
+            m.realized_count
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,210 @@
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
+use chacha20poly1305::{
+    KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, Payload},
+};
+use dialoguer::Password;
+use rand::{RngCore, rngs::OsRng};
+use serde::{Deserialize, Serialize};
+use solana_sdk::signer::{Signer, keypair::Keypair};
+use std::{env, fs, io::Write, path::Path};
+use zeroize::Zeroize;
+
+use crate::error::ArbError;
+
+/// Password for keystores; prompted for when unset.
+pub const KEYSTORE_PASSWORD_ENV: &str = "SOLANA_ARB_KEYSTORE_PASSWORD";
+
+const KEYSTORE_VERSION: u32 = 1;
+const CIPHER: &str = "xchacha20poly1305";
+const KDF: &str = "scrypt";
+// scrypt cost: N = 2^15, r = 8, p = 1 (~32 MiB, well under a second)
+const SCRYPT_LOG_N: u8 = 15;
+// Accepted when reading: weaker is not worth a password, stronger (> 128 MiB at r = 8) is a
+// file that would stall or exhaust memory on every load
+const SCRYPT_LOG_N_RANGE: std::ops::RangeInclusive<u8> = 10..=20;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Password-encrypted keypair file. The 64-byte secret is sealed with XChaCha20-Poly1305
+/// under a scrypt-derived key; the public key is authenticated as associated data.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Keystore {
+    pub version: u32,
+    pub pubkey: String,
+    pub kdf: KdfParams,
+    pub cipher: String,
+    pub nonce: String,      // base64
+    pub ciphertext: String, // base64
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KdfParams {
+    pub name: String,
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+    pub salt: String, // base64
+}
+
+fn derive_key(password: &str, kdf: &KdfParams) -> Result<[u8; KEY_LEN]> {
+    if kdf.name != KDF {
+        return Err(ArbError::Config(format!("unsupported keystore kdf `{}`", kdf.name)).into());
+    }
+    if !SCRYPT_LOG_N_RANGE.contains(&kdf.log_n) {
+        return Err(ArbError::Config(format!(
+            "keystore scrypt log_n {} is outside {}..={}",
+            kdf.log_n,
+            SCRYPT_LOG_N_RANGE.start(),
+            SCRYPT_LOG_N_RANGE.end()
+        ))
+        .into());
+    }
+    let salt = B64
+        .decode(&kdf.salt)
+        .context("keystore salt is not base64")?;
+    let params = scrypt::Params::new(kdf.log_n, kdf.r, kdf.p, KEY_LEN)
+        .map_err(|e| ArbError::Config(format!("invalid keystore scrypt params: {e}")))?;
+    let mut key = [0u8; KEY_LEN];
+    scrypt::scrypt(password.as_bytes(), &salt, &params, &mut key)
+        .map_err(|e| anyhow::anyhow!("scrypt: {e}"))?;
+    Ok(key)
+}
+
+pub fn encrypt(keypair: &Keypair, password: &str) -> Result<Keystore> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+    let kdf = KdfParams {
+        name: KDF.to_string(),
+        log_n: SCRYPT_LOG_N,
+        r: SCRYPT_R,
+        p: SCRYPT_P,
+        salt: B64.encode(salt),
+    };
+    let pubkey = keypair.pubkey().to_string();
+
+    let mut key = derive_key(password, &kdf)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).expect("32-byte key");
+    key.zeroize();
+    let mut secret = keypair.to_bytes();
+    let sealed = cipher.encrypt(
+        XNonce::from_slice(&nonce),
+        Payload {
+            msg: &secret,
+            aad: pubkey.as_bytes(),
+        },
+    );
+    secret.zeroize();
+    let ciphertext = sealed.map_err(|_| anyhow::anyhow!("keystore encryption failed"))?;
+
+    Ok(Keystore {
+        version: KEYSTORE_VERSION,
+        pubkey,
+        kdf,
+        cipher: CIPHER.to_string(),
+        nonce: B64.encode(nonce),
+        ciphertext: B64.encode(ciphertext),
+    })
+}
+
+pub fn decrypt(ks: &Keystore, password: &str) -> Result<Keypair> {
+    if ks.version != KEYSTORE_VERSION || ks.cipher != CIPHER {
+        return Err(ArbError::Config(format!(
+            "unsupported keystore (version {}, cipher {})",
+            ks.version, ks.cipher
+        ))
+        .into());
+    }
+    let nonce = B64
+        .decode(&ks.nonce)
+        .context("keystore nonce is not base64")?;
+    if nonce.len() != NONCE_LEN {
+        return Err(ArbError::Config("keystore nonce has the wrong length".into()).into());
+    }
+    let ciphertext = B64
+        .decode(&ks.ciphertext)
+        .context("keystore ciphertext is not base64")?;
+
+    let mut key = derive_key(password, &ks.kdf)?;
+    let cipher = XChaCha20Poly1305::new_from_slice(&key).expect("32-byte key");
+    key.zeroize();
+    let mut secret = cipher
+        .decrypt(
+            XNonce::from_slice(&nonce),
+            Payload {
+                msg: &ciphertext,
+                aad: ks.pubkey.as_bytes(),
+            },
+        )
+        .map_err(|_| ArbError::Config("wrong keystore password or corrupted keystore".into()))?;
+    let keypair = Keypair::from_bytes(&secret);
+    secret.zeroize();
+    let keypair = keypair.map_err(|e| ArbError::Config(format!("keystore secret: {e}")))?;
+    if keypair.pubkey().to_string() != ks.pubkey {
+        return Err(ArbError::Config("keystore pubkey does not match its secret".into()).into());
+    }
+    Ok(keypair)
+}
+
+pub fn read(path: &Path) -> Result<Keystore> {
+    let data = fs::read(path)
+        .map_err(|e| ArbError::Config(format!("read keystore {}: {e}", path.display())))?;
+    let ks = serde_json::from_slice(&data)
+        .map_err(|e| ArbError::Config(format!("{} is not a keystore: {e}", path.display())))?;
+    Ok(ks)
+}
+
+pub fn write(path: &Path, ks: &Keystore, force: bool) -> Result<()> {
+    let json = serde_json::to_string_pretty(ks)?;
+    write_secret_file(path, &format!("{json}\n"), force)
+}
+
+/// Create `path` readable by the owner only; refuses to overwrite unless `force`.
+pub fn write_secret_file(path: &Path, contents: &str, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        return Err(ArbError::Config(format!(
+            "{} already exists (pass --force to overwrite)",
+            path.display()
+        ))
+        .into());
+    }
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    }
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        opts.mode(0o600);
+    }
+    let mut f = opts
+        .open(path)
+        .with_context(|| format!("create {}", path.display()))?;
+    f.write_all(contents.as_bytes())?;
+    f.sync_all()?;
+    Ok(())
+}
+
+/// Keystore password from `SOLANA_ARB_KEYSTORE_PASSWORD`, else an interactive prompt
+/// (asked twice when `confirm`, i.e. when creating a keystore).
+pub fn password(confirm: bool) -> Result<String> {
+    if let Ok(pw) = env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(pw);
+    }
+    let prompt = Password::new().with_prompt("Keystore password");
+    let pw = if confirm {
+        prompt
+            .with_confirmation("Repeat password", "Passwords do not match")
+            .interact()?
+    } else {
+        prompt.interact()?
+    };
+    Ok(pw)
+}
@@ -1,5 +1,24 @@
-pub mod pool;
+pub mod amount;
+pub mod analyze;
+pub mod arbitrage;
 pub mod cli;
+pub mod endpoints;
+pub mod error;
+pub mod history;
+pub mod keystore;
+pub mod metrics;
+pub mod migrate;
+pub mod nonce;
+pub mod notify;
+pub mod policy;
+pub mod pool;
+pub mod profile;
+pub mod reconcile;
+pub mod report;
+pub mod sender;
+pub mod settings;
+pub mod signer;
+pub mod transaction;
+pub mod tui;
+pub mod twap;
 pub mod utils;
-pub mod arbitrage;
-pub mod transaction;
\ No newline at end of file
@@ -3,24 +3,63 @@ use carbon_raydium_cpmm_decoder::RaydiumCpmmDecoder;
 use chrono::Utc;
 use clap::Parser;
 use log::{error, info, warn};
-use serde_json::{Value, json};
-use solana_client::rpc_client::RpcClient;
+use serde_json::Value;
 use solana_sdk::{pubkey::Pubkey, signer::Signer};
 use spl_associated_token_account::get_associated_token_address;
+use std::collections::BTreeMap;
 use std::fs;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use solana_amm_arb_cli::{
-    arbitrage::{Arbitrage, calculate_min_out, calculate_pnl, calculate_price, spread_bps},
+    amount::DecimalAmount,
+    analyze::{Analysis, ladder_sizes, pool_depth, round_trip},
+    arbitrage::{
+        Arbitrage, FeeConversion, QuoteGuard, SwapQuote, calculate_min_out, calculate_pnl,
+        clears_pnl_floor, mid_price, spread_bps,
+    },
     cli::{
-        Cli, Command, ConfigCmd, config_set_amount_in, config_set_keypair, config_set_pools,
-        config_set_priority_fee, config_set_rpc, config_set_simulate, config_set_slippage_bps,
-        config_set_spread_threshold_bps, default_state, load_state, save_state, state_file_path,
-        take_or_panic,
+        AppState, Cli, Command, ConfigCmd, NonceCmd, config_add_notifier, config_clear_notifiers,
+        config_export, config_get, config_import, config_set, config_set_amount_in,
+        config_set_keypair, config_set_pools, config_set_priority_fee, config_set_reference_pool,
+        config_set_rpc, config_set_simulate, config_set_slippage_bps,
+        config_set_spread_threshold_bps, config_test_notifiers, config_unset, default_state,
+        load_state, nonce_create, nonce_show, run_keypair_cmd, run_profile_cmd, save_state,
+        selected_profile, sign_offline, state_file_path, submit,
+    },
+    endpoints::{Endpoints, RpcEndpoint},
+    error::{ArbError, exit_code},
+    history::{
+        HistoryEntry, HistoryFilter, OUTCOME_ABORTED, OUTCOME_SEND_FAILED, OUTCOME_SENT,
+        OUTCOME_SIGNED, OUTCOME_SIMULATED, OUTCOME_SIMULATION_FAILED, OUTCOME_SKIPPED,
+        append_entry, history_file_path, parse_date, print_history,
     },
+    metrics::{Labels, Metrics, serve as serve_metrics},
+    nonce::DurableNonce,
+    notify::{Event, EventKind, Notifier},
+    policy::{Rule, Signals},
     pool::{PoolData, PoolValues},
-    transaction::{create_arbitrage_transaction, simulate_transaction},
-    utils::{get_missing_token_account, get_token_account_rent, load_keypair},
+    reconcile::{Reconciliation, reconcile},
+    report::{
+        ArbitrageReport, CalculationsReport, CandidatesReport, DecisionReport, DirectionReport,
+        FailedRunReport, FinalQuoteReport, FlowReport, InputsReport, MintsReport, OutputFormat,
+        PoolValuesPairReport, PoolsReport, PricesReport, REPORT_SCHEMA_VERSION, RealizedReport,
+        ReserveDriftReport, RunReport, SendReport, TokenAccountReport, TxReport, drift_raw,
+        report_schema, tx_mode,
+    },
+    sender::{SendOptions, Sender},
+    settings::{EffectiveConfig, config_file_path, load_config_file, require, resolve},
+    signer::SignerSource,
+    transaction::{
+        BuiltTransaction, ProfitGuard, create_arbitrage_transaction, encode_transaction,
+        missing_signers, simulate_transaction,
+    },
+    tui::{self, Controls, LogBuffer, Session, init_logging as init_tui_logging},
+    twap::{PoolTwap, TwapBaseline, TwapGuard},
+    utils::{TokenAccount, get_cluster_time, get_missing_token_account, get_token_account_rent},
 };
 
 const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
@@ -56,6 +95,7 @@ fn log_pool(label: &str, addr: &str, v: &PoolValues) {
     info!("    - vault_amount0: {}", v.vault_amount0);
     info!("    - protocol_fees_token0: {}", v.protocol_fees_token0);
     info!("    - fund_fees_token0: {}", v.fund_fees_token0);
+    info!("    - creator_fees_token0: {}", v.creator_fees_token0);
     info!("  • token1 (mint_out): {}", v.mint1);
     info!("    - decimals: {}", v.token1_decimals);
     info!(
@@ -66,7 +106,32 @@ fn log_pool(label: &str, addr: &str, v: &PoolValues) {
     info!("    - vault_amount1: {}", v.vault_amount1);
     info!("    - protocol_fees_token1: {}", v.protocol_fees_token1);
     info!("    - fund_fees_token1: {}", v.fund_fees_token1);
+    info!("    - creator_fees_token1: {}", v.creator_fees_token1);
     info!("  • trade_fee_rate (raw): {}", v.trade_fee_rate);
+    info!("  • protocol_fee_rate (raw): {}", v.protocol_fee_rate);
+    info!("  • fund_fee_rate (raw): {}", v.fund_fee_rate);
+    info!(
+        "  • creator_fee_rate (raw): {} (enabled: {}, fee_on: {})",
+        v.creator_fee_rate, v.enable_creator_fee, v.creator_fee_on
+    );
+}
+
+fn log_leg(tag: &str, q: &SwapQuote) {
+    info!(
+        "  {}: in {} → out {} | trade_fee {} (protocol {}, fund {}), creator_fee {} (on {})",
+        tag,
+        q.amount_in,
+        q.amount_out,
+        q.trade_fee,
+        q.protocol_fee,
+        q.fund_fee,
+        q.creator_fee,
+        if q.creator_fee_on_input {
+            "input"
+        } else {
+            "output"
+        }
+    );
 }
 
 fn log_candidate(tag: &str, arb: &Arbitrage, mint_in: &Pubkey) {
@@ -89,13 +154,21 @@ fn log_candidate(tag: &str, arb: &Arbitrage, mint_in: &Pubkey) {
         arb.total_fees, arb.total_fees_raw
     );
     info!("  rent: {} (raw {})", arb.rent, arb.rent_raw);
+    log_leg("leg 1", &arb.leg_1);
+    log_leg("leg 2", &arb.leg_2);
+    if let (Some(fees), Some(conv)) = (arb.total_fees_in_mint_raw, &arb.fee_conversion) {
+        info!(
+            "  total_fees in mint_in (raw): {} (via {})",
+            fees, conv.source
+        );
+    }
     match arb.pnl {
-        Some(p) => info!("  pnl: {}", p),
+        Some(p) => info!("  pnl: {} (raw {:?})", p, arb.pnl_raw),
         None => {
             info!("  pnl: N/A");
             if mint_in.to_string() != SOL_MINT {
                 info!(
-                    "  note: pnl unavailable because mint_in != SOL ({}); fees are denominated in SOL",
+                    "  note: pnl unavailable because no pool prices mint_in ({}) against SOL; set `config set-reference-pool`",
                     mint_in
                 );
             }
@@ -103,6 +176,12 @@ fn log_candidate(tag: &str, arb: &Arbitrage, mint_in: &Pubkey) {
     }
 }
 
+fn result_labels(pair: &[(&'static str, String)], result: &str) -> Labels {
+    let mut labels = pair.to_vec();
+    labels.push(("result", result.to_string()));
+    labels
+}
+
 /* --------------------- Decision helper --------------------- */
 
 #[allow(clippy::too_many_arguments)]
@@ -126,17 +205,15 @@ fn choose_direction<'a>(
     f64,            // first price
     f64,            // second price
 ) {
-    if arb_a_b.pnl.is_some() && arb_b_a.pnl.is_some() {
-        if arb_a_b.pnl.unwrap() > arb_b_a.pnl.unwrap() {
-            (
-                arb_a_b, "PoolA", "PoolB", pool_a, pool_b, vals_a, vals_b, price_a, price_b,
-            )
-        } else {
-            (
-                arb_b_a, "PoolB", "PoolA", pool_b, pool_a, vals_b, vals_a, price_b, price_a,
-            )
-        }
-    } else if arb_a_b.gross_profit > arb_b_a.gross_profit {
+    // Net PnL wins whenever it is known for at least one side; gross profit only breaks
+    // the tie when fees cannot be priced in mint_in at all.
+    let a_first = match (arb_a_b.pnl_raw, arb_b_a.pnl_raw) {
+        (Some(a), Some(b)) => a > b,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => arb_a_b.gross_profit > arb_b_a.gross_profit,
+    };
+    if a_first {
         (
             arb_a_b, "PoolA", "PoolB", pool_a, pool_b, vals_a, vals_b, price_a, price_b,
         )
@@ -147,34 +224,174 @@ fn choose_direction<'a>(
     }
 }
 
+// The final quote and re-quotes are held to the same limits as the first quote
+fn quote_guard<'a>(p: &'a RunParams, twap: Option<&'a TwapBaseline>) -> QuoteGuard<'a> {
+    QuoteGuard {
+        min_pnl_raw: p.min_pnl_raw,
+        policy: &p.decision_policy,
+        twap,
+    }
+}
+
+// Balance assertion for `arb`, when a guard program is configured: the round trip has to
+// pay for the fees (converted into mint_in) plus `min_pnl_raw`
+fn profit_guard(
+    p: &RunParams,
+    ata_in: &TokenAccount,
+    arb: &Arbitrage,
+) -> Result<Option<ProfitGuard>> {
+    let Some(program_id) = p.profit_guard_program else {
+        return Ok(None);
+    };
+    // Without a fee price the run never executes; only the gross profit can be guarded
+    let fees = arb.total_fees_in_mint_raw.unwrap_or(0);
+    let min_profit = fees.checked_add(p.min_pnl_raw).ok_or_else(|| {
+        ArbError::math(format!(
+            "profit guard overflow: fees {fees} + min_pnl_raw {}",
+            p.min_pnl_raw
+        ))
+    })?;
+    Ok(Some(ProfitGuard::new(
+        program_id,
+        ata_in,
+        arb.amount_in_raw,
+        min_profit,
+    )?))
+}
+
+/* --------------------- Run parameters --------------------- */
+
+// Resolved once in `main`, reused by every evaluation (one-shot or watch mode)
+struct RunParams {
+    profile: String,
+    rpc_url: String,
+    rpc_endpoints: Vec<RpcEndpoint>,
+    keypair_path: PathBuf,
+    fee_payer: Option<PathBuf>,
+    nonce_account: Option<Pubkey>,
+    profit_guard_program: Option<Pubkey>,
+    sign_only: bool,
+    send: SendOptions,
+    amount_in: DecimalAmount,
+    spread_threshold_bps: u32,
+    slippage_bps: u32,
+    min_pnl_raw: u64,
+    decision_policy: Rule,
+    twap_guard: Option<TwapGuard>,
+    priority_fee_microlamports: u64,
+    simulate_only: bool,
+    mint_in: Pubkey,
+    mint_out: Pubkey,
+    pool_a_addr: String,
+    pool_b_addr: String,
+    reference_pool: Option<String>,
+    history_path: PathBuf,
+    // Watch mode skips evaluations while a pool cannot swap instead of reporting them
+    watch: bool,
+    output: OutputFormat,
+    report_path: PathBuf,
+    sources: BTreeMap<String, String>, // key -> layer it was taken from
+    // The dashboard renders every saved report
+    reports: Option<mpsc::Sender<RunReport>>,
+}
+
 /* ===================== main ===================== */
 
-fn main() -> Result<()> {
-    // Initialize logging
-    env_logger::init();
-    let start_time = Instant::now();
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    // The dashboard owns the terminal, so its logs go to a pane instead of stderr
+    let logs = if matches!(cli.cmd, Some(Command::Tui { .. })) {
+        Some(init_tui_logging())
+    } else {
+        env_logger::init();
+        None
+    };
+    match run(cli, logs) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(exit_code(&e))
+        }
+    }
+}
+
+fn run(mut cli: Cli, logs: Option<LogBuffer>) -> Result<()> {
     info!("==========================================");
     info!("Starting solana-cpmm-arb-cli");
     info!("==========================================");
 
-    let cli = Cli::parse();
+    let cmd = cli.cmd.take();
 
     // Ensure state dir exists
-    let state_path = state_file_path()?;
-    fs::create_dir_all(state_path.parent().unwrap())
-        .with_context(|| format!("create state dir: {}", state_path.display()))?;
+    let default_state_path = state_file_path()?;
+    let state_dir = default_state_path
+        .parent()
+        .context("state path has no parent directory")?;
+    fs::create_dir_all(state_dir)
+        .with_context(|| format!("create state dir: {}", default_state_path.display()))?;
+
+    // --- Subcommands (profiles) ---
+    // Handled before loading any state so a broken profile can still be switched away from
+    if let Some(Command::Config {
+        cmd: ConfigCmd::Profile { cmd },
+    }) = cmd
+    {
+        return run_profile_cmd(cmd);
+    }
+
+    // --- Subcommands (keypairs) ---
+    if let Some(Command::Keypair { cmd }) = cmd {
+        return run_keypair_cmd(cmd);
+    }
+
+    let (profile, state_path) = selected_profile(cli.profile.as_deref())?;
+    info!("Profile: {} ({})", profile, state_path.display());
+
+    // Created from defaults when missing; a broken or too-new file is an error, never reset
+    let mut state = load_state(&profile, &state_path)?;
+
+    let history_path = history_file_path(&state_path);
+
+    // --- Subcommands (history) ---
+    if let Some(Command::History {
+        since,
+        until,
+        pool,
+        outcome,
+        limit,
+        json,
+    }) = cmd
+    {
+        let filter = HistoryFilter {
+            since: since.as_deref().map(parse_date).transpose()?,
+            until: until.as_deref().map(parse_date).transpose()?,
+            pool,
+            outcome,
+        };
+        print_history(&history_path, &filter, limit, json)?;
+        return Ok(());
+    }
 
-    // Load or initialize defaults
-    let mut state = load_state(&state_path).unwrap_or_else(|_| default_state());
+    if let Some(Command::ReportSchema) = cmd {
+        println!("{}", report_schema()?);
+        return Ok(());
+    }
 
     // --- Subcommands (config) ---
-    if let Some(Command::Config { cmd }) = cli.cmd {
+    if let Some(Command::Config { cmd }) = cmd {
         match cmd {
-            ConfigCmd::Show => {
+            ConfigCmd::Show { effective: true } => {
+                effective_config(&cli, &state, &profile)?.print()
+            }
+            ConfigCmd::Show { effective: false } => {
                 println!("{}", serde_json::to_string_pretty(&state)?);
             }
             ConfigCmd::ResetDefaults => {
-                state = default_state();
+                // A profile keeps its base; values matching the base stay inherited
+                state = AppState {
+                    base: state.base.clone(),
+                    ..default_state()
+                };
                 save_state(&state_path, &state)?;
                 println!(
                     "State reset to defaults and saved to {}",
@@ -191,102 +408,591 @@ fn main() -> Result<()> {
             ConfigCmd::SetSlippageBps => config_set_slippage_bps(&state_path, &mut state)?,
             ConfigCmd::SetPriorityFee => config_set_priority_fee(&state_path, &mut state)?,
             ConfigCmd::SetSimulate => config_set_simulate(&state_path, &mut state)?,
+            ConfigCmd::SetReferencePool => config_set_reference_pool(&state_path, &mut state)?,
+            ConfigCmd::AddNotifier => config_add_notifier(&state_path, &mut state)?,
+            ConfigCmd::ClearNotifiers => config_clear_notifiers(&state_path, &mut state)?,
+            ConfigCmd::TestNotifiers => config_test_notifiers(&state)?,
+            ConfigCmd::Get { key } => config_get(&state, key)?,
+            ConfigCmd::Set {
+                key,
+                values,
+                mint_in,
+            } => config_set(
+                &profile,
+                &state_path,
+                &mut state,
+                key,
+                &values,
+                mint_in.as_deref(),
+            )?,
+            ConfigCmd::Unset { key } => config_unset(&profile, &state_path, &mut state, key)?,
+            ConfigCmd::Import { path } => config_import(&profile, &state_path, &mut state, &path)?,
+            ConfigCmd::Export { path } => config_export(&state, path.as_deref())?,
+            ConfigCmd::Profile { .. } => unreachable!("profile commands are handled above"),
         }
         return Ok(());
     }
 
-    // --- Resolve runtime params from flags OR state ---
-    let rpc_url = take_or_panic(cli.rpc_url, state.rpc_url.clone(), "rpc-url");
-    let keypair_path = take_or_panic(cli.keypair, state.keypair_path.clone(), "keypair");
-    let amount_in = take_or_panic(cli.amount_in, state.amount_in, "amount-in");
-    let spread_threshold_bps = take_or_panic(
-        cli.spread_threshold_bps,
-        state.spread_threshold_bps,
-        "spread-threshold-bps",
-    );
-    let slippage_bps = take_or_panic(cli.slippage_bps, state.slippage_bps, "slippage-bps");
-    let priority_fee_microlamports = take_or_panic(
-        cli.priority_fee,
-        state.priority_fee_microlamports,
-        "priority-fee",
-    );
-    let simulate_only = take_or_panic(cli.simulate_only, state.simulate_only, "simulate-only");
+    // --- Resolve runtime params: defaults -> profile -> file -> env -> flags ---
+    let eff = effective_config(&cli, &state, &profile)?;
+    if let Some(Command::Sign { tx, signers }) = cmd {
+        return sign_offline(&tx, &signers);
+    }
+    let rpc_url = require(&eff.rpc_url, "rpc_url")?;
+    let keypair_path = require(&eff.keypair_path, "keypair_path")?;
+    let fee_payer_path = eff.fee_payer.as_ref().map(|s| s.value.clone());
+    let nonce_account = eff
+        .nonce_account
+        .as_ref()
+        .map(|s| {
+            s.value
+                .parse::<Pubkey>()
+                .map_err(|e| ArbError::Config(format!("invalid nonce account {}: {e}", s.value)))
+        })
+        .transpose()?;
+    let profit_guard_program = eff
+        .profit_guard_program
+        .as_ref()
+        .map(|s| {
+            s.value.parse::<Pubkey>().map_err(|e| {
+                ArbError::Config(format!("invalid profit guard program {}: {e}", s.value))
+            })
+        })
+        .transpose()?;
+
+    // --- Subcommands (durable nonce & offline transactions) ---
+    match cmd {
+        Some(Command::Submit { tx, signers }) => return submit(&rpc_url, &tx, &signers),
+        Some(Command::Nonce {
+            cmd: NonceCmd::Show { address },
+        }) => {
+            let address = address
+                .or(nonce_account.map(|a| a.to_string()))
+                .ok_or_else(|| ArbError::Config("no nonce account given or configured".into()))?;
+            return nonce_show(&rpc_url, &address);
+        }
+        Some(Command::Nonce {
+            cmd: NonceCmd::Create { save },
+        }) => {
+            let authority = load_signer(fee_payer_path.as_ref().unwrap_or(&keypair_path), false)?;
+            let account = nonce_create(&rpc_url, authority.as_ref())?;
+            if save {
+                state.nonce_account = Some(account.to_string());
+                save_state(&state_path, &state)?;
+                println!("Saved nonce_account = {account}");
+            }
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let amount_in = require(&eff.amount_in, "amount_in")?;
+    let spread_threshold_bps = require(&eff.spread_threshold_bps, "spread_threshold_bps")?;
+    let slippage_bps = require(&eff.slippage_bps, "slippage_bps")?;
+    let min_pnl_raw = require(&eff.min_pnl_raw, "min_pnl_raw")?;
+    let decision_policy = eff
+        .decision_policy
+        .as_ref()
+        .map(|s| s.value.clone())
+        .unwrap_or_else(|| Rule::from_spread_threshold(spread_threshold_bps));
+    let twap_guard = eff.twap_guard.as_ref().map(|s| s.value.clone());
+    let priority_fee_microlamports = require(
+        &eff.priority_fee_microlamports,
+        "priority_fee_microlamports",
+    )?;
+    let simulate_only = require(&eff.simulate_only, "simulate_only")?;
+
+    // Unsigned placeholders are fine when nothing is sent
+    let sign_only = cli.sign_only;
+    if sign_only && nonce_account.is_none() {
+        return Err(ArbError::Config(
+            "--sign-only needs a durable nonce account (`nonce create --save` or --nonce-account)"
+                .into(),
+        )
+        .into());
+    }
+    let allow_unsigned = sign_only || simulate_only;
 
     info!("CONFIG");
     info!("  RPC URL: {}", rpc_url);
-    info!("  Keypair: {:?}", keypair_path);
+    info!("  Keypair: {}", keypair_path.display());
     info!("  Amount In: {}", amount_in);
     info!("  Spread Threshold: {} bps", spread_threshold_bps);
     info!("  Slippage: {} bps", slippage_bps);
+    info!("  Min PnL: {} (raw mint_in)", min_pnl_raw);
+    info!("  Decision Policy: {}", decision_policy);
+    if let Some(guard) = &twap_guard {
+        info!("  TWAP Guard: {}", guard);
+    }
     info!("  Priority Fee: {} µlamports", priority_fee_microlamports);
     info!("  Simulate Only: {}", simulate_only);
 
-    let rpc = RpcClient::new(rpc_url.clone());
-    let keypair = load_keypair(&keypair_path)?;
+    let rpc_endpoints = eff
+        .rpc_endpoints
+        .as_ref()
+        .map(|s| s.value.clone())
+        .unwrap_or_default();
+    let rpcs = Endpoints::new(
+        &rpc_url,
+        &rpc_endpoints,
+        cli.hedge_after_ms.map(Duration::from_millis),
+        cli.max_slot_lag,
+    );
+    // With a single endpoint there is nothing to fail over to
+    if rpcs.len() > 1 {
+        rpcs.check_health();
+        rpcs.log_stats();
+        if cli.watch_interval_ms.is_some() || matches!(cmd, Some(Command::Tui { .. })) {
+            rpcs.spawn_monitor(Duration::from_millis(cli.health_interval_ms));
+        }
+    }
+    // Mints + pools
+    let parse_mint = |v: String, name: &str| {
+        v.parse::<Pubkey>()
+            .map_err(|e| ArbError::Config(format!("invalid {name} {v}: {e}")))
+    };
+    let mint_in = parse_mint(require(&eff.mint_in, "mint_in")?, "mint_in")?;
+    let mint_out = parse_mint(require(&eff.mint_out, "mint_out")?, "mint_out")?;
+    let pool_a_addr = require(&eff.pool_a, "pool_a")?;
+    let pool_b_addr = require(&eff.pool_b, "pool_b")?;
+
+    let reference_pool = eff.reference_pool.as_ref().map(|s| s.value.clone());
+
+    // --- Subcommands (analytics) ---
+    if let Some(Command::Analyze { sizes, format }) = cmd {
+        let analysis = analyze(
+            &rpcs,
+            &mint_in,
+            &pool_a_addr,
+            &pool_b_addr,
+            reference_pool.as_deref(),
+            amount_in,
+            &sizes,
+            priority_fee_microlamports,
+        )?;
+        print!("{}", analysis.render(format));
+        return Ok(());
+    }
+
+    let keypair = load_signer(&keypair_path, allow_unsigned)?;
     info!("Keypair loaded: {}", keypair.pubkey());
+    let fee_payer = match &fee_payer_path {
+        None => None,
+        Some(path) => {
+            let signer = load_signer(path, allow_unsigned)?;
+            info!("Fee payer loaded: {}", signer.pubkey());
+            Some(signer)
+        }
+    };
+    let keypair = keypair.as_ref();
+    let fee_payer = fee_payer.as_deref().unwrap_or(keypair);
 
-    // Mints + pools from state
-    let mint_in = state
-        .mint_in
-        .as_ref()
-        .expect("mint-in is required")
-        .parse::<Pubkey>()?;
-    let mint_out = state
-        .mint_out
-        .as_ref()
-        .expect("mint-out is required")
-        .parse::<Pubkey>()?;
-    let pool_a_addr = state.pool_a.take().expect("pool-a is required");
-    let pool_b_addr = state.pool_b.take().expect("pool-b is required");
+    let params = RunParams {
+        profile,
+        rpc_url,
+        rpc_endpoints,
+        keypair_path,
+        fee_payer: fee_payer_path,
+        nonce_account,
+        profit_guard_program,
+        sign_only,
+        send: SendOptions {
+            skip_preflight: cli.skip_preflight,
+            rebroadcast_interval: Duration::from_millis(cli.rebroadcast_interval_ms),
+            max_requotes: cli.max_requotes,
+        },
+        amount_in,
+        spread_threshold_bps,
+        slippage_bps,
+        min_pnl_raw,
+        decision_policy,
+        twap_guard,
+        priority_fee_microlamports,
+        simulate_only,
+        mint_in,
+        mint_out,
+        pool_a_addr,
+        pool_b_addr,
+        reference_pool,
+        history_path,
+        watch: cli.watch_interval_ms.is_some(),
+        output: cli.output,
+        report_path: cli.report_path.clone(),
+        sources: eff.sources(),
+        reports: None,
+    };
+    // Without a policy the threshold is the rule, so adjusting it live changes the decision
+    let threshold_is_policy = eff.decision_policy.is_none();
+    let notifier = Notifier::new(eff.notifiers.map(|n| n.value).unwrap_or_default());
+    let metrics = Metrics::new();
+    if let Some(addr) = &cli.metrics_addr {
+        serve_metrics(addr, metrics.clone())?;
+    }
+
+    // --- Dashboard ---
+    if let Some(Command::Tui { refresh_ms }) = cmd {
+        if sign_only {
+            return Err(ArbError::Config(
+                "--sign-only prints transactions on stdout and cannot run in the tui".into(),
+            )
+            .into());
+        }
+        let can_send = [Some(&params.keypair_path), params.fee_payer.as_ref()]
+            .into_iter()
+            .flatten()
+            .all(|path| SignerSource::parse(&path.to_string_lossy()).is_ok_and(|s| s.has_secret()));
+        let session = Session {
+            title: format!(
+                " solana-amm-arb-cli · {} · A={} B={} ",
+                params.profile, params.pool_a_addr, params.pool_b_addr
+            ),
+            refresh: Duration::from_millis(refresh_ms),
+            can_send,
+            threshold_is_policy,
+        };
+        let controls = Controls::new(
+            params.simulate_only,
+            params.spread_threshold_bps,
+            params.min_pnl_raw,
+        );
+        let (reports_tx, reports_rx) = mpsc::channel();
+        // Reports go to the dashboard, not stdout; unavailable pools are shown, not skipped
+        let mut params = RunParams {
+            output: OutputFormat::None,
+            watch: false,
+            reports: Some(reports_tx),
+            ..params
+        };
+        return tui::run(
+            session,
+            controls,
+            logs.unwrap_or_default(),
+            reports_rx,
+            |c| {
+                params.simulate_only = c.simulate_only;
+                params.spread_threshold_bps = c.spread_threshold_bps;
+                params.min_pnl_raw = c.min_pnl_raw;
+                if threshold_is_policy {
+                    params.decision_policy = Rule::from_spread_threshold(c.spread_threshold_bps);
+                }
+                if let Err(e) = run_once(&params, &rpcs, keypair, fee_payer, &metrics, &notifier) {
+                    error!("Evaluation failed: {:#}", e);
+                }
+            },
+        );
+    }
+
+    match cli.watch_interval_ms {
+        None => run_once(&params, &rpcs, keypair, fee_payer, &metrics, &notifier),
+        Some(interval_ms) => {
+            info!("Watch mode: evaluating every {} ms", interval_ms);
+            loop {
+                // A failed evaluation (e.g. a flaky RPC) must not stop the watcher
+                if let Err(e) = run_once(&params, &rpcs, keypair, fee_payer, &metrics, &notifier) {
+                    error!("Evaluation failed: {:#}", e);
+                }
+                thread::sleep(Duration::from_millis(interval_ms));
+            }
+        }
+    }
+}
+
+/// Load a signer source; `pubkey:` placeholders only when nothing will be sent.
+fn load_signer(path: &Path, allow_unsigned: bool) -> Result<Box<dyn Signer>> {
+    let source = SignerSource::parse(&path.to_string_lossy())?;
+    if !source.has_secret() && !allow_unsigned {
+        return Err(ArbError::Config(format!(
+            "`{source}` cannot sign; it needs --sign-only or simulate-only"
+        ))
+        .into());
+    }
+    source
+        .signer()
+        .map_err(|e| ArbError::Config(format!("load signer {source}: {e}")).into())
+}
+
+/// The profile state with the config file, `SOLANA_ARB_*` env vars and flags layered on top.
+fn effective_config(cli: &Cli, state: &AppState, profile: &str) -> Result<EffectiveConfig> {
+    let file = match config_file_path(cli.config_file.as_deref()) {
+        Some(path) => Some((load_config_file(&path)?, path)),
+        None => None,
+    };
+    resolve(
+        state,
+        profile,
+        file.as_ref().map(|(cfg, path)| (path.as_path(), cfg)),
+        cli,
+    )
+}
+
+/* ===================== analytics ===================== */
+
+/// Quote depth and round trips on the current reserves; reads pools only, signs nothing.
+#[allow(clippy::too_many_arguments)]
+fn analyze(
+    rpcs: &Endpoints,
+    mint_in: &Pubkey,
+    pool_a_addr: &str,
+    pool_b_addr: &str,
+    reference_pool: Option<&str>,
+    amount_in: DecimalAmount,
+    sizes: &[DecimalAmount],
+    priority_fee_microlamports: u64,
+) -> Result<Analysis> {
+    let pool_values = |addr: &str| -> Result<PoolValues> {
+        let pool = rpcs.call("load_pool", |rpc| {
+            PoolData::new(rpc, addr, &RaydiumCpmmDecoder)
+        })?;
+        let mut values = rpcs.call("get_pool_values", |rpc| pool.get_values(rpc))?;
+        values.normalize_pool_values(mint_in);
+        Ok(values)
+    };
+    let a = pool_values(pool_a_addr)?;
+    let b = pool_values(pool_b_addr)?;
+    let fee_reference = match reference_pool {
+        Some(addr) => {
+            FeeConversion::from_mid(&pool_values(addr)?, &format!("reference_pool:{addr}"))
+        }
+        None => None,
+    };
+
+    let decimals = a.token0_decimals;
+    let sizes_raw = if sizes.is_empty() {
+        ladder_sizes(amount_in.to_raw(decimals)?)
+    } else {
+        sizes
+            .iter()
+            .map(|s| s.to_raw(decimals))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+    let curve = |direction: &str, pool_in: &PoolValues, pool_out: &PoolValues| {
+        round_trip(
+            direction,
+            pool_in,
+            pool_out,
+            &sizes_raw,
+            priority_fee_microlamports,
+            fee_reference.as_ref(),
+        )
+    };
+    Ok(Analysis {
+        pools: vec![
+            pool_depth("A", pool_a_addr, &a, &sizes_raw)?,
+            pool_depth("B", pool_b_addr, &b, &sizes_raw)?,
+        ],
+        round_trips: vec![curve("A→B", &a, &b)?, curve("B→A", &b, &a)?],
+    })
+}
+
+/* ===================== single evaluation ===================== */
+
+fn inputs_report(p: &RunParams) -> InputsReport {
+    InputsReport {
+        pool_a: p.pool_a_addr.clone(),
+        pool_b: p.pool_b_addr.clone(),
+        rpc_url: p.rpc_url.clone(),
+        rpc_endpoints: p.rpc_endpoints.iter().map(ToString::to_string).collect(),
+        keypair_path: p.keypair_path.clone(),
+        fee_payer: p.fee_payer.clone(),
+        nonce_account: p.nonce_account.map(|a| a.to_string()),
+        profit_guard_program: p.profit_guard_program.map(|a| a.to_string()),
+        sign_only: p.sign_only,
+        amount_in: p.amount_in.to_f64(),
+        spread_threshold_bps: p.spread_threshold_bps,
+        slippage_bps: p.slippage_bps,
+        min_pnl_raw: p.min_pnl_raw,
+        decision_policy: p.decision_policy.clone(),
+        twap_guard: p.twap_guard.clone(),
+        priority_fee_microlamports: p.priority_fee_microlamports,
+        simulate_only: p.simulate_only,
+        reference_pool: p.reference_pool.clone(),
+        profile: p.profile.clone(),
+        sources: p.sources.clone(),
+    }
+}
+
+fn save_report(p: &RunParams, report: RunReport) -> Result<()> {
+    report.write(&p.report_path)?;
+    info!("Detailed report saved to: {}", p.report_path.display());
+    report.print(p.output)?;
+    if let Some(reports) = &p.reports {
+        // Only fails once the dashboard has closed
+        let _ = reports.send(report);
+    }
+    Ok(())
+}
+
+/// One evaluation. Errors are recorded in the report (and history, once a decision was
+/// made) before being returned, so their exit code reaches the caller.
+fn run_once(
+    p: &RunParams,
+    rpcs: &Arc<Endpoints>,
+    keypair: &dyn Signer,
+    fee_payer: &dyn Signer,
+    metrics: &Metrics,
+    notifier: &Notifier,
+) -> Result<()> {
+    let start_time = Instant::now();
+    let mut steps: Vec<String> = Vec::new();
+    let outcome = evaluate(
+        p, rpcs, keypair, fee_payer, metrics, notifier, start_time, &mut steps,
+    );
+    if rpcs.len() > 1 {
+        rpcs.log_stats();
+    }
+    match outcome {
+        Ok(None) => Ok(()),
+        // The full report already carries the failure
+        Ok(Some(trade_error)) => Err(trade_error.into()),
+        Err(e) => {
+            step!(steps, "ERROR: {:#}", e);
+            let report = RunReport::Failed(FailedRunReport {
+                schema_version: REPORT_SCHEMA_VERSION,
+                timestamp: Utc::now().to_rfc3339(),
+                execution_time_ms: start_time.elapsed().as_millis() as u64,
+                inputs: inputs_report(p),
+                error: (&e).into(),
+                rpc_endpoints: rpcs.stats(),
+                steps,
+            });
+            if let Err(write_err) = save_report(p, report) {
+                warn!("Failed to write error report: {:#}", write_err);
+            }
+            Err(e)
+        }
+    }
+}
+
+fn evaluate(
+    p: &RunParams,
+    rpcs: &Arc<Endpoints>,
+    keypair: &dyn Signer,
+    fee_payer: &dyn Signer,
+    metrics: &Metrics,
+    notifier: &Notifier,
+    start_time: Instant,
+    steps: &mut Vec<String>,
+) -> Result<Option<ArbError>> {
+    let amount_in = p.amount_in;
+    let spread_threshold_bps = p.spread_threshold_bps;
+    let slippage_bps = p.slippage_bps;
+    let priority_fee_microlamports = p.priority_fee_microlamports;
+    let simulate_only = p.simulate_only;
+    let mint_in = p.mint_in;
+    let mint_out = p.mint_out;
+    let pool_a_addr = &p.pool_a_addr;
+    let pool_b_addr = &p.pool_b_addr;
+    let history_path = &p.history_path;
+    let pair: Labels = vec![("pair", format!("{}-{}", pool_a_addr, pool_b_addr))];
+
+    // Quote reads may be hedged across endpoints, so they run on their own threads
+    let load_pool = |addr: &str| {
+        let addr = addr.to_string();
+        metrics
+            .time_rpc("load_pool", || {
+                rpcs.hedged("load_pool", move |rpc| {
+                    PoolData::new(rpc, &addr, &RaydiumCpmmDecoder)
+                })
+            })
+            .map(Arc::new)
+    };
+    let pool_values = |pool: &Arc<PoolData>| {
+        let pool = Arc::clone(pool);
+        metrics.time_rpc("get_pool_values", || {
+            rpcs.hedged("get_pool_values", move |rpc| pool.get_values(rpc))
+        })
+    };
 
     info!("Loading pools…");
-    let decoder = RaydiumCpmmDecoder;
-    let pool_a = PoolData::new(&rpc, &pool_a_addr, &decoder).map_err(|e| {
+    let pool_a = load_pool(pool_a_addr).map_err(|e| {
         error!("RPC error loading PoolA {}: {}", pool_a_addr, e);
         e
     })?;
-    let pool_b = PoolData::new(&rpc, &pool_b_addr, &decoder).map_err(|e| {
+    let pool_b = load_pool(pool_b_addr).map_err(|e| {
         error!("RPC error loading PoolB {}: {}", pool_b_addr, e);
         e
     })?;
 
+    // ---------- Pool status ----------
+    // A disabled or not yet opened pool rejects the swap, so nothing built on it can land
+    let now = metrics.time_rpc("get_cluster_time", || {
+        rpcs.call("get_cluster_time", get_cluster_time)
+    })?;
+    let unavailable: Vec<String> = [("A", &pool_a, pool_a_addr), ("B", &pool_b, pool_b_addr)]
+        .into_iter()
+        .flat_map(|(label, pool, addr)| {
+            pool.swap_blockers(now)
+                .into_iter()
+                .map(move |reason| format!("pool {label} {addr}: {reason}"))
+        })
+        .collect();
+    let pools_available = unavailable.is_empty();
+    if !pools_available {
+        for reason in &unavailable {
+            warn!("Pool cannot swap: {}", reason);
+            step!(steps, "Pool cannot swap: {}", reason);
+        }
+        metrics.inc("arb_pools_unavailable_total", pair.clone());
+        if p.watch {
+            info!("Skipping evaluation until both pools can swap");
+            return Ok(None);
+        }
+    }
+
     // Raw values, then normalized so that token0 == mint_in for BOTH pools
-    let mut pool_a_values = pool_a.get_values(&rpc).map_err(|e| {
+    let mut pool_a_values = pool_values(&pool_a).map_err(|e| {
         error!("RPC error fetching PoolA values: {}", e);
         e
     })?;
-    let mut pool_b_values = pool_b.get_values(&rpc).map_err(|e| {
+    let mut pool_b_values = pool_values(&pool_b).map_err(|e| {
         error!("RPC error fetching PoolB values: {}", e);
         e
     })?;
+    let quoted_at = Instant::now();
     pool_a_values.normalize_pool_values(&mint_in);
     pool_b_values.normalize_pool_values(&mint_in);
 
+    // Optional mint_in/SOL pool used to price SOL fees when neither trading pool can
+    let reference_pool_addr = p.reference_pool.clone();
+    let fee_reference = match &reference_pool_addr {
+        Some(addr) => {
+            let reference = load_pool(addr).map_err(|e| {
+                error!("RPC error loading reference pool {}: {}", addr, e);
+                e
+            })?;
+            let mut values = pool_values(&reference).map_err(|e| {
+                error!("RPC error fetching reference pool values: {}", e);
+                e
+            })?;
+            values.normalize_pool_values(&mint_in);
+            let conv = FeeConversion::from_mid(&values, &format!("reference_pool:{}", addr));
+            if conv.is_none() {
+                warn!(
+                    "Reference pool {} does not pair mint_in with SOL; ignoring it",
+                    addr
+                );
+            }
+            conv
+        }
+        None => None,
+    };
+
     // Detailed Pool Logging (now with UI reserves)
-    log_pool("Pool A", &pool_a_addr, &pool_a_values);
-    log_pool("Pool B", &pool_b_addr, &pool_b_values);
+    log_pool("Pool A", pool_a_addr, &pool_a_values);
+    log_pool("Pool B", pool_b_addr, &pool_b_values);
 
     // Prices: both pools are oriented as mint_in -> mint_out (token0 -> token1)
-    let price_a = calculate_price(
-        pool_a_values.reserve0,
-        pool_a_values.reserve1,
-        pool_a_values.token0_decimals,
-        pool_a_values.token1_decimals,
-    );
-    let price_b = calculate_price(
-        pool_b_values.reserve0,
-        pool_b_values.reserve1,
-        pool_b_values.token0_decimals,
-        pool_b_values.token1_decimals,
-    );
+    let price_a = mid_price(&pool_a_values);
+    let price_b = mid_price(&pool_b_values);
     let spread_bps_val = spread_bps(price_a, price_b);
 
     info!("Prices ({} -> {}):", pk_s(&mint_in), pk_s(&mint_out));
     info!("  Pool A price: {:.12}", price_a);
     info!("  Pool B price: {:.12}", price_b);
     info!("  Spread: {:.4} bps", spread_bps_val);
+    metrics.inc("arb_evaluations_total", pair.clone());
+    metrics.observe_spread(pair.clone(), spread_bps_val);
 
-    let mut steps: Vec<String> = Vec::new();
     step!(steps, "Pools: A={}  B={}", pool_a_addr, pool_b_addr);
     step!(
         steps,
@@ -305,16 +1011,22 @@ fn main() -> Result<()> {
     // ---------- Token accounts & rent ----------
     let ata_in_addr = get_associated_token_address(&keypair.pubkey(), &mint_in);
     let ata_out_addr = get_associated_token_address(&keypair.pubkey(), &mint_out);
-    let atas = vec![
-        get_missing_token_account(&rpc, &keypair.pubkey(), &mint_in),
-        get_missing_token_account(&rpc, &keypair.pubkey(), &mint_out),
-    ];
-    let rent_per_ata = get_token_account_rent(&rpc).map_err(|e| {
-        error!("RPC error fetching token account rent: {}", e);
-        e
+    let atas = metrics.time_rpc("get_token_accounts", || -> Result<_> {
+        let ata = |mint: &Pubkey| {
+            rpcs.call("get_token_account", |rpc| {
+                get_missing_token_account(rpc, &keypair.pubkey(), mint)
+            })
+        };
+        Ok(vec![ata(&mint_in)?, ata(&mint_out)?])
     })?;
+    let rent_per_ata = metrics
+        .time_rpc("get_rent", || rpcs.call("get_rent", get_token_account_rent))
+        .map_err(|e| ArbError::Rpc(format!("fetch token account rent: {e}")))?;
     // pay rent only for accounts that do NOT exist
-    let rent_raw = (((!atas[0].exists) as u64) + ((!atas[1].exists) as u64)) * rent_per_ata;
+    let missing_atas = ((!atas[0].exists) as u64) + ((!atas[1].exists) as u64);
+    let rent_raw = missing_atas
+        .checked_mul(rent_per_ata)
+        .ok_or_else(|| ArbError::math(format!("rent overflow: {missing_atas} x {rent_per_ata}")))?;
 
     info!("Token Accounts");
     info!("  Owner: {}", keypair.pubkey());
@@ -345,19 +1057,21 @@ fn main() -> Result<()> {
 
     // ---------- PnL both directions ----------
     let arb_a_b = calculate_pnl(
-        amount_in,
+        &amount_in,
         &pool_a_values,
         &pool_b_values,
         rent_raw,
         priority_fee_microlamports,
-    );
+        fee_reference.as_ref(),
+    )?;
     let arb_b_a = calculate_pnl(
-        amount_in,
+        &amount_in,
         &pool_b_values,
         &pool_a_values,
         rent_raw,
         priority_fee_microlamports,
-    );
+        fee_reference.as_ref(),
+    )?;
 
     info!("Arbitrage candidates (full metrics):");
     log_candidate("A → B (PoolA first, PoolB second)", &arb_a_b, &mint_in);
@@ -429,39 +1143,209 @@ fn main() -> Result<()> {
         pk_s(&mint_in)
     );
 
-    // ---------- Decision ----------
-    let is_profitable = if let Some(p) = arb_chosen.pnl {
-        p > 0.0
-    } else {
-        arb_chosen.gross_profit > 0.0
+    // ---------- TWAP ----------
+    // Averages from the pools' observation accounts; a spot price far from them points at
+    // a pool pushed within the last blocks
+    let twap = match &p.twap_guard {
+        None => None,
+        Some(guard) => {
+            let read = |pool: &PoolData| {
+                metrics.time_rpc("get_observation", || {
+                    rpcs.call("get_observation", |rpc| {
+                        PoolTwap::read(rpc, pool, &mint_in, guard.window_secs)
+                    })
+                })
+            };
+            Some(TwapBaseline {
+                guard: guard.clone(),
+                first: read(pool_in)?,
+                second: read(pool_out)?,
+            })
+        }
     };
+    let twap_report = twap.as_ref().map(|t| t.check(price_first, price_second));
+    if let Some(report) = &twap_report {
+        for pool in &report.pools {
+            step!(
+                steps,
+                "TWAP {}: spot={:.12} twap={:?} over {}s, deviation_bps={:?}",
+                pool.pool,
+                pool.spot_price,
+                pool.twap_price,
+                pool.covered_secs,
+                pool.deviation_bps
+            );
+        }
+        let unknown = report.unknown();
+        if !unknown.is_empty() {
+            warn!(
+                "No TWAP for {} (not enough observations); not checked",
+                unknown.join(", ")
+            );
+        }
+    }
+
+    // ---------- Decision ----------
+    // Without a fee price in mint_in we cannot prove net profit, so never execute,
+    // whatever the policy says
+    let is_profitable = clears_pnl_floor(arb_chosen, p.min_pnl_raw);
     let meets_spread_threshold = spread_bps_val >= spread_threshold_bps as f64;
-    let should_execute = is_profitable && meets_spread_threshold;
+    let policy = p
+        .decision_policy
+        .evaluate(&Signals::new(arb_chosen, spread_bps_val));
+    let twap_blocked = twap_report.as_ref().is_some_and(|t| t.blocked);
+    let should_execute = pools_available && is_profitable && policy.passed && !twap_blocked;
+    if meets_spread_threshold {
+        metrics.inc("arb_opportunities_total", pair.clone());
+    }
+    if let Some(pnl) = arb_chosen.pnl_raw {
+        metrics.set_gauge("arb_expected_pnl_raw", pair.clone(), pnl as f64);
+    }
 
     if !is_profitable {
         warn!(
-            "Not profitable (pnl {:?}, gross {})",
-            arb_chosen.pnl, arb_chosen.gross_profit
+            "Not profitable (pnl {:?}, gross {}, min_pnl_raw {})",
+            arb_chosen.pnl, arb_chosen.gross_profit, p.min_pnl_raw
         );
         step!(steps, "Not profitable");
     }
-    if !meets_spread_threshold {
-        warn!(
-            "Spread below threshold: {:.4} < {}",
-            spread_bps_val, spread_threshold_bps
-        );
+    if !policy.passed {
+        let failures = policy.failures().join("; ");
+        warn!("Decision policy not met: {}", failures);
+        step!(steps, "Decision policy not met: {}", failures);
+    }
+    let twap_breaches = twap_report
+        .as_ref()
+        .map(|t| t.breaches().join("; "))
+        .unwrap_or_default();
+    if !twap_breaches.is_empty() {
+        let action = if twap_blocked { "blocked" } else { "flagged" };
+        warn!("Spot deviates from TWAP ({}): {}", action, twap_breaches);
         step!(
             steps,
-            "Spread below threshold: {:.4} < {}",
-            spread_bps_val,
-            spread_threshold_bps
+            "Spot deviates from TWAP ({}): {}",
+            action,
+            twap_breaches
         );
     }
     info!("Decision: should_execute={}", should_execute);
+
+    let base_event = Event {
+        kind: EventKind::Opportunity,
+        direction: format!("{}→{}", first_label, second_label),
+        pool_a: pool_a_addr.clone(),
+        pool_b: pool_b_addr.clone(),
+        spread_bps: spread_bps_val,
+        pnl: arb_chosen.pnl,
+        signature: None,
+        detail: String::new(),
+    };
+    if should_execute {
+        notifier.notify(&base_event);
+    }
+    if !twap_breaches.is_empty() {
+        notifier.notify(&Event {
+            kind: EventKind::RiskLimit,
+            detail: format!("spot deviates from TWAP: {twap_breaches}"),
+            ..base_event.clone()
+        });
+    }
     step!(steps, "Decision should_execute={}", should_execute);
 
+    // The stored nonce changes every time it is used, so fetch it per evaluation
+    let nonce = match &p.nonce_account {
+        Some(account) if pools_available => {
+            let nonce = metrics.time_rpc("get_nonce_account", || {
+                rpcs.call("get_nonce_account", |rpc| DurableNonce::fetch(rpc, account))
+            })?;
+            if nonce.authority != fee_payer.pubkey() && nonce.authority != keypair.pubkey() {
+                return Err(ArbError::Config(format!(
+                    "nonce account {} is controlled by {}, which is neither the fee payer nor the keypair",
+                    nonce.account, nonce.authority
+                ))
+                .into());
+            }
+            step!(
+                steps,
+                "durable nonce {} (blockhash {})",
+                nonce.account,
+                nonce.blockhash
+            );
+            Some(nonce)
+        }
+        _ => None,
+    };
+
+    // ---------- Final quote ----------
+    // Reserves may have moved during the ATA, rent and nonce round-trips: re-read both
+    // vaults right before signing and quote the chosen direction again
+    let mut final_arb: Option<Arbitrage> = None;
+    let mut final_quote: Option<FinalQuoteReport> = None;
+    if should_execute && !simulate_only {
+        let (mut first, mut second) = metrics.time_rpc("get_pool_values", || -> Result<_> {
+            Ok((
+                rpcs.call("get_pool_values", |rpc| pool_in.get_values(rpc))?,
+                rpcs.call("get_pool_values", |rpc| pool_out.get_values(rpc))?,
+            ))
+        })?;
+        first.normalize_pool_values(&mint_in);
+        second.normalize_pool_values(&mint_in);
+        let arb = calculate_pnl(
+            &amount_in,
+            &first,
+            &second,
+            rent_raw,
+            priority_fee_microlamports,
+            fee_reference.as_ref(),
+        )?;
+        let spread = spread_bps(mid_price(&first), mid_price(&second));
+        let aborted = quote_guard(p, twap.as_ref()).violation(&arb, &first, &second);
+        let report = FinalQuoteReport {
+            quote_age_ms: quoted_at.elapsed().as_millis() as u64,
+            spread_bps: spread,
+            spread_drift_bps: spread - spread_bps_val,
+            amount_out_2_raw: arb.amount_out_2_raw,
+            amount_out_2_drift_raw: drift_raw(arb_chosen.amount_out_2_raw, arb.amount_out_2_raw),
+            pnl_raw: arb.pnl_raw,
+            pnl_drift_raw: arb
+                .pnl_raw
+                .zip(arb_chosen.pnl_raw)
+                .map(|(after, before)| after.saturating_sub(before)),
+            first_pool: ReserveDriftReport::new(in_vals, &first),
+            second_pool: ReserveDriftReport::new(out_vals, &second),
+            aborted: aborted.clone(),
+        };
+        step!(
+            steps,
+            "final quote after {} ms: pnl_raw={:?} (drift {:?}), spread_bps={:.4} (drift {:.4})",
+            report.quote_age_ms,
+            report.pnl_raw,
+            report.pnl_drift_raw,
+            report.spread_bps,
+            report.spread_drift_bps
+        );
+        if let Some(reason) = &aborted {
+            warn!("Final quote no longer worth trading: {}", reason);
+            step!(steps, "final quote → abort: {}", reason);
+            metrics.inc("arb_final_quote_aborts_total", pair.clone());
+            notifier.notify(&Event {
+                kind: EventKind::RiskLimit,
+                spread_bps: spread,
+                pnl: arb.pnl,
+                detail: format!("final quote: {reason}"),
+                ..base_event.clone()
+            });
+        }
+        final_arb = Some(arb);
+        final_quote = Some(report);
+    }
+    let aborted = final_quote.as_ref().and_then(|f| f.aborted.clone());
+    let execute = should_execute && aborted.is_none();
+    // What gets built: the final quote when there is one
+    let arb_final = final_arb.as_ref().unwrap_or(arb_chosen);
+
     // ---------- Slippage & tx build ----------
-    let min_out = calculate_min_out(arb_chosen.amount_out_2_raw, slippage_bps);
+    let min_out = calculate_min_out(arb_final.amount_out_2_raw, slippage_bps);
     info!(
         "Slippage protection: min_out(raw)={} (slippage_bps={})",
         min_out, slippage_bps
@@ -472,22 +1356,45 @@ fn main() -> Result<()> {
         slippage_bps,
         min_out
     );
+    let guard = profit_guard(p, &atas[0], arb_final)?;
+    if let Some(g) = &guard {
+        step!(
+            steps,
+            "profit guard {}: {} must end with >= {} (min profit {})",
+            g.program_id,
+            g.token_account,
+            g.min_balance_raw,
+            g.min_profit_raw
+        );
+    }
 
-    let tx = create_arbitrage_transaction(
-        &rpc,
-        &keypair,
-        pool_in,
-        pool_out,
-        arb_chosen.amount_in_raw,
-        arb_chosen.amount_out_1_raw,
-        atas.clone(),
-        min_out,
-        priority_fee_microlamports,
-    )
-    .map_err(|e| {
-        error!("Error building transaction: {}", e);
-        e
-    })?;
+    // A pool that cannot swap fails any transaction: none is built, simulated or sent
+    let built = if !pools_available {
+        None
+    } else {
+        Some(
+            metrics
+                .time_rpc("build_transaction", || {
+                    rpcs.call("build_transaction", |rpc| {
+                        create_arbitrage_transaction(
+                            rpc,
+                            fee_payer,
+                            keypair,
+                            pool_in,
+                            pool_out,
+                            arb_final.amount_in_raw,
+                            arb_final.amount_out_1_raw,
+                            atas.clone(),
+                            min_out,
+                            priority_fee_microlamports,
+                            nonce.as_ref(),
+                            guard.as_ref(),
+                        )
+                    })
+                })
+                .map_err(|e| ArbError::Rpc(format!("build transaction: {e}")))?,
+        )
+    };
 
     // Prepare token-account creation result flags
     let planned_create_in = !atas[0].exists;
@@ -497,69 +1404,273 @@ fn main() -> Result<()> {
     let mut tx_signature: Option<String> = None;
     let mut simulate_result: Option<Value> = None;
     let mut tx_error: Option<String> = None;
+    let mut tx_slot: Option<u64> = None;
+    let mut realized: Option<Reconciliation> = None;
+    let mut reconcile_error: Option<String> = None;
+    let mut serialized: Option<String> = None;
+    let mut send_report: Option<SendReport> = None;
+    // Replaces the evaluated candidate when an expired transaction was re-quoted
+    let mut requoted: Option<(Arbitrage, u64)> = None;
+    let mut unsigned: Vec<String> = Vec::new();
+
+    match built {
+        None => {
+            info!("Skipping transaction: a pool cannot swap");
+            step!(steps, "pool unavailable → no transaction");
+        }
+        Some(built) if p.sign_only => {
+            if execute {
+                // Placeholder signers left their slots empty; finish with `sign` / `submit`
+                let encoded = encode_transaction(&built.tx)?;
+                unsigned = missing_signers(&built.tx)
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect();
+                step!(
+                    steps,
+                    "sign_only → transaction prepared (missing signers: {})",
+                    unsigned.len()
+                );
+                if p.output == OutputFormat::None {
+                    println!("{}", encoded);
+                }
+                serialized = Some(encoded);
+            } else {
+                info!("Skipping execution");
+                step!(steps, "sign_only, execute=false → skip");
+            }
+        }
+        Some(built) if simulate_only => {
+            info!("Simulating transaction…");
+            step!(steps, "simulate_only=true → simulate");
 
-    if simulate_only {
-        info!("Simulating transaction…");
-        step!(steps, "simulate_only=true → simulate");
-
-        match simulate_transaction(&rpc, &tx) {
-            Ok(result) => {
-                // Store full structured result for the final JSON report
-                let result_json = serde_json::to_value(&result).unwrap_or(Value::Null);
-                simulate_result = Some(result_json);
-
-                if let Some(err) = result.err {
-                    // Concise error logging only (no pretty JSON dump)
-                    error!("Simulation error: {:?}", err);
-                    if let Some(units) = result.units_consumed {
-                        error!("Compute units consumed: {}", units);
+            match metrics.time_rpc("simulate_transaction", || {
+                rpcs.call("simulate_transaction", |rpc| {
+                    simulate_transaction(rpc, &built.tx)
+                })
+            }) {
+                Ok(response) => {
+                    tx_slot = Some(response.context.slot);
+                    let result = response.value;
+                    // Store full structured result for the final JSON report
+                    let result_json = serde_json::to_value(&result).unwrap_or(Value::Null);
+                    simulate_result = Some(result_json);
+
+                    if let Some(err) = result.err {
+                        // Concise error logging only (no pretty JSON dump)
+                        error!("Simulation error: {:?}", err);
+                        if let Some(units) = result.units_consumed {
+                            error!("Compute units consumed: {}", units);
+                        }
+                        if let Some(logs) = result.logs.as_ref().and_then(|v| v.last()) {
+                            // Optional: just a single hint line, not the whole payload
+                            error!("Last program log: {}", logs);
+                        }
+                        step!(steps, "simulation ERROR: {:?}", err);
+                        notifier.notify(&Event {
+                            kind: EventKind::SimulationFailed,
+                            detail: format!("{:?}", err),
+                            ..base_event.clone()
+                        });
+                        metrics.inc("arb_simulations_total", result_labels(&pair, "failed"));
+                        tx_error = Some(format!("{:?}", err));
+                    } else {
+                        // Success: concise OK line
+                        info!(
+                            "Simulation OK (units_consumed: {:?})",
+                            result.units_consumed
+                        );
+                        step!(steps, "simulation OK");
+                        metrics.inc("arb_simulations_total", result_labels(&pair, "ok"));
                     }
-                    if let Some(logs) = result.logs.as_ref().and_then(|v| v.last()) {
-                        // Optional: just a single hint line, not the whole payload
-                        error!("Last program log: {}", logs);
+                }
+                Err(e) => {
+                    tx_error = Some(e.to_string());
+                    error!("Simulation call failed: {}", e);
+                    step!(steps, "simulation ERROR: {}", e);
+                    notifier.notify(&Event {
+                        kind: EventKind::SimulationFailed,
+                        detail: e.to_string(),
+                        ..base_event.clone()
+                    });
+                    metrics.inc("arb_simulations_total", result_labels(&pair, "failed"));
+                }
+            }
+        }
+        Some(built) if execute => {
+            info!("Sending transaction…");
+            step!(steps, "simulate_only=false & should_execute=true → send");
+            let mut sender = Sender::new(rpcs, &p.send);
+            let sent = {
+                // Same direction and size, fresh reserves; only used once the blockhash expired
+                let mut requote = || -> Result<Option<BuiltTransaction>> {
+                    let mut first = rpcs.call("get_pool_values", |rpc| pool_in.get_values(rpc))?;
+                    let mut second =
+                        rpcs.call("get_pool_values", |rpc| pool_out.get_values(rpc))?;
+                    first.normalize_pool_values(&mint_in);
+                    second.normalize_pool_values(&mint_in);
+                    let arb = calculate_pnl(
+                        &amount_in,
+                        &first,
+                        &second,
+                        rent_raw,
+                        priority_fee_microlamports,
+                        fee_reference.as_ref(),
+                    )?;
+                    if let Some(reason) =
+                        quote_guard(p, twap.as_ref()).violation(&arb, &first, &second)
+                    {
+                        warn!("Re-quote no longer worth trading: {}", reason);
+                        return Ok(None);
                     }
-                    step!(steps, "simulation ERROR: {:?}", err);
-                    tx_error = Some(format!("{:?}", err));
-                } else {
-                    // Success: concise OK line
+                    let min_out = calculate_min_out(arb.amount_out_2_raw, slippage_bps);
                     info!(
-                        "Simulation OK (units_consumed: {:?})",
-                        result.units_consumed
+                        "Re-quoted: pnl(raw)={:?} min_out(raw)={}",
+                        arb.pnl_raw, min_out
                     );
-                    step!(steps, "simulation OK");
-                }
+                    let guard = profit_guard(p, &atas[0], &arb)?;
+                    let built = rpcs.call("build_transaction", |rpc| {
+                        create_arbitrage_transaction(
+                            rpc,
+                            fee_payer,
+                            keypair,
+                            pool_in,
+                            pool_out,
+                            arb.amount_in_raw,
+                            arb.amount_out_1_raw,
+                            atas.clone(),
+                            min_out,
+                            priority_fee_microlamports,
+                            None,
+                            guard.as_ref(),
+                        )
+                    })?;
+                    requoted = Some((arb, min_out));
+                    Ok(Some(built))
+                };
+                metrics.time_rpc("send_and_confirm_transaction", || {
+                    sender.send(built, &mut requote)
+                })
+            };
+            metrics.add(
+                "arb_send_attempts_total",
+                pair.clone(),
+                sender.attempts.len() as f64,
+            );
+            tx_slot = sender.landed_slot;
+            send_report = Some((&sender).into());
+            if let Some((arb, min_out)) = &requoted {
+                step!(
+                    steps,
+                    "re-quoted {} time(s): pnl_raw={:?} min_out={}",
+                    sender.requotes,
+                    arb.pnl_raw,
+                    min_out
+                );
             }
-            Err(e) => {
-                tx_error = Some(e.to_string());
-                error!("Simulation call failed: {}", e);
-                step!(steps, "simulation ERROR: {}", e);
+            let arb_sent = requoted.as_ref().map_or(arb_final, |(arb, _)| arb);
+            match sent {
+                Ok(sig) => {
+                    metrics.observe_latency(
+                        "arb_quote_to_send_seconds",
+                        pair.clone(),
+                        quoted_at.elapsed(),
+                    );
+                    metrics.inc("arb_sends_total", result_labels(&pair, "ok"));
+                    tx_signature = Some(sig.to_string());
+                    info!(
+                        "Send OK: {} (slot {:?}, {} broadcast(s))",
+                        sig,
+                        tx_slot,
+                        sender.attempts.len()
+                    );
+                    step!(steps, "send OK: {}", sig);
+
+                    // ---------- Reconcile realized vs expected ----------
+                    match metrics.time_rpc("get_transaction", || {
+                        rpcs.call("get_transaction", |rpc| {
+                            reconcile(
+                                rpc,
+                                &sig,
+                                &keypair.pubkey(),
+                                &fee_payer.pubkey(),
+                                &atas,
+                                arb_sent,
+                            )
+                        })
+                    }) {
+                        Ok(r) => {
+                            info!(
+                                "Realized: out1={} out2={} gross={} fees={} lamports pnl(raw)={:?} (expected {:?})",
+                                r.realized_amount_out_1_raw,
+                                r.realized_amount_out_2_raw,
+                                r.realized_gross_profit_raw,
+                                r.realized_fees_lamports,
+                                r.realized_pnl_raw,
+                                arb_sent.pnl_raw
+                            );
+                            info!(
+                                "Slippage: leg1 {} raw ({:.2} bps), leg2 {} raw ({:.2} bps), fees Δ {} lamports",
+                                r.slippage.leg_1_raw,
+                                r.slippage.leg_1_bps,
+                                r.slippage.leg_2_raw,
+                                r.slippage.leg_2_bps,
+                                r.slippage.fees_delta_lamports
+                            );
+                            step!(
+                                steps,
+                                "reconciled: realized pnl_raw={:?}",
+                                r.realized_pnl_raw
+                            );
+                            tx_slot = Some(r.slot);
+                            if let Some(pnl) = r.realized_pnl_raw {
+                                metrics.add_gauge("arb_realized_pnl_raw", pair.clone(), pnl as f64);
+                            }
+                            realized = Some(r);
+                        }
+                        Err(e) => {
+                            warn!("Reconciliation failed: {}", e);
+                            step!(steps, "reconcile ERROR: {}", e);
+                            reconcile_error = Some(e.to_string());
+                        }
+                    }
+
+                    let realized_pnl = realized
+                        .as_ref()
+                        .and_then(|r| r.realized_pnl_raw)
+                        .map(|pnl| pnl as f64 / 10f64.powi(in_vals.token0_decimals as i32));
+                    notifier.notify(&Event {
+                        kind: EventKind::TradeLanded,
+                        pnl: realized_pnl.or(arb_sent.pnl),
+                        signature: Some(sig.to_string()),
+                        ..base_event.clone()
+                    });
+                }
+                Err(e) => {
+                    tx_error = Some(e.to_string());
+                    error!("Send error: {}", e);
+                    step!(steps, "send ERROR: {}", e);
+                    notifier.notify(&Event {
+                        kind: EventKind::TradeFailed,
+                        detail: e.to_string(),
+                        ..base_event.clone()
+                    });
+                    metrics.inc("arb_sends_total", result_labels(&pair, "failed"));
+                }
             }
         }
-    } else if should_execute {
-        info!("Sending transaction…");
-        step!(steps, "simulate_only=false & should_execute=true → send");
-        match rpc.send_and_confirm_transaction(&tx) {
-            Ok(sig) => {
-                tx_signature = Some(sig.to_string());
-                info!("Send OK: {}", sig);
-                step!(steps, "send OK: {}", sig);
-            }
-            Err(e) => {
-                tx_error = Some(e.to_string());
-                error!("Send error: {}", e);
-                step!(steps, "send ERROR: {}", e);
-            }
+        Some(_) => {
+            info!("Skipping execution");
+            step!(steps, "skip execution");
         }
-    } else {
-        info!("Skipping execution");
-        step!(steps, "skip execution");
     }
 
     // Whether ATAs actually created now (only true if planned && we actually sent successfully)
     let actually_created_in = planned_create_in && !simulate_only && tx_signature.is_some();
     let actually_created_out = planned_create_out && !simulate_only && tx_signature.is_some();
 
-    let creation_status_in = if simulate_only && planned_create_in {
+    let simulated = simulate_only && pools_available;
+    let creation_status_in = if simulated && planned_create_in {
         "would_create_in_simulation"
     } else if !simulate_only && planned_create_in && tx_signature.is_some() {
         "created_now"
@@ -569,7 +1680,7 @@ fn main() -> Result<()> {
         "skipped_no_send"
     };
 
-    let creation_status_out = if simulate_only && planned_create_out {
+    let creation_status_out = if simulated && planned_create_out {
         "would_create_in_simulation"
     } else if !simulate_only && planned_create_out && tx_signature.is_some() {
         "created_now"
@@ -579,148 +1690,195 @@ fn main() -> Result<()> {
         "skipped_no_send"
     };
 
-    // ---------- JSON report (now includes reserve*_ui) ----------
+    // ---------- Report ----------
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
 
-    let report = json!({
-        "timestamp": Utc::now().to_rfc3339(),
-        "execution_time_ms": execution_time_ms,
-        "inputs": {
-            "rpc_url": rpc_url,
-            "keypair_path": keypair_path,
-            "amount_in": amount_in,
-            "spread_threshold_bps": spread_threshold_bps,
-            "slippage_bps": slippage_bps,
-            "priority_fee_microlamports": priority_fee_microlamports,
-            "simulate_only": simulate_only,
-        },
-        "mints": { "mint_in": mint_in.to_string(), "mint_out": mint_out.to_string() },
-        "pools": {
-            "pool_a": pool_a_addr,
-            "pool_b": pool_b_addr,
-            "direction": {
-                "first_label": first_label,
-                "second_label": second_label,
-                "first": pool_in.pool_id.to_string(),
-                "second": pool_out.pool_id.to_string()
+    let token_account = |mint: &Pubkey,
+                         ata: &Pubkey,
+                         existed_before: bool,
+                         planned: bool,
+                         created: bool,
+                         status: &str| TokenAccountReport {
+        mint: mint.to_string(),
+        owner: keypair.pubkey().to_string(),
+        ata: ata.to_string(),
+        existed_before,
+        planned_to_create_now: planned,
+        actually_created_now: created,
+        creation_status: status.to_string(),
+    };
+
+    // A failed simulation or send, or an aborted final quote, sets the exit code after the
+    // report and history are written
+    let trade_error = match &aborted {
+        Some(reason) => Some(ArbError::Risk(format!("final quote: {reason}"))),
+        None => tx_error.as_ref().map(|e| {
+            if simulate_only {
+                ArbError::Simulation(e.clone())
+            } else {
+                ArbError::Send(e.clone())
             }
+        }),
+    };
+
+    let prepared = serialized.is_some();
+    // The last re-quote is what went out; report and record that, not the first quote
+    let (arb_sent, min_out_sent) = requoted
+        .as_ref()
+        .map_or((arb_final, min_out), |(arb, min_out)| (arb, *min_out));
+    let report = ArbitrageReport {
+        schema_version: REPORT_SCHEMA_VERSION,
+        timestamp: Utc::now().to_rfc3339(),
+        execution_time_ms,
+        inputs: inputs_report(p),
+        mints: MintsReport {
+            mint_in: mint_in.to_string(),
+            mint_out: mint_out.to_string(),
         },
-        "prices": { "first": price_first, "second": price_second, "spread_bps": spread_bps_val },
-        "pool_values": {
-            "first": {
-                "mint0": in_vals.mint0.to_string(),
-                "mint1": in_vals.mint1.to_string(),
-                "reserve0": in_vals.reserve0,
-                "reserve1": in_vals.reserve1,
-                "reserve0_ui": ui_amount(in_vals.reserve0, in_vals.token0_decimals),
-                "reserve1_ui": ui_amount(in_vals.reserve1, in_vals.token1_decimals),
-                "vault_amount0": in_vals.vault_amount0,
-                "vault_amount1": in_vals.vault_amount1,
-                "protocol_fees_token0": in_vals.protocol_fees_token0,
-                "protocol_fees_token1": in_vals.protocol_fees_token1,
-                "fund_fees_token0": in_vals.fund_fees_token0,
-                "fund_fees_token1": in_vals.fund_fees_token1,
-                "token0_decimals": in_vals.token0_decimals,
-                "token1_decimals": in_vals.token1_decimals,
-                "trade_fee_rate": in_vals.trade_fee_rate
+        pools: PoolsReport {
+            pool_a: pool_a_addr.clone(),
+            pool_b: pool_b_addr.clone(),
+            direction: DirectionReport {
+                first_label: first_label.to_string(),
+                second_label: second_label.to_string(),
+                first: pool_in.pool_id.to_string(),
+                second: pool_out.pool_id.to_string(),
             },
-            "second": {
-                "mint0": out_vals.mint0.to_string(),
-                "mint1": out_vals.mint1.to_string(),
-                "reserve0": out_vals.reserve0,
-                "reserve1": out_vals.reserve1,
-                "reserve0_ui": ui_amount(out_vals.reserve0, out_vals.token0_decimals),
-                "reserve1_ui": ui_amount(out_vals.reserve1, out_vals.token1_decimals),
-                "vault_amount0": out_vals.vault_amount0,
-                "vault_amount1": out_vals.vault_amount1,
-                "protocol_fees_token0": out_vals.protocol_fees_token0,
-                "protocol_fees_token1": out_vals.protocol_fees_token1,
-                "fund_fees_token0": out_vals.fund_fees_token0,
-                "fund_fees_token1": out_vals.fund_fees_token1,
-                "token0_decimals": out_vals.token0_decimals,
-                "token1_decimals": out_vals.token1_decimals,
-                "trade_fee_rate": out_vals.trade_fee_rate
-            }
         },
-        "flow": {
-            "amount_in": amount_in,                // mint_in
-            "amount_out_after_first": out1,        // mint_out
-            "amount_out_after_second": out2        // back to mint_in
+        prices: PricesReport {
+            first: price_first,
+            second: price_second,
+            spread_bps: spread_bps_val,
         },
-        "arbitrage_candidates": {
-            "A_to_B": {
-                "amount_out_1": arb_a_b.amount_out_1,
-                "amount_out_2": arb_a_b.amount_out_2,
-                "gross_profit": arb_a_b.gross_profit,
-                "total_fees": arb_a_b.total_fees,
-                "rent": arb_a_b.rent,
-                "pnl": arb_a_b.pnl
-            },
-            "B_to_A": {
-                "amount_out_1": arb_b_a.amount_out_1,
-                "amount_out_2": arb_b_a.amount_out_2,
-                "gross_profit": arb_b_a.gross_profit,
-                "total_fees": arb_b_a.total_fees,
-                "rent": arb_b_a.rent,
-                "pnl": arb_b_a.pnl
-            }
+        pool_values: PoolValuesPairReport {
+            first: in_vals.into(),
+            second: out_vals.into(),
         },
-        "calculations": {
-            "amount_in_raw": arb_chosen.amount_in_raw,
-            "amount_out_1_raw": arb_chosen.amount_out_1_raw,
-            "amount_out_2_raw": arb_chosen.amount_out_2_raw,
-            "gross_profit": arb_chosen.gross_profit,
-            "gross_profit_raw": arb_chosen.gross_profit_raw,
-            "total_fees": arb_chosen.total_fees,
-            "total_fees_raw": arb_chosen.total_fees_raw,
-            "rent": arb_chosen.rent,
-            "rent_raw": arb_chosen.rent_raw,
-            "pnl": arb_chosen.pnl,
-            "min_out_raw": min_out
+        flow: FlowReport {
+            amount_in: amount_in.to_f64(),
+            amount_out_after_first: out1,
+            amount_out_after_second: out2,
         },
-        "decision": {
-            "is_profitable": is_profitable,
-            "meets_spread_threshold": meets_spread_threshold,
-            "should_execute": should_execute,
-            "chosen_direction": format!("{}→{}", first_label, second_label)
+        arbitrage_candidates: CandidatesReport {
+            a_to_b: (&arb_a_b).into(),
+            b_to_a: (&arb_b_a).into(),
         },
-        "token_accounts": [
-            {
-                "mint": mint_in.to_string(),
-                "owner": keypair.pubkey().to_string(),
-                "ata": ata_in_addr.to_string(),
-                "existed_before": atas[0].exists,
-                "planned_to_create_now": planned_create_in,
-                "actually_created_now": actually_created_in,
-                "creation_status": creation_status_in
-            },
-            {
-                "mint": mint_out.to_string(),
-                "owner": keypair.pubkey().to_string(),
-                "ata": ata_out_addr.to_string(),
-                "existed_before": atas[1].exists,
-                "planned_to_create_now": planned_create_out,
-                "actually_created_now": actually_created_out,
-                "creation_status": creation_status_out
-            }
+        calculations: CalculationsReport::new(arb_sent, min_out_sent),
+        decision: DecisionReport {
+            is_profitable,
+            meets_spread_threshold,
+            unavailable,
+            policy,
+            twap: twap_report,
+            should_execute,
+            chosen_direction: format!("{}→{}", first_label, second_label),
+        },
+        final_quote,
+        token_accounts: vec![
+            token_account(
+                &mint_in,
+                &ata_in_addr,
+                atas[0].exists,
+                planned_create_in,
+                actually_created_in,
+                creation_status_in,
+            ),
+            token_account(
+                &mint_out,
+                &ata_out_addr,
+                atas[1].exists,
+                planned_create_out,
+                actually_created_out,
+                creation_status_out,
+            ),
         ],
-        "tx": {
-            "mode": if simulate_only { "simulate" } else if should_execute { "send" } else { "skip" },
-            "signature": tx_signature,
-            "simulate_result": simulate_result,
-            "error": tx_error
+        tx: TxReport {
+            mode: tx_mode(
+                aborted.is_some(),
+                pools_available,
+                p.sign_only,
+                simulate_only,
+                should_execute,
+            )
+            .to_string(),
+            fee_payer: fee_payer.pubkey().to_string(),
+            nonce_account: nonce.as_ref().map(|n| n.account.to_string()),
+            profit_guard: guard.as_ref().map(Into::into),
+            serialized,
+            missing_signers: unsigned,
+            signature: tx_signature.clone(),
+            slot: tx_slot,
+            simulate_result,
+            send: send_report,
+            error: tx_error.clone(),
         },
-        "steps": steps
-    });
+        realized: realized.as_ref().map(|r| RealizedReport::new(r, arb_sent)),
+        reconcile_error,
+        error: trade_error.as_ref().map(Into::into),
+        rpc_endpoints: rpcs.stats(),
+        steps: std::mem::take(steps),
+    };
+
+    // Save & print report
+    save_report(p, RunReport::Completed(Box::new(report)))?;
 
-    // Save & print JSON report
-    let json_str = serde_json::to_string_pretty(&report)?;
-    fs::write("arbitrage_result.json", &json_str)?;
-    info!("Detailed report saved to: arbitrage_result.json");
-    // println!("{}", json_str);
+    // ---------- History ----------
+    let outcome = if aborted.is_some() {
+        OUTCOME_ABORTED
+    } else if !pools_available {
+        OUTCOME_SKIPPED
+    } else if p.sign_only {
+        if prepared {
+            OUTCOME_SIGNED
+        } else {
+            OUTCOME_SKIPPED
+        }
+    } else if simulate_only {
+        if tx_error.is_some() {
+            OUTCOME_SIMULATION_FAILED
+        } else {
+            OUTCOME_SIMULATED
+        }
+    } else if should_execute {
+        if tx_signature.is_some() {
+            OUTCOME_SENT
+        } else {
+            OUTCOME_SEND_FAILED
+        }
+    } else {
+        OUTCOME_SKIPPED
+    };
+    let entry = HistoryEntry {
+        timestamp: Utc::now(),
+        pool_a: pool_a_addr.clone(),
+        pool_b: pool_b_addr.clone(),
+        mint_in: mint_in.to_string(),
+        mint_out: mint_out.to_string(),
+        mint_in_decimals: in_vals.token0_decimals,
+        direction: format!("{}→{}", first_label, second_label),
+        spread_bps: spread_bps_val,
+        should_execute,
+        simulate_only,
+        outcome: outcome.to_string(),
+        signature: tx_signature.clone(),
+        slot: tx_slot,
+        amount_in_raw: arb_final.amount_in_raw,
+        expected_amount_out_raw: arb_sent.amount_out_2_raw,
+        min_out_raw: min_out_sent,
+        expected_pnl_raw: arb_sent.pnl_raw,
+        total_fees_lamports: arb_sent.total_fees_raw,
+        realized_amount_out_raw: realized.as_ref().map(|r| r.realized_amount_out_2_raw),
+        realized_pnl_raw: realized.as_ref().and_then(|r| r.realized_pnl_raw),
+        realized_fees_lamports: realized.as_ref().map(|r| r.realized_fees_lamports),
+        error: tx_error.clone().or_else(|| aborted.clone()),
+    };
+    match append_entry(history_path, &entry) {
+        Ok(()) => info!("History appended to: {}", history_path.display()),
+        Err(e) => warn!("Failed to append history: {}", e),
+    }
 
     let execution_time_ms = start_time.elapsed().as_millis() as u64;
     info!("Total execution time: {} ms", execution_time_ms);
     info!("==========================================");
-    Ok(())
+    Ok(trade_error)
 }
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use log::{info, warn};
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// Seconds; covers fast local nodes up to slow confirmations
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+];
+const SPREAD_BPS_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0];
+
+pub type Labels = Vec<(&'static str, String)>;
+type Key = (&'static str, Labels);
+
+struct Histogram {
+    buckets: &'static [f64],
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(buckets: &'static [f64]) -> Self {
+        Self {
+            buckets,
+            counts: vec![0; buckets.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, v: f64) {
+        for (i, b) in self.buckets.iter().enumerate() {
+            if v <= *b {
+                self.counts[i] += 1;
+            }
+        }
+        self.sum += v;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<Key, f64>,
+    gauges: BTreeMap<Key, f64>,
+    histograms: BTreeMap<Key, Histogram>,
+}
+
+/// In-process metric registry rendered in the Prometheus text exposition format.
+#[derive(Default)]
+pub struct Metrics {
+    inner: Mutex<Registry>,
+}
+
+fn render_labels(labels: &[(&'static str, String)], extra: Option<(&str, String)>) -> String {
+    let mut parts: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    if let Some((k, v)) = extra {
+        parts.push(format!("{}=\"{}\"", k, v));
+    }
+    if parts.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", parts.join(","))
+    }
+}
+
+fn help(name: &str) -> &'static str {
+    match name {
+        "arb_evaluations_total" => "Pool pair evaluations performed",
+        "arb_spread_bps" => "Mid-price spread observed between the two pools, in bps",
+        "arb_opportunities_total" => "Evaluations whose spread met spread_threshold_bps",
+        "arb_pools_unavailable_total" => {
+            "Evaluations that found a pool with swaps disabled or not open yet"
+        }
+        "arb_simulations_total" => "Transaction simulations by result",
+        "arb_sends_total" => "Transaction sends by result",
+        "arb_send_attempts_total" => "Broadcasts of signed transactions, including rebroadcasts",
+        "arb_final_quote_aborts_total" => {
+            "Sends aborted because the final re-quote fell below min_pnl_raw, failed the decision policy or strayed from the TWAP"
+        }
+        "arb_realized_pnl_raw" => "Cumulative realized PnL of reconciled trades, raw mint_in units",
+        "arb_expected_pnl_raw" => "Expected PnL of the last evaluation, raw mint_in units",
+        "arb_rpc_latency_seconds" => "Latency of RPC round-trips by method",
+        "arb_quote_to_send_seconds" => "Time from reading pool reserves to send confirmation",
+        _ => "",
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    pub fn inc(&self, name: &'static str, labels: Labels) {
+        self.add(name, labels, 1.0);
+    }
+
+    pub fn add(&self, name: &'static str, labels: Labels, v: f64) {
+        let mut r = self.inner.lock().unwrap();
+        *r.counters.entry((name, labels)).or_default() += v;
+    }
+
+    pub fn set_gauge(&self, name: &'static str, labels: Labels, v: f64) {
+        let mut r = self.inner.lock().unwrap();
+        r.gauges.insert((name, labels), v);
+    }
+
+    pub fn add_gauge(&self, name: &'static str, labels: Labels, v: f64) {
+        let mut r = self.inner.lock().unwrap();
+        *r.gauges.entry((name, labels)).or_default() += v;
+    }
+
+    pub fn observe_latency(&self, name: &'static str, labels: Labels, d: Duration) {
+        self.observe(name, labels, LATENCY_BUCKETS, d.as_secs_f64());
+    }
+
+    pub fn observe_spread(&self, labels: Labels, bps: f64) {
+        self.observe("arb_spread_bps", labels, SPREAD_BPS_BUCKETS, bps);
+    }
+
+    fn observe(&self, name: &'static str, labels: Labels, buckets: &'static [f64], v: f64) {
+        let mut r = self.inner.lock().unwrap();
+        r.histograms
+            .entry((name, labels))
+            .or_insert_with(|| Histogram::new(buckets))
+            .observe(v);
+    }
+
+    /// Time an RPC round-trip and record it under `arb_rpc_latency_seconds{method}`.
+    pub fn time_rpc<T>(&self, method: &'static str, f: impl FnOnce() -> T) -> T {
+        let start = std::time::Instant::now();
+        let out = f();
+        self.observe_latency(
+            "arb_rpc_latency_seconds",
+            vec![("method", method.to_string())],
+            start.elapsed(),
+        );
+        out
+    }
+
+    pub fn render(&self) -> String {
+        let r = self.inner.lock().unwrap();
+        let mut out = String::new();
+        let mut last = "";
+
+        let mut header = |out: &mut String, name: &'static str, kind: &str| {
+            if name != last {
+                let _ = writeln!(out, "# HELP {} {}", name, help(name));
+                let _ = writeln!(out, "# TYPE {} {}", name, kind);
+                last = name;
+            }
+        };
+
+        for ((name, labels), v) in &r.counters {
+            header(&mut out, *name, "counter");
+            let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), v);
+        }
+        for ((name, labels), v) in &r.gauges {
+            header(&mut out, *name, "gauge");
+            let _ = writeln!(out, "{}{} {}", name, render_labels(labels, None), v);
+        }
+        for ((name, labels), h) in &r.histograms {
+            header(&mut out, *name, "histogram");
+            for (b, c) in h.buckets.iter().zip(&h.counts) {
+                let l = render_labels(labels, Some(("le", b.to_string())));
+                let _ = writeln!(out, "{}_bucket{} {}", name, l, c);
+            }
+            let l = render_labels(labels, Some(("le", "+Inf".to_string())));
+            let _ = writeln!(out, "{}_bucket{} {}", name, l, h.count);
+            let l = render_labels(labels, None);
+            let _ = writeln!(out, "{}_sum{} {}", name, l, h.sum);
+            let _ = writeln!(out, "{}_count{} {}", name, l, h.count);
+        }
+        out
+    }
+}
+
+fn handle(mut stream: TcpStream, metrics: &Metrics) -> Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", metrics.render())
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Serve `GET /metrics` on `addr` from a background thread.
+pub fn serve(addr: &str, metrics: Arc<Metrics>) -> Result<()> {
+    let listener = TcpListener::bind(addr).with_context(|| format!("bind metrics on {addr}"))?;
+    info!("Metrics endpoint: http://{}/metrics", addr);
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(s) => {
+                    if let Err(e) = handle(s, &metrics) {
+                        warn!("metrics request failed: {}", e);
+                    }
+                }
+                Err(e) => warn!("metrics accept failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
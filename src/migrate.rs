@@ -0,0 +1,148 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::{cli::AppState, error::ArbError};
+
+/// Layout written by this build. Bump it and append a step to `MIGRATIONS` whenever a
+/// stored field is added, renamed or reinterpreted.
+///
+/// Purely additive `#[serde(default)]` fields get a bump too, although this build would read
+/// the older file without one: an older build ignores fields it does not know and drops them
+/// on its next save, so a `twap_guard` or `nonce_account` would silently vanish. The bump
+/// makes such a build refuse the file instead, at the cost of one backup on upgrade.
+pub const STATE_SCHEMA_VERSION: u32 = 9;
+
+type Migration = fn(&mut Map<String, Value>);
+
+// MIGRATIONS[n] upgrades a version-n file to version n + 1. Steps for additive fields only
+// spell out the default they load with.
+const MIGRATIONS: &[Migration] = &[
+    v0_to_v1, v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8, v8_to_v9,
+];
+
+// v0: files written before `schema_version` existed. The oldest of them have no
+// `reference_pool` or `notifiers`.
+fn v0_to_v1(obj: &mut Map<String, Value>) {
+    obj.entry("reference_pool").or_insert(Value::Null);
+    if obj.get("notifiers").is_none_or(Value::is_null) {
+        obj.insert("notifiers".into(), Value::Array(Vec::new()));
+    }
+}
+
+// v2 adds `fee_payer`; unset keeps the keypair paying its own fees.
+fn v1_to_v2(obj: &mut Map<String, Value>) {
+    obj.entry("fee_payer").or_insert(Value::Null);
+}
+
+// v3 adds `nonce_account`; unset builds transactions on a recent blockhash.
+fn v2_to_v3(obj: &mut Map<String, Value>) {
+    obj.entry("nonce_account").or_insert(Value::Null);
+}
+
+// v4 adds `rpc_endpoints`; empty keeps `rpc_url` as the only endpoint.
+fn v3_to_v4(obj: &mut Map<String, Value>) {
+    if obj.get("rpc_endpoints").is_none_or(Value::is_null) {
+        obj.insert("rpc_endpoints".into(), Value::Array(Vec::new()));
+    }
+}
+
+// v5 adds `min_pnl_raw`; unset falls back to the shipped floor of 0 (any profit).
+fn v4_to_v5(obj: &mut Map<String, Value>) {
+    obj.entry("min_pnl_raw").or_insert(Value::Null);
+}
+
+// v6 adds `profit_guard_program`; unset sends without the on-chain balance assertion.
+fn v5_to_v6(obj: &mut Map<String, Value>) {
+    obj.entry("profit_guard_program").or_insert(Value::Null);
+}
+
+// v7 stores `amount_in` as an exact decimal string instead of a JSON number. Rust prints
+// the shortest decimal that reads back as the same f64, i.e. the amount as it was typed.
+fn v6_to_v7(obj: &mut Map<String, Value>) {
+    let amount = obj.get("amount_in").and_then(|v| match v {
+        Value::Number(n) => n
+            .as_u64()
+            .map(|n| n.to_string())
+            .or_else(|| n.as_f64().filter(|f| *f >= 0.0).map(|f| f.to_string())),
+        _ => None,
+    });
+    if let Some(amount) = amount {
+        obj.insert("amount_in".into(), Value::String(amount));
+    }
+}
+
+// v8 adds `decision_policy`; unset trades when the mid spread reaches spread_threshold_bps.
+fn v7_to_v8(obj: &mut Map<String, Value>) {
+    obj.entry("decision_policy").or_insert(Value::Null);
+}
+
+// v9 adds `twap_guard`; unset never reads the pools' observation accounts.
+fn v8_to_v9(obj: &mut Map<String, Value>) {
+    obj.entry("twap_guard").or_insert(Value::Null);
+}
+
+/// On-disk form of `AppState`.
+#[derive(Serialize)]
+struct StoredState<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    state: &'a AppState,
+}
+
+pub fn to_stored_json(st: &AppState) -> Result<String> {
+    Ok(serde_json::to_string_pretty(&StoredState {
+        schema_version: STATE_SCHEMA_VERSION,
+        state: st,
+    })?)
+}
+
+/// Parse a stored state, upgrading it to `STATE_SCHEMA_VERSION` first.
+/// Returns the version it was migrated from, if it was older.
+pub fn parse_state(data: &[u8], origin: &Path) -> Result<(AppState, Option<u32>)> {
+    let invalid = |what: String| {
+        ArbError::Config(format!(
+            "state file {} {what}; fix it, or move it aside to start again from defaults",
+            origin.display()
+        ))
+    };
+    let mut value: Value =
+        serde_json::from_slice(data).map_err(|e| invalid(format!("is not valid JSON: {e}")))?;
+    let obj = value
+        .as_object_mut()
+        .ok_or_else(|| invalid("is not a JSON object".to_string()))?;
+
+    let version = match obj.remove("schema_version") {
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .ok_or_else(|| invalid(format!("has an invalid schema_version {v}")))?,
+    };
+    if version > STATE_SCHEMA_VERSION {
+        return Err(ArbError::Config(format!(
+            "state file {} has schema_version {version}, newer than this build supports ({STATE_SCHEMA_VERSION}); upgrade solana-amm-arb-cli",
+            origin.display()
+        ))
+        .into());
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(obj);
+    }
+
+    let st = serde_json::from_value(value)
+        .map_err(|e| invalid(format!("does not match the state layout: {e}")))?;
+    Ok((st, (version < STATE_SCHEMA_VERSION).then_some(version)))
+}
+
+/// Where the pre-migration copy of `path` is kept, e.g. `state.json.v0-20260101T120000Z.bak`.
+pub fn backup_path(path: &Path, from_version: u32) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "state.json".to_string());
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    path.with_file_name(format!("{name}.v{from_version}-{stamp}.bak"))
+}
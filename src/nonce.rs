@@ -0,0 +1,55 @@
+use anyhow::Result;
+use solana_client::{nonce_utils, rpc_client::RpcClient};
+use solana_sdk::{
+    commitment_config::CommitmentConfig, hash::Hash, instruction::Instruction, nonce,
+    pubkey::Pubkey, system_instruction,
+};
+
+use crate::error::ArbError;
+
+/// A durable nonce account as used to build a transaction: its stored blockhash replaces
+/// the recent blockhash, so the transaction stays valid until the nonce is advanced.
+#[derive(Debug, Clone)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    pub blockhash: Hash,
+    pub lamports_per_signature: u64,
+}
+
+impl DurableNonce {
+    pub fn fetch(rpc: &RpcClient, account: &Pubkey) -> Result<Self> {
+        let acc =
+            nonce_utils::get_account_with_commitment(rpc, account, CommitmentConfig::confirmed())
+                .map_err(|e| ArbError::Rpc(format!("fetch nonce account {account}: {e}")))?;
+        let data = nonce_utils::data_from_account(&acc)
+            .map_err(|e| ArbError::Decode(format!("nonce account {account}: {e}")))?;
+        Ok(Self {
+            account: *account,
+            authority: data.authority,
+            blockhash: data.blockhash(),
+            lamports_per_signature: data.fee_calculator.lamports_per_signature,
+        })
+    }
+
+    /// Must be the first instruction of the transaction.
+    pub fn advance_instruction(&self) -> Instruction {
+        system_instruction::advance_nonce_account(&self.account, &self.authority)
+    }
+}
+
+/// Lamports needed to keep a nonce account rent-exempt.
+pub fn nonce_rent(rpc: &RpcClient) -> Result<u64> {
+    rpc.get_minimum_balance_for_rent_exemption(nonce::State::size())
+        .map_err(|e| ArbError::Rpc(format!("fetch nonce account rent: {e}")).into())
+}
+
+/// Instructions creating `account` as a nonce account controlled by `authority`.
+pub fn create_nonce_instructions(
+    funder: &Pubkey,
+    account: &Pubkey,
+    authority: &Pubkey,
+    lamports: u64,
+) -> Vec<Instruction> {
+    system_instruction::create_nonce_account(funder, account, authority, lamports)
+}
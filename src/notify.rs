@@ -0,0 +1,216 @@
+use anyhow::{Result, bail};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::{
+    process::Command,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Opportunity,
+    TradeLanded,
+    TradeFailed,
+    SimulationFailed,
+    RiskLimit,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 5] = [
+        EventKind::Opportunity,
+        EventKind::TradeLanded,
+        EventKind::TradeFailed,
+        EventKind::SimulationFailed,
+        EventKind::RiskLimit,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::Opportunity => "opportunity",
+            EventKind::TradeLanded => "trade_landed",
+            EventKind::TradeFailed => "trade_failed",
+            EventKind::SimulationFailed => "simulation_failed",
+            EventKind::RiskLimit => "risk_limit",
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            EventKind::Opportunity => {
+                "Opportunity {direction}: spread {spread_bps} bps, expected pnl {pnl}"
+            }
+            EventKind::TradeLanded => {
+                "Trade landed {direction}: spread {spread_bps} bps, pnl {pnl} ({signature})"
+            }
+            EventKind::TradeFailed => "Trade FAILED {direction}: {detail}",
+            EventKind::SimulationFailed => "Simulation failed {direction}: {detail}",
+            EventKind::RiskLimit => "Risk limit tripped {direction}: {detail}",
+        }
+    }
+}
+
+// Opportunities are frequent in watch mode, so they are opt-in
+fn default_events() -> Vec<EventKind> {
+    vec![
+        EventKind::TradeLanded,
+        EventKind::TradeFailed,
+        EventKind::SimulationFailed,
+        EventKind::RiskLimit,
+    ]
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SinkConfig {
+    /// POST the full event as JSON
+    Webhook { url: String },
+    /// Slack incoming webhook (`{"text": ...}`)
+    Slack { url: String },
+    /// Discord webhook (`{"content": ...}`)
+    Discord { url: String },
+    /// Run a local program; the event is passed in `ARB_*` environment variables
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct NotifierConfig {
+    #[serde(flatten)]
+    pub sink: SinkConfig,
+    #[serde(default = "default_events")]
+    pub events: Vec<EventKind>,
+    // Placeholders: {event} {direction} {pool_a} {pool_b} {spread_bps} {pnl} {signature} {detail}
+    #[serde(default)]
+    pub template: Option<String>,
+    // Minimum seconds between two messages on this sink; extra events are dropped
+    #[serde(default)]
+    pub min_interval_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Event {
+    pub kind: EventKind,
+    pub direction: String, // e.g. "PoolA→PoolB"
+    pub pool_a: String,
+    pub pool_b: String,
+    pub spread_bps: f64,
+    pub pnl: Option<f64>, // in mint_in
+    pub signature: Option<String>,
+    pub detail: String,
+}
+
+impl Event {
+    pub fn render(&self, template: Option<&str>) -> String {
+        let pnl = self.pnl.map_or("n/a".to_string(), |p| format!("{:.9}", p));
+        template
+            .unwrap_or(self.kind.default_template())
+            .replace("{event}", self.kind.as_str())
+            .replace("{direction}", &self.direction)
+            .replace("{pool_a}", &self.pool_a)
+            .replace("{pool_b}", &self.pool_b)
+            .replace("{spread_bps}", &format!("{:.2}", self.spread_bps))
+            .replace("{pnl}", &pnl)
+            .replace("{signature}", self.signature.as_deref().unwrap_or("-"))
+            .replace("{detail}", &self.detail)
+    }
+}
+
+/// Fans decision events out to the configured sinks. Delivery failures are logged and
+/// never affect the trading flow.
+pub struct Notifier {
+    sinks: Vec<NotifierConfig>,
+    last_sent: Mutex<Vec<Option<Instant>>>,
+}
+
+impl Notifier {
+    pub fn new(sinks: Vec<NotifierConfig>) -> Self {
+        let last_sent = Mutex::new(vec![None; sinks.len()]);
+        Self { sinks, last_sent }
+    }
+
+    pub fn notify(&self, event: &Event) {
+        for (i, sink) in self.sinks.iter().enumerate() {
+            if !sink.events.contains(&event.kind) {
+                continue;
+            }
+            {
+                let mut last = self.last_sent.lock().unwrap();
+                let interval = Duration::from_secs(sink.min_interval_secs);
+                if last[i].is_some_and(|t| t.elapsed() < interval) {
+                    info!(
+                        "Notification {} suppressed by rate limit on sink #{}",
+                        event.kind.as_str(),
+                        i
+                    );
+                    continue;
+                }
+                last[i] = Some(Instant::now());
+            }
+            let text = event.render(sink.template.as_deref());
+            if let Err(e) = deliver(&sink.sink, event, &text) {
+                warn!("Notification to sink #{} failed: {}", i, e);
+            }
+        }
+    }
+
+    /// Deliver a sample message to every sink, ignoring event filters and rate limits.
+    pub fn send_test(&self) -> Vec<Result<()>> {
+        let event = Event {
+            kind: EventKind::Opportunity,
+            direction: "PoolA→PoolB".to_string(),
+            pool_a: "-".to_string(),
+            pool_b: "-".to_string(),
+            spread_bps: 0.0,
+            pnl: None,
+            signature: None,
+            detail: "test notification".to_string(),
+        };
+        self.sinks
+            .iter()
+            .map(|sink| deliver(&sink.sink, &event, &event.render(sink.template.as_deref())))
+            .collect()
+    }
+}
+
+fn post_json(url: &str, body: &Value) -> Result<()> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(HTTP_TIMEOUT)
+        .build()?;
+    let resp = client.post(url).json(body).send()?;
+    if !resp.status().is_success() {
+        bail!("{} returned HTTP {}", url, resp.status());
+    }
+    Ok(())
+}
+
+fn deliver(sink: &SinkConfig, event: &Event, text: &str) -> Result<()> {
+    match sink {
+        SinkConfig::Webhook { url } => {
+            let mut body = serde_json::to_value(event)?;
+            body["text"] = json!(text);
+            post_json(url, &body)
+        }
+        SinkConfig::Slack { url } => post_json(url, &json!({ "text": text })),
+        SinkConfig::Discord { url } => post_json(url, &json!({ "content": text })),
+        SinkConfig::Command { program, args } => {
+            let status = Command::new(program)
+                .args(args)
+                .env("ARB_EVENT", event.kind.as_str())
+                .env("ARB_MESSAGE", text)
+                .env("ARB_EVENT_JSON", serde_json::to_string(event)?)
+                .status()?;
+            if !status.success() {
+                bail!("{} exited with {}", program, status);
+            }
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use crate::arbitrage::Arbitrage;
+
+/// When a quoted round trip is worth sending. Leaves compare one metric of the quote with
+/// a minimum; `all` / `any` combine them. Written in config as externally tagged JSON/TOML,
+/// e.g. `{"any": [{"pnl_raw": 5000}, {"all": [{"mid_spread_bps": 80}, {"pnl_bps": 5}]}]}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// Every rule passes
+    All(Vec<Rule>),
+    /// At least one rule passes
+    Any(Vec<Rule>),
+    /// Mid-price difference over the midpoint of the two pools, in bps
+    MidSpreadBps(f64),
+    /// Round-trip return after pool fees and price impact for `amount_in`, in bps of it
+    EffectiveSpreadBps(f64),
+    /// Expected PnL after every fee, raw mint_in units; must also be positive
+    PnlRaw(u64),
+    /// Expected PnL after every fee, in bps of `amount_in`; must also be positive
+    PnlBps(f64),
+    /// `land_probability * pnl - (1 - land_probability) * network fee`, raw mint_in units:
+    /// a transaction that does not land as quoted is assumed to revert and still pay its fee
+    ExpectedValueRaw { land_probability: f64, min: i64 },
+}
+
+impl Rule {
+    /// The rule used when `decision_policy` is unset: the mid spread reaches
+    /// `spread_threshold_bps`.
+    pub fn from_spread_threshold(spread_threshold_bps: u32) -> Self {
+        Rule::MidSpreadBps(spread_threshold_bps as f64)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rule::All(_) => "all",
+            Rule::Any(_) => "any",
+            Rule::MidSpreadBps(_) => "mid_spread_bps",
+            Rule::EffectiveSpreadBps(_) => "effective_spread_bps",
+            Rule::PnlRaw(_) => "pnl_raw",
+            Rule::PnlBps(_) => "pnl_bps",
+            Rule::ExpectedValueRaw { .. } => "expected_value_raw",
+        }
+    }
+
+    /// Groups are not empty, thresholds are finite and probabilities lie in [0, 1].
+    pub fn check(&self) -> Result<(), String> {
+        let finite = |v: f64| {
+            if v.is_finite() {
+                Ok(())
+            } else {
+                Err(format!("{} threshold must be a finite number", self.name()))
+            }
+        };
+        match self {
+            Rule::All(rules) | Rule::Any(rules) => {
+                if rules.is_empty() {
+                    return Err(format!("`{}` needs at least one rule", self.name()));
+                }
+                rules.iter().try_for_each(Rule::check)
+            }
+            Rule::MidSpreadBps(v) | Rule::EffectiveSpreadBps(v) | Rule::PnlBps(v) => finite(*v),
+            Rule::PnlRaw(_) => Ok(()),
+            Rule::ExpectedValueRaw {
+                land_probability, ..
+            } => {
+                if (0.0..=1.0).contains(land_probability) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "land_probability must be between 0 and 1, got {land_probability}"
+                    ))
+                }
+            }
+        }
+    }
+
+    pub fn evaluate(&self, s: &Signals) -> Evaluation {
+        let leaf = |threshold: f64, value: Option<f64>, passed: bool| Evaluation {
+            rule: self.name().to_string(),
+            threshold: Some(threshold),
+            value,
+            passed,
+            children: Vec::new(),
+        };
+        let at_least = |value: Option<f64>, min: f64| value.is_some_and(|v| v >= min);
+        let profitable = s.pnl_raw.is_some_and(|p| p > 0);
+        match self {
+            Rule::All(rules) | Rule::Any(rules) => {
+                let children: Vec<Evaluation> = rules.iter().map(|r| r.evaluate(s)).collect();
+                let passed = if matches!(self, Rule::All(_)) {
+                    children.iter().all(|c| c.passed)
+                } else {
+                    children.iter().any(|c| c.passed)
+                };
+                Evaluation {
+                    rule: self.name().to_string(),
+                    threshold: None,
+                    value: None,
+                    passed,
+                    children,
+                }
+            }
+            Rule::MidSpreadBps(min) => leaf(*min, Some(s.mid_spread_bps), s.mid_spread_bps >= *min),
+            Rule::EffectiveSpreadBps(min) => leaf(
+                *min,
+                s.effective_spread_bps,
+                at_least(s.effective_spread_bps, *min),
+            ),
+            Rule::PnlRaw(min) => {
+                let value = s.pnl_raw.map(|p| p as f64);
+                let passed = profitable && s.pnl_raw.is_some_and(|p| p as i128 >= *min as i128);
+                leaf(*min as f64, value, passed)
+            }
+            Rule::PnlBps(min) => leaf(*min, s.pnl_bps, profitable && at_least(s.pnl_bps, *min)),
+            Rule::ExpectedValueRaw {
+                land_probability,
+                min,
+            } => {
+                let value = s.expected_value_raw(*land_probability);
+                leaf(*min as f64, value, at_least(value, *min as f64))
+            }
+        }
+    }
+}
+
+/// Compact JSON, as accepted by `FromStr`.
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rule: Rule =
+            serde_json::from_str(s).map_err(|e| format!("invalid decision policy: {e}"))?;
+        rule.check()?;
+        Ok(rule)
+    }
+}
+
+/// Metrics of one quoted direction that rules are evaluated against.
+#[derive(Debug, Clone, Copy)]
+pub struct Signals {
+    pub mid_spread_bps: f64,
+    pub effective_spread_bps: Option<f64>,
+    pub pnl_raw: Option<i64>,
+    pub pnl_bps: Option<f64>,
+    /// Network fee (priority fee, no rent) in mint_in, paid even when the swaps revert
+    pub failure_cost_raw: Option<u64>,
+}
+
+impl Signals {
+    pub fn new(arb: &Arbitrage, mid_spread_bps: f64) -> Self {
+        let bps_of_notional = |v: i64| {
+            (arb.amount_in_raw > 0).then(|| v as f64 * 10_000.0 / arb.amount_in_raw as f64)
+        };
+        let network_fee = arb.total_fees_raw.saturating_sub(arb.rent_raw);
+        Self {
+            mid_spread_bps,
+            effective_spread_bps: bps_of_notional(arb.gross_profit_raw),
+            pnl_raw: arb.pnl_raw,
+            pnl_bps: arb.pnl_raw.and_then(bps_of_notional),
+            failure_cost_raw: arb
+                .fee_conversion
+                .as_ref()
+                .map(|c| c.to_mint_in_raw(network_fee)),
+        }
+    }
+
+    pub fn expected_value_raw(&self, land_probability: f64) -> Option<f64> {
+        let pnl = self.pnl_raw? as f64;
+        let cost = self.failure_cost_raw? as f64;
+        Some(land_probability * pnl - (1.0 - land_probability) * cost)
+    }
+}
+
+/// A rule with the value it saw and whether it passed; groups list their children.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct Evaluation {
+    pub rule: String,
+    /// Minimum the value had to reach (leaves only)
+    pub threshold: Option<f64>,
+    /// Unset when the metric is unknown, e.g. PnL without a fee price in mint_in
+    pub value: Option<f64>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Evaluation>,
+}
+
+impl Evaluation {
+    /// Failed leaves under failed groups, e.g. `mid_spread_bps 12.5000 (min 100)`.
+    pub fn failures(&self) -> Vec<String> {
+        if self.passed {
+            return Vec::new();
+        }
+        if self.children.is_empty() {
+            let value = self
+                .value
+                .map_or("unknown".to_string(), |v| format!("{v:.4}"));
+            return vec![format!(
+                "{} {} (min {})",
+                self.rule,
+                value,
+                self.threshold.unwrap_or_default()
+            )];
+        }
+        self.children
+            .iter()
+            .flat_map(Evaluation::failures)
+            .collect()
+    }
+}
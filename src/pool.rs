@@ -1,4 +1,4 @@
-use anyhow::{Result, anyhow};
+use anyhow::Result;
 use carbon_core::account::AccountDecoder;
 use carbon_raydium_cpmm_decoder::{
     RaydiumCpmmDecoder,
@@ -9,6 +9,8 @@ use solana_program::program_pack::Pack;
 use solana_sdk::pubkey::Pubkey;
 use spl_token::state::Account;
 
+use crate::error::ArbError;
+
 pub struct PoolData {
     pub pool_id: Pubkey,
     pub state: PoolState,
@@ -24,35 +26,71 @@ pub struct PoolValues {
     pub protocol_fees_token1: u64,
     pub fund_fees_token0: u64,
     pub fund_fees_token1: u64,
+    pub creator_fees_token0: u64,
+    pub creator_fees_token1: u64,
     pub reserve0: u64,
     pub reserve1: u64,
     pub token0_decimals: u8,
     pub token1_decimals: u8,
     pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+    pub creator_fee_rate: u64,
+    pub creator_fee_on: u8, // 0 = both tokens (on input), 1 = only token0, 2 = only token1
+    pub enable_creator_fee: bool,
 }
 
+pub const CREATOR_FEE_ON_BOTH: u8 = 0;
+pub const CREATOR_FEE_ON_TOKEN0: u8 = 1;
+pub const CREATOR_FEE_ON_TOKEN1: u8 = 2;
+
+// Fee rates are parts per million
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+// `PoolState.status` bits (the program's `PoolStatusBitIndex`); a set bit disables the action
+pub const POOL_STATUS_DEPOSIT_DISABLED: u8 = 1 << 0;
+pub const POOL_STATUS_WITHDRAW_DISABLED: u8 = 1 << 1;
+pub const POOL_STATUS_SWAP_DISABLED: u8 = 1 << 2;
+
 impl PoolData {
     pub fn new(rpc: &RpcClient, pool_address: &str, decoder: &RaydiumCpmmDecoder) -> Result<Self> {
-        let pool_pk: Pubkey = pool_address.parse()?;
-        let pool_acc = rpc.get_account(&pool_pk)?;
+        let pool_pk: Pubkey = pool_address
+            .parse()
+            .map_err(|e| ArbError::Config(format!("invalid pool address {pool_address}: {e}")))?;
+        let pool_acc = rpc
+            .get_account(&pool_pk)
+            .map_err(|e| ArbError::Rpc(format!("get pool account {pool_pk}: {e}")))?;
 
         let pool_state = match decoder
             .decode_account(&pool_acc)
-            .ok_or(anyhow!("Failed to decode pool account"))?
+            .ok_or_else(|| ArbError::Decode(format!("{pool_pk} is not a Raydium CPMM account")))?
             .data
         {
             RaydiumCpmmAccount::PoolState(state) => state,
-            _ => return Err(anyhow!("Invalid pool account type")),
+            _ => return Err(ArbError::Decode(format!("{pool_pk} is not a CPMM pool")).into()),
         };
 
-        let config_acc = rpc.get_account(&pool_state.amm_config)?;
+        let config_acc = rpc
+            .get_account(&pool_state.amm_config)
+            .map_err(|e| ArbError::Rpc(format!("get amm config {}: {e}", pool_state.amm_config)))?;
         let amm_config = match decoder
             .decode_account(&config_acc)
-            .ok_or(anyhow!("Failed to decode config account"))?
+            .ok_or_else(|| {
+                ArbError::Decode(format!(
+                    "failed to decode amm config {}",
+                    pool_state.amm_config
+                ))
+            })?
             .data
         {
             RaydiumCpmmAccount::AmmConfig(config) => config,
-            _ => return Err(anyhow!("Invalid config account type")),
+            _ => {
+                return Err(ArbError::Decode(format!(
+                    "{} is not a CPMM amm config",
+                    pool_state.amm_config
+                ))
+                .into());
+            }
         };
 
         Ok(Self {
@@ -62,12 +100,22 @@ impl PoolData {
         })
     }
 
-    pub fn get_values(&self, rpc: &RpcClient) -> Result<PoolValues> {
-        let vault0_acc = rpc.get_account(&self.state.token0_vault)?;
-        let vault1_acc = rpc.get_account(&self.state.token1_vault)?;
+    /// Why the program would reject a swap on this pool at cluster time `now`; see
+    /// `swap_blockers`.
+    pub fn swap_blockers(&self, now: i64) -> Vec<String> {
+        swap_blockers(self.state.status, self.state.open_time, now)
+    }
 
-        let vault0_data = Account::unpack(&vault0_acc.data)?;
-        let vault1_data = Account::unpack(&vault1_acc.data)?;
+    pub fn get_values(&self, rpc: &RpcClient) -> Result<PoolValues> {
+        let get_vault = |vault: &Pubkey| -> Result<Account> {
+            let acc = rpc
+                .get_account(vault)
+                .map_err(|e| ArbError::Rpc(format!("get vault {vault}: {e}")))?;
+            Ok(Account::unpack(&acc.data)
+                .map_err(|e| ArbError::Decode(format!("vault {vault}: {e}")))?)
+        };
+        let vault0_data = get_vault(&self.state.token0_vault)?;
+        let vault1_data = get_vault(&self.state.token1_vault)?;
 
         let vault_amount0 = vault0_data.amount;
         let vault_amount1 = vault1_data.amount;
@@ -76,13 +124,41 @@ impl PoolData {
         let protocol_fees_token1 = self.state.protocol_fees_token1;
         let fund_fees_token0 = self.state.fund_fees_token0;
         let fund_fees_token1 = self.state.fund_fees_token1;
+        let creator_fees_token0 = self.state.creator_fees_token0;
+        let creator_fees_token1 = self.state.creator_fees_token1;
 
-        let reserve0 = vault_amount0 - protocol_fees_token0 - fund_fees_token0;
-        let reserve1 = vault_amount1 - protocol_fees_token1 - fund_fees_token1;
+        // Same as the program's `vault_amount_without_fee`
+        let reserve0 = vault_amount_without_fee(
+            vault_amount0,
+            &[protocol_fees_token0, fund_fees_token0, creator_fees_token0],
+        )
+        .ok_or_else(|| self.fees_exceed_vault(0, vault_amount0))?;
+        let reserve1 = vault_amount_without_fee(
+            vault_amount1,
+            &[protocol_fees_token1, fund_fees_token1, creator_fees_token1],
+        )
+        .ok_or_else(|| self.fees_exceed_vault(1, vault_amount1))?;
 
         let token0_decimals = self.state.mint0_decimals;
         let token1_decimals = self.state.mint1_decimals;
         let trade_fee_rate = self.config.trade_fee_rate;
+        let protocol_fee_rate = self.config.protocol_fee_rate;
+        let fund_fee_rate = self.config.fund_fee_rate;
+        let creator_fee_rate = self.config.creator_fee_rate;
+        for (name, rate) in [
+            ("trade_fee_rate", trade_fee_rate),
+            ("protocol_fee_rate", protocol_fee_rate),
+            ("fund_fee_rate", fund_fee_rate),
+            ("creator_fee_rate", creator_fee_rate),
+        ] {
+            if rate > FEE_RATE_DENOMINATOR {
+                return Err(ArbError::Decode(format!(
+                    "pool {}: {name} {rate} exceeds {FEE_RATE_DENOMINATOR}",
+                    self.pool_id
+                ))
+                .into());
+            }
+        }
 
         Ok(PoolValues {
             mint0: self.state.token0_mint,
@@ -93,13 +169,52 @@ impl PoolData {
             protocol_fees_token1,
             fund_fees_token0,
             fund_fees_token1,
+            creator_fees_token0,
+            creator_fees_token1,
             reserve0,
             reserve1,
             token0_decimals,
             token1_decimals,
             trade_fee_rate,
+            protocol_fee_rate,
+            fund_fee_rate,
+            creator_fee_rate,
+            creator_fee_on: self.state.creator_fee_on,
+            enable_creator_fee: self.state.enable_creator_fee,
         })
     }
+
+    fn fees_exceed_vault(&self, token: u8, vault_amount: u64) -> ArbError {
+        ArbError::math(format!(
+            "pool {}: accrued fees on token{token} exceed vault balance {vault_amount}",
+            self.pool_id
+        ))
+    }
+}
+
+/// Why the program would reject a swap at cluster time `now`, given a pool's `status` bitmask
+/// and `open_time`: swaps disabled in the status, or `open_time` not passed yet (swaps need
+/// `now > open_time`). Empty when the pool can swap. The amm config has no swap switch:
+/// `disable_create_pool` only stops new pools from being created with it.
+pub fn swap_blockers(status: u8, open_time: u64, now: i64) -> Vec<String> {
+    let mut reasons = Vec::new();
+    if status & POOL_STATUS_SWAP_DISABLED != 0 {
+        reasons.push(format!("swaps disabled (status {status:#05b})"));
+    }
+    let now = now.max(0) as u64;
+    if now <= open_time {
+        reasons.push(format!(
+            "not open until {} ({}s from now)",
+            open_time,
+            open_time - now
+        ));
+    }
+    reasons
+}
+
+fn vault_amount_without_fee(vault_amount: u64, fees: &[u64]) -> Option<u64> {
+    fees.iter()
+        .try_fold(vault_amount, |acc, fee| acc.checked_sub(*fee))
 }
 
 impl PoolValues {
@@ -114,13 +229,43 @@ impl PoolValues {
                 protocol_fees_token1: self.protocol_fees_token0,
                 fund_fees_token0: self.fund_fees_token1,
                 fund_fees_token1: self.fund_fees_token0,
+                creator_fees_token0: self.creator_fees_token1,
+                creator_fees_token1: self.creator_fees_token0,
                 reserve0: self.reserve1,
                 reserve1: self.reserve0,
                 token0_decimals: self.token1_decimals,
                 token1_decimals: self.token0_decimals,
                 trade_fee_rate: self.trade_fee_rate,
+                protocol_fee_rate: self.protocol_fee_rate,
+                fund_fee_rate: self.fund_fee_rate,
+                creator_fee_rate: self.creator_fee_rate,
+                // "only token0" becomes "only token1" once the sides are swapped
+                creator_fee_on: match self.creator_fee_on {
+                    CREATOR_FEE_ON_TOKEN0 => CREATOR_FEE_ON_TOKEN1,
+                    CREATOR_FEE_ON_TOKEN1 => CREATOR_FEE_ON_TOKEN0,
+                    other => other,
+                },
+                enable_creator_fee: self.enable_creator_fee,
             };
             *self = pool_val;
         }
     }
+
+    /// Creator fee rate that actually applies to swaps (zero unless enabled on the pool).
+    pub fn effective_creator_fee_rate(&self) -> u64 {
+        if self.enable_creator_fee {
+            self.creator_fee_rate
+        } else {
+            0
+        }
+    }
+
+    /// Mirrors the program's `is_creator_fee_on_input` for a token0 -> token1 (or reverse) swap.
+    pub fn is_creator_fee_on_input(&self, zero_for_one: bool) -> bool {
+        match self.creator_fee_on {
+            CREATOR_FEE_ON_TOKEN0 => zero_for_one,
+            CREATOR_FEE_ON_TOKEN1 => !zero_for_one,
+            _ => true,
+        }
+    }
 }
@@ -0,0 +1,215 @@
+use anyhow::{Context, Result, bail};
+use std::{fs, path::PathBuf};
+
+use crate::cli::{AppState, load_raw_state, state_file_path};
+
+/// The profile backed by the original top-level `state.json`.
+pub const DEFAULT_PROFILE: &str = "default";
+
+const PROFILES_DIR: &str = "profiles";
+const ACTIVE_PROFILE_FILE: &str = "active_profile";
+
+fn state_dir() -> Result<PathBuf> {
+    let path = state_file_path()?;
+    Ok(path
+        .parent()
+        .context("state path has no parent")?
+        .to_path_buf())
+}
+
+pub fn validate_profile_name(name: &str) -> Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        bail!("invalid profile name `{name}`: use letters, digits, `-` and `_`");
+    }
+    Ok(())
+}
+
+/// `default` lives in `<state dir>/state.json`, every other profile in
+/// `<state dir>/profiles/<name>/state.json` (with its own `history.jsonl` next to it).
+pub fn profile_state_path(name: &str) -> Result<PathBuf> {
+    validate_profile_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return state_file_path();
+    }
+    Ok(state_dir()?
+        .join(PROFILES_DIR)
+        .join(name)
+        .join("state.json"))
+}
+
+pub fn profile_exists(name: &str) -> Result<bool> {
+    Ok(profile_state_path(name)?.exists())
+}
+
+/// Profile selected with `config profile use`, or `default`.
+pub fn active_profile() -> Result<String> {
+    let path = state_dir()?.join(ACTIVE_PROFILE_FILE);
+    if !path.exists() {
+        return Ok(DEFAULT_PROFILE.to_string());
+    }
+    let name = fs::read_to_string(&path).with_context(|| format!("read {}", path.display()))?;
+    let name = name.trim();
+    if name.is_empty() {
+        return Ok(DEFAULT_PROFILE.to_string());
+    }
+    Ok(name.to_string())
+}
+
+pub fn set_active_profile(name: &str) -> Result<()> {
+    let path = state_dir()?.join(ACTIVE_PROFILE_FILE);
+    fs::write(&path, format!("{name}\n")).with_context(|| format!("write {}", path.display()))
+}
+
+/// All profiles on disk, `default` first.
+pub fn list_profiles() -> Result<Vec<String>> {
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    let dir = state_dir()?.join(PROFILES_DIR);
+    if dir.exists() {
+        let mut others = Vec::new();
+        for entry in fs::read_dir(&dir).with_context(|| format!("read {}", dir.display()))? {
+            let entry = entry?;
+            if entry.path().join("state.json").exists() {
+                others.push(entry.file_name().to_string_lossy().into_owned());
+            }
+        }
+        others.sort();
+        names.extend(others);
+    }
+    Ok(names)
+}
+
+/// Fill every unset field of `child` from `base`. Notifiers and RPC endpoints are inherited
+/// only when the child has none of its own.
+pub fn inherit(child: AppState, base: &AppState) -> AppState {
+    AppState {
+        base: child.base,
+        pool_a: child.pool_a.or(base.pool_a.clone()),
+        pool_b: child.pool_b.or(base.pool_b.clone()),
+        mint_in: child.mint_in.or(base.mint_in.clone()),
+        mint_out: child.mint_out.or(base.mint_out.clone()),
+        reference_pool: child.reference_pool.or(base.reference_pool.clone()),
+        amount_in: child.amount_in.or(base.amount_in),
+        spread_threshold_bps: child.spread_threshold_bps.or(base.spread_threshold_bps),
+        slippage_bps: child.slippage_bps.or(base.slippage_bps),
+        min_pnl_raw: child.min_pnl_raw.or(base.min_pnl_raw),
+        decision_policy: child.decision_policy.or(base.decision_policy.clone()),
+        twap_guard: child.twap_guard.or(base.twap_guard.clone()),
+        priority_fee_microlamports: child
+            .priority_fee_microlamports
+            .or(base.priority_fee_microlamports),
+        simulate_only: child.simulate_only.or(base.simulate_only),
+        rpc_url: child.rpc_url.or(base.rpc_url.clone()),
+        rpc_endpoints: if child.rpc_endpoints.is_empty() {
+            base.rpc_endpoints.clone()
+        } else {
+            child.rpc_endpoints
+        },
+        keypair_path: child.keypair_path.or(base.keypair_path.clone()),
+        fee_payer: child.fee_payer.or(base.fee_payer.clone()),
+        nonce_account: child.nonce_account.or(base.nonce_account.clone()),
+        profit_guard_program: child
+            .profit_guard_program
+            .or(base.profit_guard_program.clone()),
+        notifiers: if child.notifiers.is_empty() {
+            base.notifiers.clone()
+        } else {
+            child.notifiers
+        },
+    }
+}
+
+fn own<T: PartialEq + Clone>(v: &Option<T>, base: &Option<T>) -> Option<T> {
+    if v == base { None } else { v.clone() }
+}
+
+/// Inverse of `inherit`: keep only the values that differ from `base`, so later changes
+/// to the base profile still flow through.
+pub fn strip_inherited(st: &AppState, base: &AppState) -> AppState {
+    AppState {
+        base: st.base.clone(),
+        pool_a: own(&st.pool_a, &base.pool_a),
+        pool_b: own(&st.pool_b, &base.pool_b),
+        mint_in: own(&st.mint_in, &base.mint_in),
+        mint_out: own(&st.mint_out, &base.mint_out),
+        reference_pool: own(&st.reference_pool, &base.reference_pool),
+        amount_in: own(&st.amount_in, &base.amount_in),
+        spread_threshold_bps: own(&st.spread_threshold_bps, &base.spread_threshold_bps),
+        slippage_bps: own(&st.slippage_bps, &base.slippage_bps),
+        min_pnl_raw: own(&st.min_pnl_raw, &base.min_pnl_raw),
+        decision_policy: own(&st.decision_policy, &base.decision_policy),
+        twap_guard: own(&st.twap_guard, &base.twap_guard),
+        priority_fee_microlamports: own(
+            &st.priority_fee_microlamports,
+            &base.priority_fee_microlamports,
+        ),
+        simulate_only: own(&st.simulate_only, &base.simulate_only),
+        rpc_url: own(&st.rpc_url, &base.rpc_url),
+        rpc_endpoints: if st.rpc_endpoints == base.rpc_endpoints {
+            Vec::new()
+        } else {
+            st.rpc_endpoints.clone()
+        },
+        keypair_path: own(&st.keypair_path, &base.keypair_path),
+        fee_payer: own(&st.fee_payer, &base.fee_payer),
+        nonce_account: own(&st.nonce_account, &base.nonce_account),
+        profit_guard_program: own(&st.profit_guard_program, &base.profit_guard_program),
+        notifiers: if st.notifiers == base.notifiers {
+            Vec::new()
+        } else {
+            st.notifiers.clone()
+        },
+    }
+}
+
+/// Resolve `st.base` (and its bases) into a single effective state. `profile` is the profile
+/// `st` belongs to, so a base chain leading back to it is reported as a cycle.
+pub fn resolve_inheritance(profile: &str, st: AppState) -> Result<AppState> {
+    let mut chain = vec![profile.to_string()];
+    let mut resolved = st;
+    let mut next = resolved.base.clone();
+    while let Some(name) = next {
+        if chain.contains(&name) {
+            chain.push(name);
+            bail!("profile inheritance cycle: {}", chain.join(" -> "));
+        }
+        let path = profile_state_path(&name)?;
+        if !path.exists() {
+            bail!("base profile `{name}` does not exist ({})", path.display());
+        }
+        let base = load_raw_state(&path)?;
+        next = base.base.clone();
+        chain.push(name);
+        resolved = inherit(resolved, &base);
+    }
+    Ok(resolved)
+}
+
+/// Load the fully resolved base profile of `st`, if it has one.
+pub fn load_base(st: &AppState) -> Result<Option<AppState>> {
+    match &st.base {
+        None => Ok(None),
+        Some(name) => {
+            let path = profile_state_path(name)?;
+            if !path.exists() {
+                bail!("base profile `{name}` does not exist ({})", path.display());
+            }
+            Ok(Some(resolve_inheritance(name, load_raw_state(&path)?)?))
+        }
+    }
+}
+
+/// Names of the profiles that directly inherit from `name`.
+pub fn dependents(name: &str) -> Result<Vec<String>> {
+    let mut out = Vec::new();
+    for p in list_profiles()? {
+        let st = load_raw_state(&profile_state_path(&p)?)?;
+        if st.base.as_deref() == Some(name) {
+            out.push(p);
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,225 @@
+use anyhow::{Result, anyhow, bail};
+use schemars::JsonSchema;
+use serde::Serialize;
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, UiMessage,
+    UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use std::{thread, time::Duration};
+
+use crate::{
+    arbitrage::{Arbitrage, SOL_MINT},
+    error::ArbError,
+    utils::TokenAccount,
+};
+
+const FETCH_ATTEMPTS: u32 = 10;
+const FETCH_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// How far each component of the realized result drifted from the quote.
+/// Positive slippage means we received less than expected.
+#[derive(Debug, Serialize, Clone, JsonSchema)]
+pub struct SlippageAttribution {
+    pub leg_1_raw: i64, // mint_out
+    pub leg_1_bps: f64,
+    pub leg_2_raw: i64, // mint_in
+    pub leg_2_bps: f64,
+    pub fees_delta_lamports: i64,   // realized - expected
+    pub pnl_delta_raw: Option<i64>, // realized - expected, mint_in
+}
+
+/// Realized outcome of a landed arbitrage transaction, derived from pre/post balances.
+#[derive(Debug, Serialize, Clone)]
+pub struct Reconciliation {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub tx_fee_lamports: u64,
+    pub payer_lamports_delta: i64, // owner + fee payer (counted once when they are the same)
+    pub ata_in_delta_raw: i64,
+    pub ata_out_delta_raw: i64,
+    pub realized_amount_out_1_raw: u64,
+    pub realized_amount_out_2_raw: u64,
+    pub realized_gross_profit_raw: i64,
+    pub realized_fees_lamports: u64, // network fee + rent + anything else the payer spent
+    pub realized_pnl_raw: Option<i64>,
+    pub slippage: SlippageAttribution,
+}
+
+pub fn fetch_confirmed_transaction(
+    rpc: &RpcClient,
+    signature: &Signature,
+) -> Result<EncodedConfirmedTransactionWithStatusMeta> {
+    let config = RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Json),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let mut last_err = None;
+    // The node that confirmed the signature may not serve the full transaction yet
+    for _ in 0..FETCH_ATTEMPTS {
+        match rpc.get_transaction_with_config(signature, config) {
+            Ok(tx) => return Ok(tx),
+            Err(e) => last_err = Some(e),
+        }
+        thread::sleep(FETCH_RETRY_DELAY);
+    }
+    Err(anyhow!(
+        "transaction {} not available: {}",
+        signature,
+        last_err.map(|e| e.to_string()).unwrap_or_default()
+    ))
+}
+
+fn token_amount(balances: &[UiTransactionTokenBalance], index: usize) -> u64 {
+    balances
+        .iter()
+        .find(|b| b.account_index as usize == index)
+        .and_then(|b| b.ui_token_amount.amount.parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+fn bps_of(part: i64, whole: u64) -> f64 {
+    if whole == 0 {
+        return 0.0;
+    }
+    part as f64 / whole as f64 * 10_000.0
+}
+
+/// Lamports `create_arbitrage_transaction` wraps into a freshly created wSOL account.
+pub fn wrapped_lamports(ata: &TokenAccount, amount_in_raw: u64) -> i64 {
+    let sol_mint = SOL_MINT.parse::<Pubkey>().unwrap();
+    if ata.mint == sol_mint && !ata.exists {
+        amount_in_raw as i64
+    } else {
+        0
+    }
+}
+
+/// Compare a landed transaction against the `Arbitrage` it was built from.
+/// `atas[0]` is the mint_in account, `atas[1]` the mint_out account, as passed to the builder.
+/// `owner` funds rent and wrapped SOL, `fee_payer` the transaction fees.
+pub fn reconcile(
+    rpc: &RpcClient,
+    signature: &Signature,
+    owner: &Pubkey,
+    fee_payer: &Pubkey,
+    atas: &[TokenAccount],
+    arb: &Arbitrage,
+) -> Result<Reconciliation> {
+    let confirmed = fetch_confirmed_transaction(rpc, signature)?;
+    reconcile_transaction(confirmed, signature, owner, fee_payer, atas, arb)
+}
+
+/// `reconcile` on an already fetched transaction.
+pub fn reconcile_transaction(
+    confirmed: EncodedConfirmedTransactionWithStatusMeta,
+    signature: &Signature,
+    owner: &Pubkey,
+    fee_payer: &Pubkey,
+    atas: &[TokenAccount],
+    arb: &Arbitrage,
+) -> Result<Reconciliation> {
+    let meta = confirmed
+        .transaction
+        .meta
+        .ok_or_else(|| anyhow!("transaction {} has no status meta", signature))?;
+    if let Some(err) = meta.err {
+        bail!("transaction {} failed on-chain: {:?}", signature, err);
+    }
+
+    let account_keys: Vec<String> = match confirmed.transaction.transaction {
+        EncodedTransaction::Json(ui_tx) => match ui_tx.message {
+            UiMessage::Raw(raw) => raw.account_keys,
+            UiMessage::Parsed(parsed) => {
+                parsed.account_keys.into_iter().map(|k| k.pubkey).collect()
+            }
+        },
+        _ => bail!("unexpected transaction encoding for {}", signature),
+    };
+    let index_of = |key: &Pubkey| {
+        let key = key.to_string();
+        account_keys
+            .iter()
+            .position(|k| *k == key)
+            .ok_or_else(|| anyhow!("account {} not in transaction {}", key, signature))
+    };
+
+    let owner_idx = index_of(owner)?;
+    let fee_payer_idx = index_of(fee_payer)?;
+    let ata_in_idx = index_of(&atas[0].ata)?;
+    let ata_out_idx = index_of(&atas[1].ata)?;
+
+    let pre_tokens: Vec<UiTransactionTokenBalance> =
+        Option::from(meta.pre_token_balances).unwrap_or_default();
+    let post_tokens: Vec<UiTransactionTokenBalance> =
+        Option::from(meta.post_token_balances).unwrap_or_default();
+
+    // A truncated meta is the node's fault, not a reason to panic
+    let lamports = |balances: &[u64], idx: usize| {
+        balances.get(idx).map(|&l| l as i64).ok_or_else(|| {
+            ArbError::Decode(format!(
+                "transaction {signature} has no lamport balance for account {idx}"
+            ))
+        })
+    };
+    let lamports_delta = |idx: usize| -> Result<i64, ArbError> {
+        Ok(lamports(&meta.post_balances, idx)? - lamports(&meta.pre_balances, idx)?)
+    };
+    let mut payer_lamports_delta = lamports_delta(owner_idx)?;
+    if fee_payer_idx != owner_idx {
+        payer_lamports_delta += lamports_delta(fee_payer_idx)?;
+    }
+    let ata_in_delta_raw = token_amount(&post_tokens, ata_in_idx) as i64
+        - token_amount(&pre_tokens, ata_in_idx) as i64;
+    let ata_out_delta_raw = token_amount(&post_tokens, ata_out_idx) as i64
+        - token_amount(&pre_tokens, ata_out_idx) as i64;
+
+    let wrapped_in = wrapped_lamports(&atas[0], arb.amount_in_raw);
+    let wrapped_out = wrapped_lamports(&atas[1], arb.amount_in_raw);
+
+    // Swap 2 consumes exactly `amount_out_1_raw`, so whatever is left in (or missing from)
+    // the mint_out account is the first leg's deviation.
+    let realized_amount_out_1_raw =
+        (arb.amount_out_1_raw as i64 + ata_out_delta_raw - wrapped_out).max(0) as u64;
+    let realized_gross_profit_raw = ata_in_delta_raw - wrapped_in;
+    let realized_amount_out_2_raw =
+        (arb.amount_in_raw as i64 + realized_gross_profit_raw).max(0) as u64;
+    let realized_fees_lamports = (-payer_lamports_delta - wrapped_in - wrapped_out).max(0) as u64;
+
+    let realized_pnl_raw = arb.fee_conversion.as_ref().map(|c| {
+        (realized_gross_profit_raw as i128 - c.to_mint_in_raw(realized_fees_lamports) as i128)
+            as i64
+    });
+
+    let leg_1_raw = arb.amount_out_1_raw as i64 - realized_amount_out_1_raw as i64;
+    let leg_2_raw = arb.amount_out_2_raw as i64 - realized_amount_out_2_raw as i64;
+    let slippage = SlippageAttribution {
+        leg_1_raw,
+        leg_1_bps: bps_of(leg_1_raw, arb.amount_out_1_raw),
+        leg_2_raw,
+        leg_2_bps: bps_of(leg_2_raw, arb.amount_out_2_raw),
+        fees_delta_lamports: realized_fees_lamports as i64 - arb.total_fees_raw as i64,
+        pnl_delta_raw: realized_pnl_raw
+            .zip(arb.pnl_raw)
+            .map(|(realized, expected)| realized - expected),
+    };
+
+    Ok(Reconciliation {
+        signature: signature.to_string(),
+        slot: confirmed.slot,
+        block_time: confirmed.block_time,
+        tx_fee_lamports: meta.fee,
+        payer_lamports_delta,
+        ata_in_delta_raw,
+        ata_out_delta_raw,
+        realized_amount_out_1_raw,
+        realized_amount_out_2_raw,
+        realized_gross_profit_raw,
+        realized_fees_lamports,
+        realized_pnl_raw,
+        slippage,
+    })
+}
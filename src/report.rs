@@ -0,0 +1,711 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use schemars::{JsonSchema, schema_for};
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    arbitrage::{Arbitrage, FeeConversion, SwapQuote},
+    endpoints::EndpointStats,
+    error::ErrorReport,
+    policy::{Evaluation, Rule},
+    pool::PoolValues,
+    reconcile::{Reconciliation, SlippageAttribution},
+    sender::{SendAttempt, Sender},
+    transaction::ProfitGuard,
+    twap::{TwapGuard, TwapReport},
+};
+
+/// Bump on any breaking change to the report layout (renamed/removed fields, changed types).
+/// Adding optional fields keeps the version.
+/// v2: runs that fail before a decision write a `FailedRunReport`; both shapes carry `error`.
+pub const REPORT_SCHEMA_VERSION: u32 = 2;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Pretty-printed JSON report on stdout
+    Json,
+    /// One compact JSON report per line (suits watch mode)
+    Jsonl,
+    /// Human-readable summary
+    Table,
+    /// Nothing on stdout (report file and logs only)
+    #[default]
+    None,
+}
+
+fn ui_amount(raw: u64, decimals: u8) -> f64 {
+    raw as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Full result of one evaluation, written to `--report-path` and optionally to stdout.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ArbitrageReport {
+    pub schema_version: u32,
+    pub timestamp: String, // RFC 3339
+    pub execution_time_ms: u64,
+    pub inputs: InputsReport,
+    pub mints: MintsReport,
+    pub pools: PoolsReport,
+    pub prices: PricesReport,
+    pub pool_values: PoolValuesPairReport,
+    pub flow: FlowReport,
+    pub arbitrage_candidates: CandidatesReport,
+    pub calculations: CalculationsReport,
+    pub decision: DecisionReport,
+    /// Present when the pools were re-read before signing (send and sign-only)
+    pub final_quote: Option<FinalQuoteReport>,
+    pub token_accounts: Vec<TokenAccountReport>,
+    pub tx: TxReport,
+    pub realized: Option<RealizedReport>,
+    pub reconcile_error: Option<String>,
+    pub error: Option<ErrorReport>, // simulation, send or final-quote failure; sets the exit code
+    /// Per-endpoint calls, errors, latency and health since the process started
+    pub rpc_endpoints: Vec<EndpointStats>,
+    pub steps: Vec<String>,
+}
+
+/// Written instead of `ArbitrageReport` when the evaluation aborts (config, RPC, decode or
+/// math error) before a decision is made.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FailedRunReport {
+    pub schema_version: u32,
+    pub timestamp: String, // RFC 3339
+    pub execution_time_ms: u64,
+    pub inputs: InputsReport,
+    pub error: ErrorReport,
+    pub rpc_endpoints: Vec<EndpointStats>,
+    pub steps: Vec<String>,
+}
+
+/// Either report shape; tell them apart by the presence of `decision`.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(untagged)]
+#[schemars(title = "solana-amm-arb-cli run report")]
+pub enum RunReport {
+    Completed(Box<ArbitrageReport>),
+    Failed(FailedRunReport),
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct InputsReport {
+    pub pool_a: String,
+    pub pool_b: String,
+    pub rpc_url: String,
+    /// `[ROLES=]URL` of every endpoint besides `rpc_url`
+    pub rpc_endpoints: Vec<String>,
+    pub keypair_path: PathBuf,
+    pub fee_payer: Option<PathBuf>,
+    pub nonce_account: Option<String>,
+    pub profit_guard_program: Option<String>,
+    pub sign_only: bool,
+    pub amount_in: f64,
+    pub spread_threshold_bps: u32,
+    pub slippage_bps: u32,
+    pub min_pnl_raw: u64,
+    pub decision_policy: Rule,
+    pub twap_guard: Option<TwapGuard>,
+    pub priority_fee_microlamports: u64,
+    pub simulate_only: bool,
+    pub reference_pool: Option<String>,
+    pub profile: String,
+    /// Where each value came from: `default`, `profile:<name>`, `file:<path>`,
+    /// `env:<VAR>` or `flag:--<name>`
+    pub sources: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MintsReport {
+    pub mint_in: String,
+    pub mint_out: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DirectionReport {
+    pub first_label: String,
+    pub second_label: String,
+    pub first: String,
+    pub second: String,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PoolsReport {
+    pub pool_a: String,
+    pub pool_b: String,
+    pub direction: DirectionReport,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PricesReport {
+    pub first: f64,
+    pub second: f64,
+    pub spread_bps: f64,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PoolValuesReport {
+    pub mint0: String,
+    pub mint1: String,
+    pub reserve0: u64,
+    pub reserve1: u64,
+    pub reserve0_ui: f64,
+    pub reserve1_ui: f64,
+    pub vault_amount0: u64,
+    pub vault_amount1: u64,
+    pub protocol_fees_token0: u64,
+    pub protocol_fees_token1: u64,
+    pub fund_fees_token0: u64,
+    pub fund_fees_token1: u64,
+    pub creator_fees_token0: u64,
+    pub creator_fees_token1: u64,
+    pub token0_decimals: u8,
+    pub token1_decimals: u8,
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+    pub creator_fee_rate: u64,
+    pub creator_fee_on: u8,
+    pub enable_creator_fee: bool,
+}
+
+impl From<&PoolValues> for PoolValuesReport {
+    fn from(v: &PoolValues) -> Self {
+        Self {
+            mint0: v.mint0.to_string(),
+            mint1: v.mint1.to_string(),
+            reserve0: v.reserve0,
+            reserve1: v.reserve1,
+            reserve0_ui: ui_amount(v.reserve0, v.token0_decimals),
+            reserve1_ui: ui_amount(v.reserve1, v.token1_decimals),
+            vault_amount0: v.vault_amount0,
+            vault_amount1: v.vault_amount1,
+            protocol_fees_token0: v.protocol_fees_token0,
+            protocol_fees_token1: v.protocol_fees_token1,
+            fund_fees_token0: v.fund_fees_token0,
+            fund_fees_token1: v.fund_fees_token1,
+            creator_fees_token0: v.creator_fees_token0,
+            creator_fees_token1: v.creator_fees_token1,
+            token0_decimals: v.token0_decimals,
+            token1_decimals: v.token1_decimals,
+            trade_fee_rate: v.trade_fee_rate,
+            protocol_fee_rate: v.protocol_fee_rate,
+            fund_fee_rate: v.fund_fee_rate,
+            creator_fee_rate: v.creator_fee_rate,
+            creator_fee_on: v.creator_fee_on,
+            enable_creator_fee: v.enable_creator_fee,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct PoolValuesPairReport {
+    pub first: PoolValuesReport,
+    pub second: PoolValuesReport,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FlowReport {
+    pub amount_in: f64,               // mint_in
+    pub amount_out_after_first: f64,  // mint_out
+    pub amount_out_after_second: f64, // back to mint_in
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SwapQuoteReport {
+    pub amount_in_raw: u64,
+    pub amount_out_raw: u64,
+    pub trade_fee_raw: u64,
+    pub protocol_fee_raw: u64,
+    pub fund_fee_raw: u64,
+    pub creator_fee_raw: u64,
+    pub creator_fee_on: String, // "input" | "output"
+}
+
+impl From<&SwapQuote> for SwapQuoteReport {
+    fn from(q: &SwapQuote) -> Self {
+        Self {
+            amount_in_raw: q.amount_in,
+            amount_out_raw: q.amount_out,
+            trade_fee_raw: q.trade_fee,
+            protocol_fee_raw: q.protocol_fee,
+            fund_fee_raw: q.fund_fee,
+            creator_fee_raw: q.creator_fee,
+            creator_fee_on: if q.creator_fee_on_input {
+                "input"
+            } else {
+                "output"
+            }
+            .to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FeeBreakdownReport {
+    pub first_leg: SwapQuoteReport,
+    pub second_leg: SwapQuoteReport,
+}
+
+impl From<&Arbitrage> for FeeBreakdownReport {
+    fn from(arb: &Arbitrage) -> Self {
+        Self {
+            first_leg: (&arb.leg_1).into(),
+            second_leg: (&arb.leg_2).into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CandidateReport {
+    pub amount_out_1: f64,
+    pub amount_out_2: f64,
+    pub gross_profit: f64,
+    pub total_fees: f64,
+    pub rent: f64,
+    pub pnl: Option<f64>,
+    pub pnl_raw: Option<i64>,
+    pub total_fees_in_mint_raw: Option<u64>,
+    pub fee_breakdown: FeeBreakdownReport,
+}
+
+impl From<&Arbitrage> for CandidateReport {
+    fn from(arb: &Arbitrage) -> Self {
+        Self {
+            amount_out_1: arb.amount_out_1,
+            amount_out_2: arb.amount_out_2,
+            gross_profit: arb.gross_profit,
+            total_fees: arb.total_fees,
+            rent: arb.rent,
+            pnl: arb.pnl,
+            pnl_raw: arb.pnl_raw,
+            total_fees_in_mint_raw: arb.total_fees_in_mint_raw,
+            fee_breakdown: arb.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CandidatesReport {
+    #[serde(rename = "A_to_B")]
+    pub a_to_b: CandidateReport,
+    #[serde(rename = "B_to_A")]
+    pub b_to_a: CandidateReport,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FeeConversionReport {
+    pub source: String,
+    // u128 ratios, as strings so JSON consumers do not lose precision
+    pub mint_in_per: String,
+    pub lamports_per: String,
+}
+
+impl From<&FeeConversion> for FeeConversionReport {
+    fn from(c: &FeeConversion) -> Self {
+        Self {
+            source: c.source.clone(),
+            mint_in_per: c.mint_in_per.to_string(),
+            lamports_per: c.lamports_per.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct CalculationsReport {
+    pub amount_in_raw: u64,
+    pub amount_out_1_raw: u64,
+    pub amount_out_2_raw: u64,
+    pub gross_profit: f64,
+    pub gross_profit_raw: i64,
+    pub total_fees: f64,
+    pub total_fees_raw: u64,
+    pub rent: f64,
+    pub rent_raw: u64,
+    pub pnl: Option<f64>,
+    pub pnl_raw: Option<i64>,
+    pub total_fees_in_mint_raw: Option<u64>,
+    pub fee_conversion: Option<FeeConversionReport>,
+    pub min_out_raw: u64,
+    pub fee_breakdown: FeeBreakdownReport,
+}
+
+impl CalculationsReport {
+    pub fn new(arb: &Arbitrage, min_out_raw: u64) -> Self {
+        Self {
+            amount_in_raw: arb.amount_in_raw,
+            amount_out_1_raw: arb.amount_out_1_raw,
+            amount_out_2_raw: arb.amount_out_2_raw,
+            gross_profit: arb.gross_profit,
+            gross_profit_raw: arb.gross_profit_raw,
+            total_fees: arb.total_fees,
+            total_fees_raw: arb.total_fees_raw,
+            rent: arb.rent,
+            rent_raw: arb.rent_raw,
+            pnl: arb.pnl,
+            pnl_raw: arb.pnl_raw,
+            total_fees_in_mint_raw: arb.total_fees_in_mint_raw,
+            fee_conversion: arb.fee_conversion.as_ref().map(Into::into),
+            min_out_raw,
+            fee_breakdown: arb.into(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct DecisionReport {
+    /// Why a pool cannot swap right now (swaps disabled, not open yet); any reason blocks
+    pub unavailable: Vec<String>,
+    /// Expected PnL is known, positive and at least `min_pnl_raw`
+    pub is_profitable: bool,
+    /// Mid spread reaches `spread_threshold_bps`; only decisive without a `decision_policy`
+    pub meets_spread_threshold: bool,
+    /// The decision policy evaluated on the chosen direction
+    pub policy: Evaluation,
+    /// Spot vs TWAP of both pools, when `twap_guard` is set
+    pub twap: Option<TwapReport>,
+    /// Both pools can swap, `is_profitable`, the policy passed and the TWAP guard did not block
+    pub should_execute: bool,
+    pub chosen_direction: String,
+}
+
+/// `after - before`, clamped to i64.
+pub fn drift_raw(before: u64, after: u64) -> i64 {
+    (after as i128 - before as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Change of one pool's normalized reserves between the first quote and the final re-read.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ReserveDriftReport {
+    pub reserve0_raw: i64,
+    pub reserve1_raw: i64,
+}
+
+impl ReserveDriftReport {
+    pub fn new(before: &PoolValues, after: &PoolValues) -> Self {
+        Self {
+            reserve0_raw: drift_raw(before.reserve0, after.reserve0),
+            reserve1_raw: drift_raw(before.reserve1, after.reserve1),
+        }
+    }
+}
+
+/// Vault balances re-read right before signing and the chosen direction quoted again on
+/// them. Drift is `final - first`; the transaction is built from the final quote.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FinalQuoteReport {
+    /// Since the first pool values were read
+    pub quote_age_ms: u64,
+    pub spread_bps: f64,
+    pub spread_drift_bps: f64,
+    pub amount_out_2_raw: u64,
+    pub amount_out_2_drift_raw: i64,
+    pub pnl_raw: Option<i64>,
+    pub pnl_drift_raw: Option<i64>,
+    pub first_pool: ReserveDriftReport,
+    pub second_pool: ReserveDriftReport,
+    /// Why the send was aborted, if it was
+    pub aborted: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TokenAccountReport {
+    pub mint: String,
+    pub owner: String,
+    pub ata: String,
+    pub existed_before: bool,
+    pub planned_to_create_now: bool,
+    pub actually_created_now: bool,
+    // "existed_before" | "would_create_in_simulation" | "created_now" | "skipped_no_send"
+    pub creation_status: String,
+}
+
+/// `TxReport.mode`: an aborted final quote wins over everything else, a pool that cannot
+/// swap skips the trade.
+pub fn tx_mode(
+    aborted: bool,
+    pools_available: bool,
+    sign_only: bool,
+    simulate_only: bool,
+    should_execute: bool,
+) -> &'static str {
+    if aborted {
+        "aborted"
+    } else if !pools_available {
+        "skip"
+    } else if sign_only {
+        "sign_only"
+    } else if simulate_only {
+        "simulate"
+    } else if should_execute {
+        "send"
+    } else {
+        "skip"
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct TxReport {
+    pub mode: String, // "simulate" | "send" | "sign_only" | "aborted" | "skip"
+    pub fee_payer: String,
+    pub nonce_account: Option<String>,
+    /// Balance assertion appended to the transaction, if a guard program is configured
+    pub profit_guard: Option<ProfitGuardReport>,
+    /// `sign_only`: base64 transaction, signed by every available signer
+    pub serialized: Option<String>,
+    /// Signers whose signature `serialized` still lacks
+    pub missing_signers: Vec<String>,
+    pub signature: Option<String>,
+    pub slot: Option<u64>,
+    pub simulate_result: Option<Value>,
+    /// `send`: every broadcast, re-quote and the confirmation
+    pub send: Option<SendReport>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ProfitGuardReport {
+    pub program_id: String,
+    pub token_account: String,
+    /// Fees converted into mint_in plus `min_pnl_raw`
+    pub min_profit_raw: u64,
+    /// The transaction reverts if `token_account` ends below this
+    pub min_balance_raw: u64,
+}
+
+impl From<&ProfitGuard> for ProfitGuardReport {
+    fn from(g: &ProfitGuard) -> Self {
+        Self {
+            program_id: g.program_id.to_string(),
+            token_account: g.token_account.to_string(),
+            min_profit_raw: g.min_profit_raw,
+            min_balance_raw: g.min_balance_raw,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct SendReport {
+    pub skip_preflight: bool,
+    pub rebroadcast_interval_ms: u64,
+    pub endpoints: Vec<String>,
+    /// Of the last transaction sent (for a durable nonce: when watching stopped)
+    pub last_valid_block_height: Option<u64>,
+    pub requotes: u32,
+    pub attempts: Vec<SendAttempt>,
+    pub landed_slot: Option<u64>,
+    pub confirmed_after_ms: Option<u64>,
+}
+
+impl From<&Sender<'_>> for SendReport {
+    fn from(s: &Sender<'_>) -> Self {
+        Self {
+            skip_preflight: s.options().skip_preflight,
+            rebroadcast_interval_ms: s.options().rebroadcast_interval.as_millis() as u64,
+            endpoints: s.endpoints(),
+            last_valid_block_height: s.last_valid_block_height,
+            requotes: s.requotes,
+            attempts: s.attempts.clone(),
+            landed_slot: s.landed_slot,
+            confirmed_after_ms: s.confirmed_after_ms,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ExpectedReport {
+    pub amount_out_1_raw: u64,
+    pub amount_out_2_raw: u64,
+    pub total_fees_raw: u64,
+    pub pnl_raw: Option<i64>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RealizedReport {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub tx_fee_lamports: u64,
+    pub payer_lamports_delta: i64,
+    pub ata_in_delta_raw: i64,
+    pub ata_out_delta_raw: i64,
+    pub amount_out_1_raw: u64,
+    pub amount_out_2_raw: u64,
+    pub gross_profit_raw: i64,
+    pub fees_lamports: u64,
+    pub pnl_raw: Option<i64>,
+    pub expected: ExpectedReport,
+    pub slippage_attribution: SlippageAttribution,
+}
+
+impl RealizedReport {
+    pub fn new(r: &Reconciliation, arb: &Arbitrage) -> Self {
+        Self {
+            signature: r.signature.clone(),
+            slot: r.slot,
+            block_time: r.block_time,
+            tx_fee_lamports: r.tx_fee_lamports,
+            payer_lamports_delta: r.payer_lamports_delta,
+            ata_in_delta_raw: r.ata_in_delta_raw,
+            ata_out_delta_raw: r.ata_out_delta_raw,
+            amount_out_1_raw: r.realized_amount_out_1_raw,
+            amount_out_2_raw: r.realized_amount_out_2_raw,
+            gross_profit_raw: r.realized_gross_profit_raw,
+            fees_lamports: r.realized_fees_lamports,
+            pnl_raw: r.realized_pnl_raw,
+            expected: ExpectedReport {
+                amount_out_1_raw: arb.amount_out_1_raw,
+                amount_out_2_raw: arb.amount_out_2_raw,
+                total_fees_raw: arb.total_fees_raw,
+                pnl_raw: arb.pnl_raw,
+            },
+            slippage_attribution: r.slippage.clone(),
+        }
+    }
+}
+
+/// JSON Schema (draft-07) describing `RunReport`.
+pub fn report_schema() -> Result<String> {
+    let mut schema = serde_json::to_value(schema_for!(RunReport))?;
+    schema["$id"] = Value::String(format!(
+        "urn:solana-amm-arb-cli:arbitrage-report:v{}",
+        REPORT_SCHEMA_VERSION
+    ));
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+fn fmt_opt<T: std::fmt::Display>(v: Option<T>) -> String {
+    v.map_or("-".to_string(), |x| x.to_string())
+}
+
+fn print_rows(rows: Vec<(&str, String)>) {
+    let width = rows.iter().map(|(k, _)| k.len()).max().unwrap_or(0);
+    for (k, v) in rows {
+        println!("{:<width$}  {}", k, v, width = width);
+    }
+}
+
+impl RunReport {
+    /// Save the pretty JSON report to `path`.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let json_str = serde_json::to_string_pretty(self)?;
+        fs::write(path, &json_str).with_context(|| format!("write {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn print(&self, format: OutputFormat) -> Result<()> {
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(self)?),
+            OutputFormat::Jsonl => println!("{}", serde_json::to_string(self)?),
+            OutputFormat::Table => match self {
+                RunReport::Completed(r) => r.print_table(),
+                RunReport::Failed(r) => r.print_table(),
+            },
+            OutputFormat::None => {}
+        }
+        Ok(())
+    }
+}
+
+impl FailedRunReport {
+    fn print_table(&self) {
+        print_rows(vec![
+            ("timestamp", self.timestamp.clone()),
+            (
+                "pools",
+                format!("A={}  B={}", self.inputs.pool_a, self.inputs.pool_b),
+            ),
+            ("error.kind", self.error.kind.clone()),
+            ("error.message", self.error.message.clone()),
+            ("exit_code", self.error.exit_code.to_string()),
+            ("execution_time_ms", self.execution_time_ms.to_string()),
+        ]);
+    }
+}
+
+impl ArbitrageReport {
+    fn print_table(&self) {
+        let c = &self.calculations;
+        let rows: Vec<(&str, String)> = vec![
+            ("timestamp", self.timestamp.clone()),
+            (
+                "pools",
+                format!("A={}  B={}", self.pools.pool_a, self.pools.pool_b),
+            ),
+            ("direction", self.decision.chosen_direction.clone()),
+            (
+                "unavailable",
+                if self.decision.unavailable.is_empty() {
+                    "-".to_string()
+                } else {
+                    self.decision.unavailable.join("; ")
+                },
+            ),
+            (
+                "prices",
+                format!(
+                    "first={:.12}  second={:.12}",
+                    self.prices.first, self.prices.second
+                ),
+            ),
+            ("spread_bps", format!("{:.4}", self.prices.spread_bps)),
+            ("amount_in_raw", c.amount_in_raw.to_string()),
+            ("amount_out_2_raw", c.amount_out_2_raw.to_string()),
+            ("gross_profit_raw", c.gross_profit_raw.to_string()),
+            ("total_fees_raw (lamports)", c.total_fees_raw.to_string()),
+            ("pnl", fmt_opt(c.pnl)),
+            ("min_out_raw", c.min_out_raw.to_string()),
+            (
+                "policy",
+                if self.decision.policy.passed {
+                    "passed".to_string()
+                } else {
+                    self.decision.policy.failures().join("; ")
+                },
+            ),
+            (
+                "twap",
+                fmt_opt(self.decision.twap.as_ref().map(|t| {
+                    let breaches = t.breaches();
+                    if breaches.is_empty() {
+                        "within limit".to_string()
+                    } else {
+                        breaches.join("; ")
+                    }
+                })),
+            ),
+            ("should_execute", self.decision.should_execute.to_string()),
+            (
+                "final_quote pnl_drift_raw",
+                fmt_opt(self.final_quote.as_ref().and_then(|f| f.pnl_drift_raw)),
+            ),
+            ("tx.mode", self.tx.mode.clone()),
+            ("tx.fee_payer", self.tx.fee_payer.clone()),
+            ("tx.signature", fmt_opt(self.tx.signature.as_ref())),
+            (
+                "tx.broadcasts",
+                fmt_opt(self.tx.send.as_ref().map(|s| s.attempts.len())),
+            ),
+            (
+                "tx.requotes",
+                fmt_opt(self.tx.send.as_ref().map(|s| s.requotes)),
+            ),
+            ("tx.missing_signers", self.tx.missing_signers.join(" ")),
+            ("tx.error", fmt_opt(self.tx.error.as_ref())),
+            (
+                "realized pnl_raw",
+                fmt_opt(self.realized.as_ref().and_then(|r| r.pnl_raw)),
+            ),
+            (
+                "error",
+                fmt_opt(self.error.as_ref().map(|e| e.message.as_str())),
+            ),
+            ("execution_time_ms", self.execution_time_ms.to_string()),
+        ];
+        print_rows(rows);
+    }
+}
@@ -0,0 +1,390 @@
+use anyhow::Result;
+use log::{info, warn};
+use schemars::JsonSchema;
+use serde::Serialize;
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    pubsub_client::{PubsubClient, SignatureSubscription},
+    rpc_config::{RpcSendTransactionConfig, RpcSignatureSubscribeConfig},
+    rpc_custom_error::{
+        JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE,
+        JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_LEN_MISMATCH,
+        JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_VERIFICATION_FAILURE,
+        JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION,
+    },
+    rpc_request::RpcError,
+    rpc_response::RpcSignatureResult,
+};
+use solana_sdk::{
+    clock::MAX_PROCESSING_AGE,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    signature::Signature,
+    transaction::{Transaction, TransactionError},
+};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    endpoints::Endpoints,
+    error::ArbError,
+    transaction::{BuiltTransaction, uses_durable_nonce},
+};
+
+pub const DEFAULT_REBROADCAST_MS: u64 = 2_000;
+pub const DEFAULT_MAX_REQUOTES: u32 = 1;
+
+// Signature status is polled this often between rebroadcasts, unless a websocket
+// subscription reports it
+const STATUS_POLL: Duration = Duration::from_millis(400);
+
+// JSON-RPC "invalid params", e.g. a transaction the node cannot decode
+const INVALID_PARAMS: i64 = -32602;
+
+/// How signed transactions are pushed to the cluster.
+pub struct SendOptions {
+    /// Skip the node's preflight simulation on the first broadcast (rebroadcasts always do)
+    pub skip_preflight: bool,
+    /// Send the same signed transaction again this often until it confirms or expires
+    pub rebroadcast_interval: Duration,
+    /// How many times an expired transaction may be re-quoted and re-signed
+    pub max_requotes: u32,
+}
+
+impl Default for SendOptions {
+    fn default() -> Self {
+        Self {
+            skip_preflight: false,
+            rebroadcast_interval: Duration::from_millis(DEFAULT_REBROADCAST_MS),
+            max_requotes: DEFAULT_MAX_REQUOTES,
+        }
+    }
+}
+
+/// One broadcast of a signed transaction to one endpoint.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct SendAttempt {
+    /// 0 for the original transaction, n after the n-th re-quote
+    pub generation: u32,
+    pub endpoint: String,
+    pub signature: String,
+    /// Since the first broadcast
+    pub elapsed_ms: u64,
+    pub error: Option<String>,
+}
+
+/// A failed `send_transaction` as `ArbError::Send` when the node rejected the transaction
+/// itself (preflight failures, which include an expired blockhash or an already processed
+/// signature, bad signatures or an unsupported version) and `ArbError::Rpc` when the endpoint
+/// is at fault (transport errors, an unhealthy or failing node). Only the latter marks the
+/// endpoint unhealthy.
+pub fn send_error(e: ClientError) -> anyhow::Error {
+    let rejected = match e.kind() {
+        ClientErrorKind::TransactionError(_) | ClientErrorKind::SigningError(_) => true,
+        ClientErrorKind::RpcError(RpcError::RpcResponseError { code, .. }) => matches!(
+            *code,
+            JSON_RPC_SERVER_ERROR_SEND_TRANSACTION_PREFLIGHT_FAILURE
+                | JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_VERIFICATION_FAILURE
+                | JSON_RPC_SERVER_ERROR_TRANSACTION_SIGNATURE_LEN_MISMATCH
+                | JSON_RPC_SERVER_ERROR_UNSUPPORTED_TRANSACTION_VERSION
+                | INVALID_PARAMS
+        ),
+        _ => false,
+    };
+    if rejected {
+        ArbError::Send(e.to_string()).into()
+    } else {
+        ArbError::Rpc(e.to_string()).into()
+    }
+}
+
+/// Block height after which a transaction is no longer watched: its own last valid height,
+/// or `MAX_PROCESSING_AGE` blocks past the current `height()` for a durable nonce, which
+/// never expires. `None` when neither applies; expiry is then asked of the blockhash itself.
+pub fn watch_deadline(
+    last_valid_block_height: Option<u64>,
+    durable: bool,
+    height: impl FnOnce() -> Result<u64>,
+) -> Result<Option<u64>> {
+    Ok(match last_valid_block_height {
+        Some(height) => Some(height),
+        None if durable => Some(height()? + MAX_PROCESSING_AGE as u64),
+        None => None,
+    })
+}
+
+/// A transaction can still land in its last valid block.
+pub fn past_deadline(block_height: u64, deadline: u64) -> bool {
+    block_height > deadline
+}
+
+/// The re-quote count after `sig` expired unconfirmed, or why it is not re-quoted: a
+/// durable-nonce transaction may still land, and `max_requotes` may be used up.
+pub fn next_requote(
+    sig: &Signature,
+    durable: bool,
+    requotes: u32,
+    max_requotes: u32,
+) -> Result<u32, ArbError> {
+    if durable {
+        return Err(ArbError::Send(format!(
+            "{sig} not confirmed within {MAX_PROCESSING_AGE} blocks; it stays valid until the nonce is advanced"
+        )));
+    }
+    if requotes >= max_requotes {
+        return Err(ArbError::Send(format!(
+            "{sig} expired unconfirmed after {requotes} re-quote(s)"
+        )));
+    }
+    Ok(requotes + 1)
+}
+
+/// Builds a replacement for an expired transaction from a fresh quote; `None` when the
+/// opportunity is gone.
+pub type Requote<'r> = dyn FnMut() -> Result<Option<BuiltTransaction>> + 'r;
+
+/// Rebroadcasts a signed transaction until it confirms or its blockhash expires, then
+/// re-quotes (up to `max_requotes`) instead of re-sending a transaction that can no
+/// longer land. Broadcasts go to every send endpoint. Confirmation arrives through a
+/// signature subscription on the first websocket endpoint that accepts one; the read
+/// endpoints are still polled, every `STATUS_POLL` without a subscription and once per
+/// rebroadcast with one. Everything it did is kept for the report.
+pub struct Sender<'a> {
+    rpcs: &'a Endpoints,
+    opts: &'a SendOptions,
+    started: Instant,
+    pub attempts: Vec<SendAttempt>,
+    pub requotes: u32,
+    pub last_valid_block_height: Option<u64>,
+    /// Slot the transaction was confirmed in (also when it failed on chain)
+    pub landed_slot: Option<u64>,
+    pub confirmed_after_ms: Option<u64>,
+}
+
+impl<'a> Sender<'a> {
+    pub fn new(rpcs: &'a Endpoints, opts: &'a SendOptions) -> Self {
+        Self {
+            rpcs,
+            opts,
+            started: Instant::now(),
+            attempts: Vec::new(),
+            requotes: 0,
+            last_valid_block_height: None,
+            landed_slot: None,
+            confirmed_after_ms: None,
+        }
+    }
+
+    pub fn endpoints(&self) -> Vec<String> {
+        self.rpcs
+            .send_targets()
+            .into_iter()
+            .map(|(i, _)| self.rpcs.url(i).to_string())
+            .collect()
+    }
+
+    pub fn options(&self) -> &SendOptions {
+        self.opts
+    }
+
+    pub fn send(
+        &mut self,
+        mut built: BuiltTransaction,
+        requote: &mut Requote<'_>,
+    ) -> Result<Signature> {
+        self.started = Instant::now();
+        let mut generation = 0;
+        loop {
+            let durable = uses_durable_nonce(&built.tx);
+            let deadline = watch_deadline(built.last_valid_block_height, durable, || {
+                self.block_height()
+            })?;
+            self.last_valid_block_height = deadline;
+
+            if let Some(sig) = self.broadcast_until_expired(generation, &built.tx, deadline)? {
+                return Ok(sig);
+            }
+
+            let sig = built.tx.signatures[0];
+            self.requotes = next_requote(&sig, durable, self.requotes, self.opts.max_requotes)?;
+            info!("Blockhash expired before {sig} confirmed; re-quoting");
+            built = requote()?.ok_or_else(|| {
+                ArbError::Send("blockhash expired and the re-quote is no longer profitable".into())
+            })?;
+            generation += 1;
+        }
+    }
+
+    /// `Some(signature)` once confirmed, `None` when the transaction can no longer land.
+    fn broadcast_until_expired(
+        &mut self,
+        generation: u32,
+        tx: &Transaction,
+        deadline: Option<u64>,
+    ) -> Result<Option<Signature>> {
+        let sig = tx.signatures[0];
+        let mut watch = self.subscribe(&sig);
+        let mut last_broadcast: Option<Instant> = None;
+        loop {
+            if last_broadcast.is_none_or(|t| t.elapsed() >= self.opts.rebroadcast_interval) {
+                if last_broadcast.is_some() && self.expired(tx, deadline) {
+                    // It may have landed right before expiring
+                    return self.confirmed(&sig);
+                }
+                let first = last_broadcast.is_none();
+                let (accepted, errors) = self.broadcast(generation, tx, first);
+                // Nobody accepted it (e.g. preflight failed): rebroadcasting cannot help
+                if first && accepted == 0 {
+                    return Err(ArbError::Send(errors.join("; ")).into());
+                }
+                last_broadcast = Some(Instant::now());
+            }
+            if let Some(sig) = self.confirmed(&sig)? {
+                return Ok(Some(sig));
+            }
+            let Some((_, notifications)) = &watch else {
+                thread::sleep(STATUS_POLL);
+                continue;
+            };
+            let until_rebroadcast = last_broadcast
+                .map_or(Duration::ZERO, |t| {
+                    self.opts.rebroadcast_interval.saturating_sub(t.elapsed())
+                })
+                .max(STATUS_POLL);
+            match notifications.recv_timeout(until_rebroadcast) {
+                Ok(update) => {
+                    if let RpcSignatureResult::ProcessedSignature(result) = update.value {
+                        return self.landed(&sig, update.context.slot, result.err).map(Some);
+                    }
+                }
+                Err(e) if e.is_disconnected() => {
+                    warn!("Signature subscription for {} closed; polling instead", sig);
+                    watch = None;
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    /// A confirmation subscription on the first websocket endpoint that takes it.
+    fn subscribe(&self, sig: &Signature) -> Option<SignatureSubscription> {
+        let config = RpcSignatureSubscribeConfig {
+            commitment: Some(CommitmentConfig::confirmed()),
+            enable_received_notification: Some(false),
+        };
+        for (i, url) in self.rpcs.websocket_urls() {
+            let start = Instant::now();
+            match PubsubClient::signature_subscribe(url, sig, Some(config.clone())) {
+                Ok(subscription) => {
+                    self.rpcs.record(i, start.elapsed(), None);
+                    return Some(subscription);
+                }
+                Err(e) => {
+                    warn!("Signature subscription via {} failed: {}", url, e);
+                    let e = ArbError::Rpc(format!("signature subscription: {e}")).into();
+                    self.rpcs.record(i, start.elapsed(), Some(&e));
+                }
+            }
+        }
+        None
+    }
+
+    /// Sends `tx` to every send endpoint; returns how many accepted it, and the errors.
+    fn broadcast(
+        &mut self,
+        generation: u32,
+        tx: &Transaction,
+        first: bool,
+    ) -> (usize, Vec<String>) {
+        let config = RpcSendTransactionConfig {
+            skip_preflight: self.opts.skip_preflight || !first,
+            preflight_commitment: Some(CommitmentLevel::Processed),
+            // Rebroadcasting is done here, not by the node
+            max_retries: Some(0),
+            ..Default::default()
+        };
+        let elapsed_ms = self.started.elapsed().as_millis() as u64;
+        let mut attempts = Vec::new();
+        for (i, rpc) in self.rpcs.send_targets() {
+            let start = Instant::now();
+            let error = rpc
+                .send_transaction_with_config(tx, config)
+                .err()
+                .map(send_error);
+            self.rpcs.record(i, start.elapsed(), error.as_ref());
+            let error = error.map(|e| format!("{e:#}"));
+            if let Some(e) = &error {
+                warn!("Send to {} failed: {}", self.rpcs.url(i), e);
+            }
+            attempts.push(SendAttempt {
+                generation,
+                endpoint: self.rpcs.url(i).to_string(),
+                signature: tx.signatures[0].to_string(),
+                elapsed_ms,
+                error,
+            });
+        }
+        let errors: Vec<String> = attempts.iter().filter_map(|a| a.error.clone()).collect();
+        let accepted = attempts.len() - errors.len();
+        self.attempts.extend(attempts);
+        (accepted, errors)
+    }
+
+    /// Status errors are transient: the next poll tries again.
+    fn confirmed(&mut self, sig: &Signature) -> Result<Option<Signature>> {
+        let status = match self.rpcs.call("get_signature_statuses", |rpc| {
+            Ok(rpc.get_signature_statuses(&[*sig])?)
+        }) {
+            Ok(r) => r.value.into_iter().next().flatten(),
+            Err(e) => {
+                warn!("Signature status for {} failed: {:#}", sig, e);
+                None
+            }
+        };
+        let Some(status) = status.filter(|s| s.satisfies_commitment(CommitmentConfig::confirmed()))
+        else {
+            return Ok(None);
+        };
+        self.landed(sig, status.slot, status.err).map(Some)
+    }
+
+    fn landed(
+        &mut self,
+        sig: &Signature,
+        slot: u64,
+        err: Option<TransactionError>,
+    ) -> Result<Signature> {
+        self.landed_slot = Some(slot);
+        self.confirmed_after_ms = Some(self.started.elapsed().as_millis() as u64);
+        match err {
+            Some(err) => Err(ArbError::Send(format!("{sig} failed in slot {slot}: {err}")).into()),
+            None => Ok(*sig),
+        }
+    }
+
+    fn block_height(&self) -> Result<u64> {
+        self.rpcs.call("get_block_height", |rpc| {
+            rpc.get_block_height_with_commitment(CommitmentConfig::confirmed())
+                .map_err(|e| ArbError::Rpc(format!("fetch block height: {e}")).into())
+        })
+    }
+
+    // Without a known last valid height (e.g. `submit`), ask whether the blockhash is valid
+    fn expired(&self, tx: &Transaction, deadline: Option<u64>) -> bool {
+        let expired = match deadline {
+            Some(deadline) => self.block_height().map(|h| past_deadline(h, deadline)),
+            None => self.rpcs.call("is_blockhash_valid", |rpc| {
+                let valid = rpc.is_blockhash_valid(
+                    &tx.message.recent_blockhash,
+                    CommitmentConfig::confirmed(),
+                )?;
+                Ok(!valid)
+            }),
+        };
+        expired.unwrap_or_else(|e| {
+            warn!("Expiry check failed: {:#}", e);
+            false
+        })
+    }
+}
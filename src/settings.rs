@@ -0,0 +1,353 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    env,
+    fmt::Display,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    amount::DecimalAmount,
+    cli::{AppState, Cli, default_state},
+    endpoints::RpcEndpoint,
+    error::ArbError,
+    notify::NotifierConfig,
+    policy::Rule,
+    twap::TwapGuard,
+};
+
+/// Every state key can be overridden by `SOLANA_ARB_<KEY>`, e.g. `SOLANA_ARB_RPC_URL`.
+pub const ENV_PREFIX: &str = "SOLANA_ARB_";
+pub const CONFIG_FILE_ENV: &str = "SOLANA_ARB_CONFIG_FILE";
+pub const PROFILE_ENV: &str = "SOLANA_ARB_PROFILE";
+
+/// Optional TOML file layered between the profile state and environment variables.
+/// Uses the same keys as `state.json`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub pool_a: Option<String>,
+    pub pool_b: Option<String>,
+    pub mint_in: Option<String>,
+    pub mint_out: Option<String>,
+    pub reference_pool: Option<String>,
+    pub amount_in: Option<DecimalAmount>,
+    pub spread_threshold_bps: Option<u32>,
+    pub slippage_bps: Option<u32>,
+    pub min_pnl_raw: Option<u64>,
+    pub decision_policy: Option<Rule>,
+    pub twap_guard: Option<TwapGuard>,
+    pub priority_fee_microlamports: Option<u64>,
+    pub simulate_only: Option<bool>,
+    pub rpc_url: Option<String>,
+    pub rpc_endpoints: Option<Vec<RpcEndpoint>>,
+    pub keypair_path: Option<PathBuf>,
+    pub fee_payer: Option<PathBuf>,
+    pub nonce_account: Option<String>,
+    pub profit_guard_program: Option<String>,
+    pub notifiers: Option<Vec<NotifierConfig>>,
+}
+
+/// An effective value and the layer it came from: `default`, `profile:<name>`,
+/// `file:<path>`, `env:<VAR>` or `flag:--<name>`.
+#[derive(Debug, Clone)]
+pub struct Sourced<T> {
+    pub value: T,
+    pub source: String,
+}
+
+/// Runtime settings after applying defaults → profile state → config file → env → flags.
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    pub pool_a: Option<Sourced<String>>,
+    pub pool_b: Option<Sourced<String>>,
+    pub mint_in: Option<Sourced<String>>,
+    pub mint_out: Option<Sourced<String>>,
+    pub reference_pool: Option<Sourced<String>>,
+    pub amount_in: Option<Sourced<DecimalAmount>>,
+    pub spread_threshold_bps: Option<Sourced<u32>>,
+    pub slippage_bps: Option<Sourced<u32>>,
+    pub min_pnl_raw: Option<Sourced<u64>>,
+    pub decision_policy: Option<Sourced<Rule>>,
+    pub twap_guard: Option<Sourced<TwapGuard>>,
+    pub priority_fee_microlamports: Option<Sourced<u64>>,
+    pub simulate_only: Option<Sourced<bool>>,
+    pub rpc_url: Option<Sourced<String>>,
+    pub rpc_endpoints: Option<Sourced<Vec<RpcEndpoint>>>,
+    pub keypair_path: Option<Sourced<PathBuf>>,
+    pub fee_payer: Option<Sourced<PathBuf>>,
+    pub nonce_account: Option<Sourced<String>>,
+    pub profit_guard_program: Option<Sourced<String>>,
+    pub notifiers: Option<Sourced<Vec<NotifierConfig>>>,
+}
+
+/// Config file path from `--config-file`, else `SOLANA_ARB_CONFIG_FILE`.
+pub fn config_file_path(flag: Option<&Path>) -> Option<PathBuf> {
+    flag.map(Path::to_path_buf).or_else(|| {
+        env::var(CONFIG_FILE_ENV)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(PathBuf::from)
+    })
+}
+
+pub fn load_config_file(path: &Path) -> Result<FileConfig> {
+    let data = fs::read_to_string(path)
+        .map_err(|e| ArbError::Config(format!("read config file {}: {e}", path.display())))?;
+    let cfg = toml::from_str(&data)
+        .map_err(|e| ArbError::Config(format!("parse config file {}: {e}", path.display())))?;
+    Ok(cfg)
+}
+
+fn env_value<T: FromStr>(key: &str) -> Result<(Option<T>, String)>
+where
+    T::Err: Display,
+{
+    let var = format!("{ENV_PREFIX}{}", key.to_uppercase());
+    let value = match env::var(&var) {
+        Ok(s) if !s.is_empty() => Some(
+            s.parse::<T>()
+                .map_err(|e| ArbError::Config(format!("invalid {var}={s}: {e}")))?,
+        ),
+        _ => None,
+    };
+    Ok((value, format!("env:{var}")))
+}
+
+// Highest layer last
+fn pick<T>(layers: Vec<(Option<T>, String)>) -> Option<Sourced<T>> {
+    layers
+        .into_iter()
+        .rev()
+        .find_map(|(v, source)| v.map(|value| Sourced { value, source }))
+}
+
+fn expand(path: PathBuf) -> PathBuf {
+    shellexpand::tilde(&path.to_string_lossy())
+        .to_string()
+        .into()
+}
+
+/// Resolve every runtime setting and remember where it came from.
+pub fn resolve(
+    state: &AppState,
+    profile: &str,
+    file: Option<(&Path, &FileConfig)>,
+    cli: &Cli,
+) -> Result<EffectiveConfig> {
+    let defaults = default_state();
+    let empty = FileConfig::default();
+    let (file_cfg, file_src) = match file {
+        Some((path, cfg)) => (cfg, format!("file:{}", path.display())),
+        None => (&empty, "file".to_string()),
+    };
+    let d = "default".to_string();
+    let s = format!("profile:{profile}");
+    let f = file_src;
+
+    macro_rules! layered {
+        ($field:ident, $flag:expr, $flag_name:expr) => {
+            pick(vec![
+                (defaults.$field.clone(), d.clone()),
+                (state.$field.clone(), s.clone()),
+                (file_cfg.$field.clone(), f.clone()),
+                env_value(stringify!($field))?,
+                ($flag, format!("flag:--{}", $flag_name)),
+            ])
+        };
+        ($field:ident) => {
+            layered!($field, None, "")
+        };
+    }
+
+    let notifiers_env = match env::var(format!("{ENV_PREFIX}NOTIFIERS")) {
+        Ok(json) if !json.is_empty() => Some(serde_json::from_str(&json).map_err(|e| {
+            ArbError::Config(format!("invalid {ENV_PREFIX}NOTIFIERS (JSON array): {e}"))
+        })?),
+        _ => None,
+    };
+    let non_empty = |v: &Vec<NotifierConfig>| (!v.is_empty()).then(|| v.clone());
+    // Whitespace-separated `[ROLES=]URL` specs, like repeated --rpc-endpoint flags
+    let endpoints_env = match env::var(format!("{ENV_PREFIX}RPC_ENDPOINTS")) {
+        Ok(specs) if !specs.trim().is_empty() => Some(
+            specs
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<Vec<RpcEndpoint>, _>>()
+                .map_err(|e| ArbError::Config(format!("invalid {ENV_PREFIX}RPC_ENDPOINTS: {e}")))?,
+        ),
+        _ => None,
+    };
+    let some_endpoints = |v: &Vec<RpcEndpoint>| (!v.is_empty()).then(|| v.clone());
+
+    let mut eff = EffectiveConfig {
+        pool_a: layered!(pool_a),
+        pool_b: layered!(pool_b),
+        mint_in: layered!(mint_in),
+        mint_out: layered!(mint_out),
+        reference_pool: layered!(reference_pool, cli.reference_pool.clone(), "reference-pool"),
+        amount_in: layered!(amount_in, cli.amount_in, "amount-in"),
+        spread_threshold_bps: layered!(
+            spread_threshold_bps,
+            cli.spread_threshold_bps,
+            "spread-threshold-bps"
+        ),
+        slippage_bps: layered!(slippage_bps, cli.slippage_bps, "slippage-bps"),
+        min_pnl_raw: layered!(min_pnl_raw, cli.min_pnl_raw, "min-pnl-raw"),
+        decision_policy: layered!(
+            decision_policy,
+            cli.decision_policy.clone(),
+            "decision-policy"
+        ),
+        twap_guard: layered!(twap_guard, cli.twap_guard.clone(), "twap-guard"),
+        priority_fee_microlamports: layered!(
+            priority_fee_microlamports,
+            cli.priority_fee,
+            "priority-fee"
+        ),
+        simulate_only: layered!(simulate_only, cli.simulate_only, "simulate-only"),
+        rpc_url: layered!(rpc_url, cli.rpc_url.clone(), "rpc-url"),
+        rpc_endpoints: pick(vec![
+            (some_endpoints(&defaults.rpc_endpoints), d.clone()),
+            (some_endpoints(&state.rpc_endpoints), s.clone()),
+            (file_cfg.rpc_endpoints.clone(), f.clone()),
+            (endpoints_env, format!("env:{ENV_PREFIX}RPC_ENDPOINTS")),
+            (
+                some_endpoints(&cli.rpc_endpoints),
+                "flag:--rpc-endpoint".to_string(),
+            ),
+        ]),
+        keypair_path: layered!(keypair_path, cli.keypair.clone(), "keypair"),
+        fee_payer: layered!(fee_payer, cli.fee_payer.clone(), "fee-payer"),
+        nonce_account: layered!(nonce_account, cli.nonce_account.clone(), "nonce-account"),
+        profit_guard_program: layered!(
+            profit_guard_program,
+            cli.profit_guard_program.clone(),
+            "profit-guard-program"
+        ),
+        notifiers: pick(vec![
+            (non_empty(&defaults.notifiers), d.clone()),
+            (non_empty(&state.notifiers), s.clone()),
+            (file_cfg.notifiers.clone(), f.clone()),
+            (notifiers_env, format!("env:{ENV_PREFIX}NOTIFIERS")),
+        ]),
+    };
+
+    for kp in [eff.keypair_path.as_mut(), eff.fee_payer.as_mut()]
+        .into_iter()
+        .flatten()
+    {
+        kp.value = expand(kp.value.clone());
+    }
+    if let Some(endpoints) = &eff.rpc_endpoints {
+        for endpoint in &endpoints.value {
+            endpoint
+                .check()
+                .map_err(|e| ArbError::Config(format!("{e} (from {})", endpoints.source)))?;
+        }
+    }
+    if let Some(policy) = &eff.decision_policy {
+        policy
+            .value
+            .check()
+            .map_err(|e| ArbError::Config(format!("{e} (from {})", policy.source)))?;
+    }
+    if let Some(guard) = &eff.twap_guard {
+        guard
+            .value
+            .check()
+            .map_err(|e| ArbError::Config(format!("{e} (from {})", guard.source)))?;
+    }
+    Ok(eff)
+}
+
+/// Value of a required setting, or a config error naming every way to provide it.
+pub fn require<T: Clone>(v: &Option<Sourced<T>>, key: &str) -> Result<T> {
+    v.as_ref().map(|s| s.value.clone()).ok_or_else(|| {
+        ArbError::Config(format!(
+            "missing required parameter `{key}`: set it with `config set`, a config file or {ENV_PREFIX}{}",
+            key.to_uppercase()
+        ))
+        .into()
+    })
+}
+
+fn row<T: Display>(key: &'static str, v: &Option<Sourced<T>>) -> (&'static str, String, String) {
+    match v {
+        Some(s) => (key, s.value.to_string(), s.source.clone()),
+        None => (key, "-unset-".to_string(), "-".to_string()),
+    }
+}
+
+impl EffectiveConfig {
+    fn rows(&self) -> Vec<(&'static str, String, String)> {
+        let display = |v: &Option<Sourced<PathBuf>>| {
+            v.as_ref().map(|s| Sourced {
+                value: s.value.display().to_string(),
+                source: s.source.clone(),
+            })
+        };
+        let keypair = display(&self.keypair_path);
+        let fee_payer = display(&self.fee_payer);
+        let endpoints = self.rpc_endpoints.as_ref().map(|s| Sourced {
+            value: s
+                .value
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(" "),
+            source: s.source.clone(),
+        });
+        let notifiers = self.notifiers.as_ref().map(|s| Sourced {
+            value: format!("{} sink(s)", s.value.len()),
+            source: s.source.clone(),
+        });
+        vec![
+            row("rpc_url", &self.rpc_url),
+            row("rpc_endpoints", &endpoints),
+            row("keypair_path", &keypair),
+            row("fee_payer", &fee_payer),
+            row("nonce_account", &self.nonce_account),
+            row("profit_guard_program", &self.profit_guard_program),
+            row("pool_a", &self.pool_a),
+            row("pool_b", &self.pool_b),
+            row("mint_in", &self.mint_in),
+            row("mint_out", &self.mint_out),
+            row("reference_pool", &self.reference_pool),
+            row("amount_in", &self.amount_in),
+            row("spread_threshold_bps", &self.spread_threshold_bps),
+            row("slippage_bps", &self.slippage_bps),
+            row("min_pnl_raw", &self.min_pnl_raw),
+            row("decision_policy", &self.decision_policy),
+            row("twap_guard", &self.twap_guard),
+            row(
+                "priority_fee_microlamports",
+                &self.priority_fee_microlamports,
+            ),
+            row("simulate_only", &self.simulate_only),
+            row("notifiers", &notifiers),
+        ]
+    }
+
+    /// `key -> source` for every value that is set, as written to the run report.
+    pub fn sources(&self) -> BTreeMap<String, String> {
+        self.rows()
+            .into_iter()
+            .filter(|(_, _, source)| source != "-")
+            .map(|(k, _, source)| (k.to_string(), source))
+            .collect()
+    }
+
+    /// Backs `config show --effective`.
+    pub fn print(&self) {
+        let rows = self.rows();
+        let kw = rows.iter().map(|(k, _, _)| k.len()).max().unwrap_or(0);
+        let vw = rows.iter().map(|(_, v, _)| v.len()).max().unwrap_or(0);
+        for (k, v, source) in rows {
+            println!("{:<kw$}  {:<vw$}  {}", k, v, source, kw = kw, vw = vw);
+        }
+    }
+}
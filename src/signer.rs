@@ -0,0 +1,161 @@
+use anyhow::Result;
+use dialoguer::Password;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signer::{Signer, keypair::Keypair, null_signer::NullSigner},
+};
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead, IsTerminal},
+    path::PathBuf,
+};
+use zeroize::Zeroize;
+
+use crate::{error::ArbError, keystore};
+
+/// Where the payer's secret key comes from, written as the `keypair` setting:
+///
+/// - `<path>` or `file:<path>`: Solana CLI JSON byte array (or a base58 string)
+/// - `keystore:<path>`: encrypted keystore created by `keypair new` / `keypair import`
+/// - `env:<VAR>`: JSON byte array or base58 string in an environment variable
+/// - `stdin`: JSON byte array or base58 string read from standard input
+/// - `pubkey:<ADDRESS>`: no secret at all; the transaction is left unsigned for this key
+///   (`--sign-only`, simulation), to be signed later with `sign`/`submit`
+///
+/// Secrets themselves never go into state, reports or logs, only the source.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SignerSource {
+    File(PathBuf),
+    Keystore(PathBuf),
+    Env(String),
+    Stdin,
+    Pubkey(Pubkey),
+}
+
+fn expand(path: &str) -> PathBuf {
+    shellexpand::tilde(path).to_string().into()
+}
+
+impl SignerSource {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let spec = spec.trim();
+        let source = if spec == "stdin" || spec == "-" {
+            SignerSource::Stdin
+        } else if let Some(var) = spec.strip_prefix("env:") {
+            if var.is_empty() {
+                return Err(ArbError::Config("`env:` signer needs a variable name".into()).into());
+            }
+            SignerSource::Env(var.to_string())
+        } else if let Some(path) = spec.strip_prefix("keystore:") {
+            SignerSource::Keystore(expand(path))
+        } else if let Some(addr) = spec.strip_prefix("pubkey:") {
+            SignerSource::Pubkey(
+                addr.parse().map_err(|e| {
+                    ArbError::Config(format!("invalid pubkey signer `{addr}`: {e}"))
+                })?,
+            )
+        } else if let Some(path) = spec.strip_prefix("file:") {
+            SignerSource::File(expand(path))
+        } else if spec.is_empty() {
+            return Err(ArbError::Config("empty keypair source".into()).into());
+        } else {
+            SignerSource::File(expand(spec))
+        };
+        Ok(source)
+    }
+
+    /// Read the secret key (may prompt for a keystore password or read stdin).
+    pub fn load(&self) -> Result<Keypair> {
+        match self {
+            SignerSource::File(path) => {
+                let mut text = fs::read_to_string(path).map_err(|e| {
+                    ArbError::Config(format!("read keypair {}: {e}", path.display()))
+                })?;
+                let kp = parse_secret(&text);
+                text.zeroize();
+                kp
+            }
+            SignerSource::Keystore(path) => {
+                let ks = keystore::read(path)?;
+                keystore::decrypt(&ks, &keystore::password(false)?)
+            }
+            SignerSource::Env(var) => {
+                let mut text = env::var(var).map_err(|_| {
+                    ArbError::Config(format!("environment variable {var} is not set"))
+                })?;
+                let kp = parse_secret(&text);
+                text.zeroize();
+                kp
+            }
+            SignerSource::Stdin => {
+                let mut text = if io::stdin().is_terminal() {
+                    Password::new()
+                        .with_prompt("Secret key (base58 or JSON byte array)")
+                        .interact()?
+                } else {
+                    let mut line = String::new();
+                    io::stdin().lock().read_line(&mut line)?;
+                    line
+                };
+                let kp = parse_secret(&text);
+                text.zeroize();
+                kp
+            }
+            SignerSource::Pubkey(pk) => Err(ArbError::Config(format!(
+                "`pubkey:{pk}` has no secret key; it can only be used to build unsigned transactions"
+            ))
+            .into()),
+        }
+    }
+
+    /// A signer for building transactions; `pubkey:` sources yield a placeholder that
+    /// leaves its signature empty.
+    pub fn signer(&self) -> Result<Box<dyn Signer>> {
+        match self {
+            SignerSource::Pubkey(pk) => Ok(Box::new(NullSigner::new(pk))),
+            _ => Ok(Box::new(self.load()?)),
+        }
+    }
+
+    pub fn has_secret(&self) -> bool {
+        !matches!(self, SignerSource::Pubkey(_))
+    }
+
+    /// Cheap check for `config set`: files must exist and keystores must parse, without
+    /// asking for a password or consuming stdin.
+    pub fn validate(&self) -> Result<()> {
+        match self {
+            SignerSource::File(_) => self.load().map(|_| ()),
+            SignerSource::Keystore(path) => keystore::read(path).map(|_| ()),
+            SignerSource::Env(_) | SignerSource::Stdin | SignerSource::Pubkey(_) => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for SignerSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerSource::File(path) => write!(f, "{}", path.display()),
+            SignerSource::Keystore(path) => write!(f, "keystore:{}", path.display()),
+            SignerSource::Env(var) => write!(f, "env:{var}"),
+            SignerSource::Stdin => write!(f, "stdin"),
+            SignerSource::Pubkey(pk) => write!(f, "pubkey:{pk}"),
+        }
+    }
+}
+
+/// A secret key as a Solana CLI JSON byte array or a base58 string.
+pub fn parse_secret(text: &str) -> Result<Keypair> {
+    let text = text.trim();
+    let mut bytes: Vec<u8> = if text.starts_with('[') {
+        serde_json::from_str(text)
+            .map_err(|e| ArbError::Config(format!("secret key is not a JSON byte array: {e}")))?
+    } else {
+        bs58::decode(text)
+            .into_vec()
+            .map_err(|e| ArbError::Config(format!("secret key is not base58: {e}")))?
+    };
+    let kp = Keypair::from_bytes(&bytes);
+    bytes.zeroize();
+    Ok(kp.map_err(|e| ArbError::Config(format!("invalid secret key: {e}")))?)
+}
@@ -1,21 +1,81 @@
 use anyhow::Result;
+use base64::{Engine, engine::general_purpose::STANDARD as B64};
 use carbon_raydium_cpmm_decoder::accounts::pool_state::PoolState;
 use raydium_cpmm::instructions::SwapBaseInputBuilder;
-use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_client::{
+    rpc_client::RpcClient,
+    rpc_config::RpcSimulateTransactionConfig,
+    rpc_response::{Response, RpcSimulateTransactionResult},
+};
 use solana_sdk::{
-    compute_budget::ComputeBudgetInstruction,
-    instruction::Instruction,
-    message::Message,
-    pubkey::Pubkey,
-    signer::{Signer, keypair::Keypair},
-    system_instruction,
+    commitment_config::CommitmentConfig, compute_budget::ComputeBudgetInstruction, hash::Hash,
+    instruction::Instruction, message::Message, pubkey::Pubkey, signature::Signature,
+    signer::Signer, system_instruction, system_instruction::SystemInstruction, system_program,
     transaction::Transaction,
 };
 
-use crate::{arbitrage::SOL_MINT, pool::PoolData, utils::TokenAccount};
+use crate::{
+    arbitrage::SOL_MINT, error::ArbError, nonce::DurableNonce, pool::PoolData, utils::TokenAccount,
+};
 
 const COMPUTE_UNIT_LIMIT: u32 = 400_000;
 
+/// Balance assertion appended after the second swap: the transaction reverts unless the
+/// input token account ends with at least `min_balance_raw`, i.e. its balance before the
+/// swaps plus `min_profit_raw`.
+#[derive(Debug, Clone)]
+pub struct ProfitGuard {
+    pub program_id: Pubkey,
+    pub token_account: Pubkey,
+    pub min_profit_raw: u64,
+    pub min_balance_raw: u64,
+}
+
+impl ProfitGuard {
+    /// `ata_in` as checked before building; a wrapped SOL account created by the
+    /// transaction starts with `amount_in`.
+    pub fn new(
+        program_id: Pubkey,
+        ata_in: &TokenAccount,
+        amount_in: u64,
+        min_profit_raw: u64,
+    ) -> Result<Self, ArbError> {
+        let start = if ata_in.exists {
+            ata_in.amount
+        } else if ata_in.mint == SOL_MINT.parse::<Pubkey>().unwrap() {
+            amount_in
+        } else {
+            0
+        };
+        let min_balance_raw = start.checked_add(min_profit_raw).ok_or_else(|| {
+            ArbError::math(format!(
+                "profit guard overflow: balance {start} + min profit {min_profit_raw}"
+            ))
+        })?;
+        Ok(Self {
+            program_id,
+            token_account: ata_in.ata,
+            min_profit_raw,
+            min_balance_raw,
+        })
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        profit_guard::instruction::assert_min_balance(
+            &self.program_id,
+            &self.token_account,
+            self.min_balance_raw,
+        )
+    }
+}
+
+/// A signed transaction and the last block height its blockhash is valid for
+/// (`None` on a durable nonce, which does not expire).
+pub struct BuiltTransaction {
+    pub tx: Transaction,
+    pub last_valid_block_height: Option<u64>,
+}
+
 pub fn create_ata_instruction(payer: &Pubkey, wallet: &Pubkey, mint: &Pubkey) -> Instruction {
     let create_ata_ix = spl_associated_token_account::instruction::create_associated_token_account(
         payer,
@@ -88,7 +148,8 @@ pub fn create_swap_instruction(
 
 pub fn create_arbitrage_transaction(
     rpc: &RpcClient,
-    payer: &Keypair,
+    fee_payer: &dyn Signer,
+    authority: &dyn Signer,
     pool_in: &PoolData,
     pool_out: &PoolData,
     amount_in: u64,
@@ -96,9 +157,13 @@ pub fn create_arbitrage_transaction(
     atas: Vec<TokenAccount>,
     min_out: u64,
     priority_fee: u64,
-) -> Result<Transaction> {
+    nonce: Option<&DurableNonce>,
+    guard: Option<&ProfitGuard>,
+) -> Result<BuiltTransaction> {
     let mut instructions = Vec::new();
-    let payer_pubkey = payer.pubkey();
+    // The authority owns the token accounts and funds their rent and wrapped SOL; the
+    // fee payer only pays network and priority fees.
+    let owner = authority.pubkey();
 
     // 1. Add compute budget instructions
     instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
@@ -111,32 +176,20 @@ pub fn create_arbitrage_transaction(
     for ata in &atas {
         if !ata.exists {
             if ata.mint == SOL_MINT.parse::<Pubkey>().unwrap() {
-                instructions.push(create_ata_instruction(
-                    &payer_pubkey,
-                    &payer_pubkey,
-                    &ata.mint,
-                ));
-                instructions.push(system_instruction::transfer(
-                    &payer_pubkey,
-                    &ata.ata,
-                    amount_in,
-                ));
+                instructions.push(create_ata_instruction(&owner, &owner, &ata.mint));
+                instructions.push(system_instruction::transfer(&owner, &ata.ata, amount_in));
                 instructions.push(spl_token::instruction::sync_native(
                     &spl_token::id(),
                     &ata.ata,
                 )?);
             } else {
-                instructions.push(create_ata_instruction(
-                    &payer_pubkey,
-                    &payer_pubkey,
-                    &ata.mint,
-                ));
+                instructions.push(create_ata_instruction(&owner, &owner, &ata.mint));
             }
         }
     }
     let swap_direction = pool_in.state.token0_mint == atas[0].mint;
     let swap1_ix = create_swap_instruction(
-        &payer_pubkey,
+        &owner,
         &pool_in.pool_id,
         &pool_in.state,
         &atas[0].ata,
@@ -149,7 +202,7 @@ pub fn create_arbitrage_transaction(
 
     let swap_direction = pool_out.state.token0_mint == atas[1].mint;
     let swap2_ix = create_swap_instruction(
-        &payer_pubkey,
+        &owner,
         &pool_out.pool_id,
         &pool_out.state,
         &atas[1].ata,
@@ -160,28 +213,119 @@ pub fn create_arbitrage_transaction(
     )?;
     instructions.push(swap2_ix);
 
-    let recent_blockhash = rpc.get_latest_blockhash()?;
+    // `min_out` only bounds the second leg; the guard bounds the whole round trip
+    if let Some(guard) = guard {
+        instructions.push(guard.instruction());
+    }
+
+    build_transaction(instructions, fee_payer, authority, nonce, || {
+        Ok(rpc.get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())?)
+    })
+}
+
+/// Sign `instructions` as one transaction paid by `fee_payer`. With a durable nonce its
+/// advance becomes instruction 0 and its stored blockhash is used, so `latest_blockhash`
+/// is not called. Signers without a secret (`NullSigner`) leave their signature empty.
+pub fn build_transaction(
+    instructions: Vec<Instruction>,
+    fee_payer: &dyn Signer,
+    authority: &dyn Signer,
+    nonce: Option<&DurableNonce>,
+    latest_blockhash: impl FnOnce() -> Result<(Hash, u64)>,
+) -> Result<BuiltTransaction> {
+    let payer_pubkey = fee_payer.pubkey();
+    // A durable nonce must be advanced by the first instruction; its authority has to be
+    // one of the signers
+    let instructions: Vec<Instruction> = nonce
+        .map(DurableNonce::advance_instruction)
+        .into_iter()
+        .chain(instructions)
+        .collect();
+    let (recent_blockhash, last_valid_block_height) = match nonce {
+        Some(nonce) => (nonce.blockhash, None),
+        None => {
+            let (hash, height) = latest_blockhash()?;
+            (hash, Some(height))
+        }
+    };
     let message = Message::new(&instructions, Some(&payer_pubkey));
-    let transaction = Transaction::new(&[payer], message, recent_blockhash);
+    let mut signers: Vec<&dyn Signer> = vec![fee_payer];
+    if authority.pubkey() != payer_pubkey {
+        signers.push(authority);
+    }
+    let transaction = Transaction::new(signers.as_slice(), message, recent_blockhash);
+
+    Ok(BuiltTransaction {
+        tx: transaction,
+        last_valid_block_height,
+    })
+}
+
+/// Wire format used by `--sign-only`, `sign` and `submit`.
+pub fn encode_transaction(tx: &Transaction) -> Result<String> {
+    Ok(B64.encode(bincode::serialize(tx)?))
+}
+
+pub fn decode_transaction(encoded: &str) -> Result<Transaction> {
+    let bytes = B64
+        .decode(encoded.trim())
+        .map_err(|e| ArbError::Config(format!("transaction is not base64: {e}")))?;
+    let tx = bincode::deserialize(&bytes)
+        .map_err(|e| ArbError::Config(format!("not a serialized transaction: {e}")))?;
+    Ok(tx)
+}
+
+/// Required signers that have not signed yet.
+pub fn missing_signers(tx: &Transaction) -> Vec<Pubkey> {
+    let required = tx.message.header.num_required_signatures as usize;
+    tx.message.account_keys[..required]
+        .iter()
+        .zip(&tx.signatures)
+        .filter(|(_, sig)| **sig == Signature::default())
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+/// Add the signatures of `signers` that the transaction still needs; others are ignored.
+/// Returns the keys that signed.
+pub fn sign_missing(tx: &mut Transaction, signers: &[Box<dyn Signer>]) -> Result<Vec<Pubkey>> {
+    let missing = missing_signers(tx);
+    let mut signed = Vec::new();
+    for signer in signers {
+        let key = signer.pubkey();
+        if missing.contains(&key) {
+            tx.try_partial_sign(&[signer.as_ref()], tx.message.recent_blockhash)?;
+            signed.push(key);
+        }
+    }
+    Ok(signed)
+}
 
-    Ok(transaction)
+/// True when the first instruction advances a durable nonce.
+pub fn uses_durable_nonce(tx: &Transaction) -> bool {
+    tx.message.instructions.first().is_some_and(|ix| {
+        tx.message.account_keys.get(ix.program_id_index as usize) == Some(&system_program::id())
+            && matches!(
+                bincode::deserialize(&ix.data),
+                Ok(SystemInstruction::AdvanceNonceAccount)
+            )
+    })
 }
 
+// Returns the full RPC response so callers also get the slot the simulation ran at
 pub fn simulate_transaction(
     rpc: &RpcClient,
     transaction: &Transaction,
-) -> Result<solana_client::rpc_response::RpcSimulateTransactionResult> {
+) -> Result<Response<RpcSimulateTransactionResult>> {
     let config = RpcSimulateTransactionConfig {
         sig_verify: false,
         replace_recent_blockhash: true,
-        commitment: Some(solana_sdk::commitment_config::CommitmentConfig::processed()),
+        commitment: Some(CommitmentConfig::processed()),
         encoding: None,
         accounts: None,
         min_context_slot: None,
         inner_instructions: true,
     };
 
-    Ok(rpc
-        .simulate_transaction_with_config(transaction, config)?
-        .value)
+    Ok(rpc.simulate_transaction_with_config(transaction, config)?)
 }
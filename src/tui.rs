@@ -0,0 +1,637 @@
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Row, Sparkline, Table},
+};
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    sync::{Arc, Mutex, mpsc::Receiver},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::report::{ArbitrageReport, CandidateReport, PoolValuesReport, RunReport};
+
+// Redraw and key polling period of the dashboard thread
+const TICK: Duration = Duration::from_millis(100);
+const SPREAD_HISTORY: usize = 240;
+const ACTIVITY_ROWS: usize = 20;
+const LOG_LINES: usize = 500;
+const SPREAD_STEP_BPS: u32 = 5;
+
+/// Log lines captured for the dashboard while it owns the terminal.
+#[derive(Clone, Default)]
+pub struct LogBuffer(Arc<Mutex<VecDeque<String>>>);
+
+// env_logger writes records in pieces; lines are split here
+struct LogPipe {
+    buffer: LogBuffer,
+    partial: Vec<u8>,
+}
+
+impl Write for LogPipe {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.partial.extend_from_slice(data);
+        while let Some(end) = self.partial.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.partial.drain(..=end).collect();
+            let mut lines = self.buffer.0.lock().unwrap();
+            lines.push_back(String::from_utf8_lossy(&line).trim_end().to_string());
+            if lines.len() > LOG_LINES {
+                lines.pop_front();
+            }
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Route `log` output into a buffer shown in the dashboard (default level `info`).
+pub fn init_logging() -> LogBuffer {
+    let buffer = LogBuffer::default();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|f, record| {
+            writeln!(
+                f,
+                "{} {:<5} {}",
+                Local::now().format("%H:%M:%S"),
+                record.level(),
+                record.args()
+            )
+        })
+        .target(env_logger::Target::Pipe(Box::new(LogPipe {
+            buffer: buffer.clone(),
+            partial: Vec::new(),
+        })))
+        .init();
+    buffer
+}
+
+/// Settings the keys change at runtime; applied before each evaluation.
+#[derive(Debug, Clone)]
+pub struct Controls {
+    paused: bool,
+    pub simulate_only: bool,
+    pub spread_threshold_bps: u32,
+    pub min_pnl_raw: u64,
+    evaluate_now: bool,
+    quit: bool,
+}
+
+impl Controls {
+    pub fn new(simulate_only: bool, spread_threshold_bps: u32, min_pnl_raw: u64) -> Self {
+        Self {
+            paused: false,
+            simulate_only,
+            spread_threshold_bps,
+            min_pnl_raw,
+            evaluate_now: false,
+            quit: false,
+        }
+    }
+}
+
+/// What the dashboard knows before the first report.
+pub struct Session {
+    pub title: String,
+    pub refresh: Duration,
+    /// Both signers hold a secret, so simulate-only may be switched off
+    pub can_send: bool,
+    /// No `decision_policy` is set: the spread threshold is the rule
+    pub threshold_is_policy: bool,
+}
+
+/// Show the dashboard on its own thread and call `evaluate` on this one every
+/// `session.refresh` (unless paused) until the user quits. Reports reach the dashboard
+/// through `reports`.
+pub fn run(
+    session: Session,
+    controls: Controls,
+    logs: LogBuffer,
+    reports: Receiver<RunReport>,
+    mut evaluate: impl FnMut(&Controls),
+) -> Result<()> {
+    let refresh = session.refresh;
+    let shared = Arc::new(Mutex::new(controls));
+    let ui = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            let result = ratatui::try_init()
+                .map_err(anyhow::Error::from)
+                .and_then(|terminal| {
+                    Dashboard::new(session, logs).run(terminal, &shared, &reports)
+                });
+            ratatui::restore();
+            // Whatever ended the dashboard also ends the evaluations
+            shared.lock().unwrap().quit = true;
+            result
+        })
+    };
+
+    let mut last_run: Option<Instant> = None;
+    loop {
+        let controls = shared.lock().unwrap().clone();
+        if controls.quit {
+            break;
+        }
+        let due = last_run.is_none_or(|t| t.elapsed() >= refresh);
+        if controls.evaluate_now || (!controls.paused && due) {
+            shared.lock().unwrap().evaluate_now = false;
+            evaluate(&controls);
+            last_run = Some(Instant::now());
+        }
+        thread::sleep(TICK);
+    }
+    ui.join()
+        .map_err(|_| anyhow!("dashboard thread panicked"))?
+}
+
+// One row of the recent activity table
+struct Activity {
+    time: String,
+    mode: String,
+    status: String,
+    signature: Option<String>,
+}
+
+struct Dashboard {
+    session: Session,
+    logs: LogBuffer,
+    last: Option<RunReport>,
+    spreads: Vec<u64>, // bps * 100, oldest first
+    activity: VecDeque<Activity>,
+    evaluations: u64,
+    confirm_live: bool,
+    status: String,
+}
+
+impl Dashboard {
+    fn new(session: Session, logs: LogBuffer) -> Self {
+        Self {
+            session,
+            logs,
+            last: None,
+            spreads: Vec::new(),
+            activity: VecDeque::new(),
+            evaluations: 0,
+            confirm_live: false,
+            status: String::new(),
+        }
+    }
+
+    fn run(
+        mut self,
+        mut terminal: DefaultTerminal,
+        shared: &Mutex<Controls>,
+        reports: &Receiver<RunReport>,
+    ) -> Result<()> {
+        loop {
+            while let Ok(report) = reports.try_recv() {
+                self.record(report);
+            }
+            let controls = shared.lock().unwrap().clone();
+            terminal.draw(|frame| self.render(frame, &controls))?;
+            if !event::poll(TICK)? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if !self.on_key(key.code, shared) {
+                        return Ok(());
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Applies a key to the shared controls; false quits
+    fn on_key(&mut self, code: KeyCode, shared: &Mutex<Controls>) -> bool {
+        let mut c = shared.lock().unwrap();
+        if self.confirm_live {
+            self.confirm_live = false;
+            if code == KeyCode::Char('y') {
+                c.simulate_only = false;
+                self.status = "LIVE: profitable quotes are sent".to_string();
+            } else {
+                self.status = "still simulating".to_string();
+            }
+            return true;
+        }
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                c.quit = true;
+                return false;
+            }
+            KeyCode::Char('p') | KeyCode::Char(' ') => {
+                c.paused = !c.paused;
+                self.status = if c.paused { "paused" } else { "resumed" }.to_string();
+            }
+            KeyCode::Char('r') => {
+                c.evaluate_now = true;
+                self.status = "evaluating now".to_string();
+            }
+            KeyCode::Char('s') if c.simulate_only => {
+                if self.session.can_send {
+                    self.confirm_live = true;
+                    self.status = "send real transactions? y to confirm".to_string();
+                } else {
+                    self.status =
+                        "cannot send: the keypair or fee payer has no secret key".to_string();
+                }
+            }
+            KeyCode::Char('s') => {
+                c.simulate_only = true;
+                self.status = "simulate-only".to_string();
+            }
+            KeyCode::Char('+') | KeyCode::Char('=') | KeyCode::Char('-') => {
+                c.spread_threshold_bps = if code == KeyCode::Char('-') {
+                    c.spread_threshold_bps.saturating_sub(SPREAD_STEP_BPS)
+                } else {
+                    c.spread_threshold_bps.saturating_add(SPREAD_STEP_BPS)
+                };
+                self.status = format!("spread threshold {} bps", c.spread_threshold_bps);
+                if !self.session.threshold_is_policy {
+                    self.status
+                        .push_str(" (a decision policy is set; it decides)");
+                }
+            }
+            KeyCode::Char(']') => {
+                c.min_pnl_raw = c.min_pnl_raw.saturating_mul(2).max(1);
+                self.status = format!("min_pnl_raw {}", c.min_pnl_raw);
+            }
+            KeyCode::Char('[') => {
+                c.min_pnl_raw /= 2;
+                self.status = format!("min_pnl_raw {}", c.min_pnl_raw);
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn record(&mut self, report: RunReport) {
+        self.evaluations += 1;
+        let time = Local::now().format("%H:%M:%S").to_string();
+        match &report {
+            RunReport::Completed(r) => {
+                self.spreads
+                    .push((r.prices.spread_bps * 100.0).round().max(0.0) as u64);
+                if self.spreads.len() > SPREAD_HISTORY {
+                    self.spreads.remove(0);
+                }
+                if r.tx.mode != "skip" {
+                    self.push_activity(Activity {
+                        time,
+                        mode: r.tx.mode.clone(),
+                        status: tx_status(r),
+                        signature: r.tx.signature.clone(),
+                    });
+                }
+            }
+            RunReport::Failed(r) => self.push_activity(Activity {
+                time,
+                mode: "error".to_string(),
+                status: format!("{}: {}", r.error.kind, r.error.message),
+                signature: None,
+            }),
+        }
+        self.last = Some(report);
+    }
+
+    fn push_activity(&mut self, a: Activity) {
+        self.activity.push_front(a);
+        self.activity.truncate(ACTIVITY_ROWS);
+    }
+
+    fn render(&self, frame: &mut Frame, c: &Controls) {
+        let [header, pools, spread, middle, bottom, footer] = Layout::vertical([
+            Constraint::Length(4),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(10),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let report = match &self.last {
+            Some(RunReport::Completed(r)) => Some(r.as_ref()),
+            _ => None,
+        };
+
+        self.render_header(frame, header, c);
+        let [pool_a, pool_b] = Layout::horizontal([Constraint::Percentage(50); 2]).areas(pools);
+        render_pool(frame, pool_a, "Pool A", report, true);
+        render_pool(frame, pool_b, "Pool B", report, false);
+        self.render_spread(frame, spread, c);
+        let [candidates, decision] =
+            Layout::horizontal([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .areas(middle);
+        render_candidates(frame, candidates, report);
+        render_decision(frame, decision, self.last.as_ref());
+        let [activity, logs] = Layout::horizontal([Constraint::Percentage(50); 2]).areas(bottom);
+        self.render_activity(frame, activity);
+        self.render_logs(frame, logs);
+        frame.render_widget(
+            Paragraph::new(
+                " q quit  p pause  r run now  s simulate/live  +/- spread threshold  [/] min pnl",
+            )
+            .style(Style::new().fg(Color::DarkGray)),
+            footer,
+        );
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect, c: &Controls) {
+        let mode = if c.simulate_only {
+            Span::styled(" SIMULATE ", Style::new().black().on_yellow())
+        } else {
+            Span::styled(" LIVE ", Style::new().white().on_red().bold())
+        };
+        let state = if c.paused {
+            Span::styled(" PAUSED ", Style::new().black().on_gray())
+        } else {
+            Span::styled(" RUNNING ", Style::new().black().on_green())
+        };
+        let last = self
+            .last
+            .as_ref()
+            .map(|r| match r {
+                RunReport::Completed(r) => r.timestamp.clone(),
+                RunReport::Failed(r) => r.timestamp.clone(),
+            })
+            .unwrap_or_else(|| "-".to_string());
+        let lines = vec![
+            Line::from(vec![
+                mode,
+                Span::raw(" "),
+                state,
+                Span::raw(format!(
+                    "  spread ≥ {} bps  min_pnl_raw {}  every {} ms  evaluations {}  last {}",
+                    c.spread_threshold_bps,
+                    c.min_pnl_raw,
+                    self.session.refresh.as_millis(),
+                    self.evaluations,
+                    last
+                )),
+            ]),
+            Line::from(self.status.clone()).fg(Color::Cyan),
+        ];
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(self.session.title.clone())),
+            area,
+        );
+    }
+
+    fn render_spread(&self, frame: &mut Frame, area: Rect, c: &Controls) {
+        let now = self.spreads.last().map_or(0.0, |s| *s as f64 / 100.0);
+        let max = self.spreads.iter().max().map_or(0.0, |s| *s as f64 / 100.0);
+        // Newest samples that fit the inner width
+        let width = area.width.saturating_sub(2) as usize;
+        let data = &self.spreads[self.spreads.len().saturating_sub(width)..];
+        let color = if now >= c.spread_threshold_bps as f64 {
+            Color::Green
+        } else {
+            Color::Blue
+        };
+        frame.render_widget(
+            Sparkline::default()
+                .block(Block::bordered().title(format!(
+                    "Spread bps: now {now:.2}  max {max:.2}  threshold {}",
+                    c.spread_threshold_bps
+                )))
+                .data(data)
+                .style(Style::new().fg(color)),
+            area,
+        );
+    }
+
+    fn render_activity(&self, frame: &mut Frame, area: Rect) {
+        let rows = self.activity.iter().map(|a| {
+            let style = if a.status.starts_with("failed") || a.mode == "error" {
+                Style::new().fg(Color::Red)
+            } else {
+                Style::new()
+            };
+            Row::new(vec![
+                a.time.clone(),
+                a.mode.clone(),
+                a.signature.as_deref().map_or("-".to_string(), short),
+                a.status.clone(),
+            ])
+            .style(style)
+        });
+        frame.render_widget(
+            Table::new(
+                rows,
+                [
+                    Constraint::Length(8),
+                    Constraint::Length(9),
+                    Constraint::Length(11),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(Row::new(vec!["time", "mode", "signature", "status"]).bold())
+            .block(Block::bordered().title("Recent sends")),
+            area,
+        );
+    }
+
+    fn render_logs(&self, frame: &mut Frame, area: Rect) {
+        let height = area.height.saturating_sub(2) as usize;
+        let logs = self.logs.0.lock().unwrap();
+        let lines: Vec<Line> = logs
+            .iter()
+            .skip(logs.len().saturating_sub(height))
+            .map(|l| {
+                let style = if l.contains(" ERROR ") {
+                    Style::new().fg(Color::Red)
+                } else if l.contains(" WARN ") {
+                    Style::new().fg(Color::Yellow)
+                } else {
+                    Style::new()
+                };
+                Line::styled(l.clone(), style)
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title("Log")),
+            area,
+        );
+    }
+}
+
+// `abcd…wxyz`
+fn short(s: &str) -> String {
+    if s.len() <= 10 {
+        return s.to_string();
+    }
+    format!("{}…{}", &s[..4], &s[s.len() - 4..])
+}
+
+fn tx_status(r: &ArbitrageReport) -> String {
+    let tx = &r.tx;
+    if let Some(error) = &tx.error {
+        return format!("failed: {error}");
+    }
+    match tx.mode.as_str() {
+        "aborted" => format!(
+            "aborted: {}",
+            r.final_quote
+                .as_ref()
+                .and_then(|f| f.aborted.clone())
+                .unwrap_or_default()
+        ),
+        "simulate" => "simulated ok".to_string(),
+        "sign_only" => "signed".to_string(),
+        _ => match (tx.slot, tx.send.as_ref()) {
+            (Some(slot), Some(send)) => format!(
+                "landed in slot {slot} after {} ms, {} broadcasts",
+                send.confirmed_after_ms.unwrap_or_default(),
+                send.attempts.len()
+            ),
+            (Some(slot), None) => format!("landed in slot {slot}"),
+            _ => "sent, unconfirmed".to_string(),
+        },
+    }
+}
+
+// Pool A/B values from the report, which orders them by the chosen direction
+fn render_pool(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    report: Option<&ArbitrageReport>,
+    pool_a: bool,
+) {
+    let lines = match report {
+        None => vec![Line::from("waiting for the first evaluation…")],
+        Some(r) => {
+            let a_first = r.pools.direction.first_label == "PoolA";
+            let (values, price): (&PoolValuesReport, f64) = if a_first == pool_a {
+                (&r.pool_values.first, r.prices.first)
+            } else {
+                (&r.pool_values.second, r.prices.second)
+            };
+            vec![
+                Line::from(if pool_a {
+                    r.pools.pool_a.clone()
+                } else {
+                    r.pools.pool_b.clone()
+                }),
+                Line::from(format!(
+                    "reserve mint_in  {:.6} ({})",
+                    values.reserve0_ui, values.reserve0
+                )),
+                Line::from(format!(
+                    "reserve mint_out {:.6} ({})",
+                    values.reserve1_ui, values.reserve1
+                )),
+                Line::from(format!("price {price:.12}")).bold(),
+            ]
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title(title.to_string())),
+        area,
+    );
+}
+
+const CANDIDATE_ROWS: [&str; 6] = [
+    "amount_out_1",
+    "amount_out_2",
+    "gross_profit",
+    "total_fees",
+    "pnl",
+    "pnl_raw",
+];
+
+// In `CANDIDATE_ROWS` order
+fn candidate_values(c: &CandidateReport) -> [String; 6] {
+    let dash = || "-".to_string();
+    [
+        format!("{:.9}", c.amount_out_1),
+        format!("{:.9}", c.amount_out_2),
+        format!("{:.9}", c.gross_profit),
+        format!("{:.9}", c.total_fees),
+        c.pnl.map_or_else(dash, |p| format!("{p:.9}")),
+        c.pnl_raw.map_or_else(dash, |p| p.to_string()),
+    ]
+}
+
+fn render_candidates(frame: &mut Frame, area: Rect, report: Option<&ArbitrageReport>) {
+    let Some(r) = report else {
+        frame.render_widget(Block::bordered().title("Candidates"), area);
+        return;
+    };
+    let a_to_b_chosen = r.decision.chosen_direction.starts_with("PoolA");
+    let rows = CANDIDATE_ROWS
+        .iter()
+        .zip(candidate_values(&r.arbitrage_candidates.a_to_b))
+        .zip(candidate_values(&r.arbitrage_candidates.b_to_a))
+        .map(|((name, a_to_b), b_to_a)| Row::new(vec![name.to_string(), a_to_b, b_to_a]));
+    let chosen = Style::new().add_modifier(Modifier::BOLD).fg(Color::Cyan);
+    let header = Row::new(vec![
+        Span::raw(""),
+        Span::styled("A → B", if a_to_b_chosen { chosen } else { Style::new() }),
+        Span::styled("B → A", if a_to_b_chosen { Style::new() } else { chosen }),
+    ]);
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Length(13),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(Block::bordered().title("Candidates (chosen in bold)")),
+        area,
+    );
+}
+
+fn render_decision(frame: &mut Frame, area: Rect, report: Option<&RunReport>) {
+    let yes_no = |ok: bool| {
+        if ok {
+            Span::styled("yes", Style::new().fg(Color::Green))
+        } else {
+            Span::styled("no", Style::new().fg(Color::Red))
+        }
+    };
+    let lines = match report {
+        None => vec![Line::from("-")],
+        Some(RunReport::Failed(r)) => vec![
+            Line::from("evaluation failed").red().bold(),
+            Line::from(format!("{}: {}", r.error.kind, r.error.message)),
+        ],
+        Some(RunReport::Completed(r)) => {
+            let d = &r.decision;
+            let mut lines = vec![
+                Line::from(vec![Span::raw("execute     "), yes_no(d.should_execute)]).bold(),
+                Line::from(format!("direction   {}", d.chosen_direction)),
+                Line::from(vec![Span::raw("profitable  "), yes_no(d.is_profitable)]),
+                Line::from(vec![Span::raw("policy      "), yes_no(d.policy.passed)]),
+            ];
+            let reasons = d
+                .unavailable
+                .iter()
+                .cloned()
+                .chain(d.policy.failures())
+                .chain(d.twap.iter().flat_map(|t| t.breaches()))
+                .chain(r.final_quote.as_ref().and_then(|f| f.aborted.clone()));
+            lines.extend(reasons.map(|reason| Line::from(format!("· {reason}")).yellow()));
+            lines
+        }
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Decision")),
+        area,
+    );
+}
@@ -0,0 +1,262 @@
+use anyhow::Result;
+use carbon_core::account::AccountDecoder;
+use carbon_raydium_cpmm_decoder::{
+    RaydiumCpmmDecoder,
+    accounts::{RaydiumCpmmAccount, observation_state::ObservationState},
+    types::Observation,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use std::{fmt, str::FromStr};
+
+use crate::{error::ArbError, pool::PoolData};
+
+// Cumulative prices are Q32 fixed point
+const X32: f64 = 4_294_967_296.0;
+
+fn default_window_secs() -> u64 {
+    300
+}
+
+/// What to do when a pool's spot price strays from its TWAP.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TwapAction {
+    /// Do not trade
+    #[default]
+    Block,
+    /// Trade anyway; warn, notify and report
+    Flag,
+}
+
+/// Compares each pool's spot mid price with its time-weighted average price from the pool's
+/// observation account. Written in config as JSON/TOML, e.g.
+/// `{"window_secs": 300, "max_deviation_bps": 150, "action": "block"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct TwapGuard {
+    /// Length of the averaging window, ending at the pool's latest observation
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Largest tolerated |spot - twap| / twap, in bps
+    pub max_deviation_bps: u32,
+    #[serde(default)]
+    pub action: TwapAction,
+}
+
+impl TwapGuard {
+    pub fn check(&self) -> Result<(), String> {
+        if self.window_secs == 0 {
+            return Err("twap guard window_secs must be greater than 0".to_string());
+        }
+        Ok(())
+    }
+
+    fn compare(&self, twap: &PoolTwap, spot_price: f64) -> PoolTwapReport {
+        let deviation_bps = twap
+            .twap_price
+            .filter(|t| *t > 0.0)
+            .map(|t| (spot_price - t).abs() / t * 10_000.0);
+        PoolTwapReport {
+            pool: twap.pool.clone(),
+            spot_price,
+            twap_price: twap.twap_price,
+            covered_secs: twap.covered_secs,
+            deviation_bps,
+            exceeded: deviation_bps.is_some_and(|d| d > self.max_deviation_bps as f64),
+        }
+    }
+}
+
+/// Compact JSON, as accepted by `FromStr`.
+impl fmt::Display for TwapGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_string(self).map_err(|_| fmt::Error)?;
+        f.write_str(&json)
+    }
+}
+
+impl FromStr for TwapGuard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let guard: TwapGuard =
+            serde_json::from_str(s).map_err(|e| format!("invalid twap guard: {e}"))?;
+        guard.check()?;
+        Ok(guard)
+    }
+}
+
+/// TWAP of one pool, oriented as mint_in -> mint_out like `calculate_price`.
+#[derive(Debug, Clone)]
+pub struct PoolTwap {
+    pub pool: String,
+    /// Unset while the pool has fewer than two usable observations
+    pub twap_price: Option<f64>,
+    /// Seconds actually averaged: at least the window when the history is long enough
+    pub covered_secs: u64,
+}
+
+impl PoolTwap {
+    pub fn read(
+        rpc: &RpcClient,
+        pool: &PoolData,
+        mint_in: &Pubkey,
+        window_secs: u64,
+    ) -> Result<Self> {
+        let key = pool.state.observation_key;
+        let acc = rpc
+            .get_account(&key)
+            .map_err(|e| ArbError::Rpc(format!("get observation account {key}: {e}")))?;
+        let observations = decode_observations(&key, &acc)?;
+
+        // token0's cumulative price is token1 per token0; token1's is the inverse
+        let (d0, d1) = (
+            pool.state.mint0_decimals as i32,
+            pool.state.mint1_decimals as i32,
+        );
+        let (token0_in, scale) = if pool.state.token0_mint == *mint_in {
+            (true, 10f64.powi(d0 - d1))
+        } else {
+            (false, 10f64.powi(d1 - d0))
+        };
+        let twap = twap_x32(&observations, window_secs, token0_in);
+        Ok(Self {
+            pool: pool.pool_id.to_string(),
+            twap_price: twap.map(|(price_x32, _)| price_x32 as f64 / X32 * scale),
+            covered_secs: twap.map_or(0, |(_, secs)| secs),
+        })
+    }
+}
+
+/// Decode a pool's observation account (owner and discriminator checked).
+pub fn decode_observations(key: &Pubkey, acc: &Account) -> Result<ObservationState> {
+    match RaydiumCpmmDecoder
+        .decode_account(acc)
+        .ok_or_else(|| ArbError::Decode(format!("{key} is not a Raydium CPMM account")))?
+        .data
+    {
+        RaydiumCpmmAccount::ObservationState(state) => Ok(state),
+        _ => Err(ArbError::Decode(format!("{key} is not a CPMM observation account")).into()),
+    }
+}
+
+/// Average Q32 raw price (token1 per token0 for `token0`, else the inverse) over the newest
+/// observations spanning `window_secs`, walking the ring buffer back from
+/// `observation_index`. Returns the price and the seconds actually covered, or `None` with
+/// fewer than two usable observations.
+pub fn twap_x32(state: &ObservationState, window_secs: u64, token0: bool) -> Option<(u128, u64)> {
+    let ring = &state.observations;
+    let n = ring.len();
+    if !state.initialized || n == 0 {
+        return None;
+    }
+    let newest_index = state.observation_index as usize % n;
+    let newest = &ring[newest_index];
+    if newest.block_timestamp == 0 {
+        return None;
+    }
+    let mut start: Option<&Observation> = None;
+    let mut later = newest.block_timestamp;
+    for back in 1..n {
+        let o = &ring[(newest_index + n - back) % n];
+        // Unwritten slot, or the walk went all the way around
+        if o.block_timestamp == 0 || o.block_timestamp >= later {
+            break;
+        }
+        start = Some(o);
+        later = o.block_timestamp;
+        if newest.block_timestamp - o.block_timestamp >= window_secs {
+            break;
+        }
+    }
+    let start = start?;
+    let secs = newest.block_timestamp - start.block_timestamp;
+    let cumulative = |o: &Observation| {
+        if token0 {
+            o.cumulative_token0_price_x32
+        } else {
+            o.cumulative_token1_price_x32
+        }
+    };
+    // The program lets cumulative prices wrap
+    let delta = cumulative(newest).wrapping_sub(cumulative(start));
+    Some((delta / secs as u128, secs))
+}
+
+/// TWAPs of the chosen direction's pools, read once per evaluation and compared with every
+/// later quote of the same pools.
+pub struct TwapBaseline {
+    pub guard: TwapGuard,
+    pub first: PoolTwap,
+    pub second: PoolTwap,
+}
+
+impl TwapBaseline {
+    pub fn check(&self, spot_first: f64, spot_second: f64) -> TwapReport {
+        let pools = vec![
+            self.guard.compare(&self.first, spot_first),
+            self.guard.compare(&self.second, spot_second),
+        ];
+        let exceeded = pools.iter().any(|p| p.exceeded);
+        TwapReport {
+            window_secs: self.guard.window_secs,
+            max_deviation_bps: self.guard.max_deviation_bps,
+            action: self.guard.action,
+            pools,
+            blocked: exceeded && self.guard.action == TwapAction::Block,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct PoolTwapReport {
+    pub pool: String,
+    pub spot_price: f64,
+    /// Unset without enough observation history; the pool is then never flagged
+    pub twap_price: Option<f64>,
+    pub covered_secs: u64,
+    pub deviation_bps: Option<f64>,
+    pub exceeded: bool,
+}
+
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub struct TwapReport {
+    pub window_secs: u64,
+    pub max_deviation_bps: u32,
+    pub action: TwapAction,
+    /// First then second pool of the chosen direction
+    pub pools: Vec<PoolTwapReport>,
+    /// A pool exceeded the deviation and the action is `block`
+    pub blocked: bool,
+}
+
+impl TwapReport {
+    /// Pools past the limit, e.g. `<pool> spot 1.02 vs twap 1.00 (200.0 bps, max 150)`.
+    pub fn breaches(&self) -> Vec<String> {
+        self.pools
+            .iter()
+            .filter(|p| p.exceeded)
+            .map(|p| {
+                format!(
+                    "{} spot {:.12} vs twap {:.12} ({:.1} bps, max {})",
+                    p.pool,
+                    p.spot_price,
+                    p.twap_price.unwrap_or_default(),
+                    p.deviation_bps.unwrap_or_default(),
+                    self.max_deviation_bps
+                )
+            })
+            .collect()
+    }
+
+    /// Pools whose TWAP could not be computed.
+    pub fn unknown(&self) -> Vec<&str> {
+        self.pools
+            .iter()
+            .filter(|p| p.twap_price.is_none())
+            .map(|p| p.pool.as_str())
+            .collect()
+    }
+}
@@ -1,46 +1,51 @@
 use anyhow::Result;
 use solana_client::rpc_client::RpcClient;
-use solana_sdk::{pubkey::Pubkey, signer::keypair::Keypair};
+use solana_sdk::{clock::Clock, program_pack::Pack, pubkey::Pubkey, sysvar};
 use spl_associated_token_account::get_associated_token_address;
-use std::{fs, path::Path};
+use spl_token::state::Account;
 
-const TOKEN_ACCOUNT_SIZE: usize = 165;
+use crate::error::ArbError;
 
-pub fn load_keypair(keypair_path: &Path) -> Result<Keypair> {
-    let json_string = fs::read_to_string(keypair_path)?;
-    let bytes: Vec<u8> = serde_json::from_str(&json_string)?;
-    Ok(Keypair::from_bytes(&bytes)?)
-}
+const TOKEN_ACCOUNT_SIZE: usize = 165;
 
 pub fn get_token_account_rent(rpc: &RpcClient) -> Result<u64> {
     Ok(rpc.get_minimum_balance_for_rent_exemption(TOKEN_ACCOUNT_SIZE)?)
 }
 
+/// Unix time of the cluster's Clock sysvar, the clock on-chain checks compare against.
+pub fn get_cluster_time(rpc: &RpcClient) -> Result<i64> {
+    let acc = rpc
+        .get_account(&sysvar::clock::ID)
+        .map_err(|e| ArbError::Rpc(format!("get clock sysvar: {e}")))?;
+    let clock: Clock = bincode::deserialize(&acc.data)
+        .map_err(|e| ArbError::Decode(format!("clock sysvar: {e}")))?;
+    Ok(clock.unix_timestamp)
+}
+
 #[derive(Clone)]
 pub struct TokenAccount {
     pub mint: Pubkey,
     pub ata: Pubkey,
     pub exists: bool,
+    pub amount: u64, // raw balance when checked; 0 if missing
 }
 
+/// The wallet's ATA for `token_mint`. A missing account is `exists: false`; a failed
+/// lookup is an error, so a dead endpoint never looks like a missing account.
 pub fn get_missing_token_account(
     rpc: &RpcClient,
     wallet: &Pubkey,
     token_mint: &Pubkey,
-) -> TokenAccount {
+) -> Result<TokenAccount> {
     let ata = get_associated_token_address(wallet, token_mint);
-
-    if rpc.get_account(&ata).is_err() {
-        TokenAccount {
-            mint: *token_mint,
-            ata,
-            exists: false,
-        }
-    } else {
-        TokenAccount {
-            mint: *token_mint,
-            ata,
-            exists: true,
-        }
-    }
+    let account = rpc
+        .get_account_with_commitment(&ata, rpc.commitment())
+        .map_err(|e| ArbError::Rpc(format!("get token account {ata}: {e}")))?
+        .value;
+    Ok(TokenAccount {
+        mint: *token_mint,
+        ata,
+        exists: account.is_some(),
+        amount: account.map_or(0, |a| Account::unpack(&a.data).map_or(0, |a| a.amount)),
+    })
 }
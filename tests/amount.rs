@@ -0,0 +1,128 @@
+use proptest::prelude::*;
+use solana_amm_arb_cli::{amount::DecimalAmount, arbitrage::calculate_min_out, error::ArbError};
+
+// Mints carry up to 9 decimals in practice; the type itself allows more
+const MAX_DECIMALS: u8 = 19;
+
+fn amount(s: &str) -> DecimalAmount {
+    s.parse().unwrap()
+}
+
+proptest! {
+    #[test]
+    fn raw_round_trips(raw in any::<u64>(), decimals in 0..=MAX_DECIMALS) {
+        let a = DecimalAmount::from_raw(raw, decimals);
+        prop_assert_eq!(a.to_raw(decimals).unwrap(), raw);
+        // More decimals on the mint only scales, until the u64 runs out
+        match a.to_raw(decimals + 1) {
+            Ok(scaled) => prop_assert_eq!(scaled as u128, raw as u128 * 10),
+            Err(e) => prop_assert!(matches!(e, ArbError::Math(_))),
+        }
+    }
+
+    #[test]
+    fn display_round_trips(raw in any::<u64>(), decimals in 0..=MAX_DECIMALS) {
+        let a = DecimalAmount::from_raw(raw, decimals);
+        let shown = a.to_string();
+        prop_assert_eq!(shown.parse::<DecimalAmount>().unwrap(), a);
+        prop_assert!(!shown.ends_with('.'));
+        prop_assert!(!shown.contains('.') || !shown.ends_with('0'));
+    }
+
+    #[test]
+    fn trailing_zeros_do_not_count(raw in any::<u64>(), decimals in 1..=9u8, zeros in 0..=20usize) {
+        // Every fractional digit written out, e.g. "1.230000000" for 9 decimals
+        let unit = 10u64.pow(decimals as u32);
+        let s = format!(
+            "{}.{:0width$}{}",
+            raw / unit,
+            raw % unit,
+            "0".repeat(zeros),
+            width = decimals as usize
+        );
+        let a = s.parse::<DecimalAmount>().unwrap();
+        prop_assert_eq!(a, DecimalAmount::from_raw(raw, decimals));
+        prop_assert_eq!(a.to_raw(decimals).unwrap(), raw);
+    }
+
+    #[test]
+    fn excess_decimals_are_rejected(int in 0..1_000_000u64, decimals in 0..=9u8) {
+        // One digit past the mint's precision cannot be represented without rounding
+        let s = format!("{int}.{}1", "0".repeat(decimals as usize));
+        let err = s.parse::<DecimalAmount>().unwrap().to_raw(decimals).unwrap_err();
+        prop_assert!(matches!(err, ArbError::Config(_)));
+    }
+
+    #[test]
+    fn min_out_never_exceeds_amount_out(amount_out in any::<u64>(), bps in any::<u32>()) {
+        let min_out = calculate_min_out(amount_out, bps);
+        prop_assert!(min_out <= amount_out);
+        let exact = amount_out as u128 * 10_000u128.saturating_sub(bps as u128) / 10_000;
+        prop_assert_eq!(min_out as u128, exact);
+    }
+
+    #[test]
+    fn min_out_falls_as_slippage_grows(amount_out in any::<u64>(), bps in 0..10_000u32) {
+        prop_assert!(calculate_min_out(amount_out, bps + 1) <= calculate_min_out(amount_out, bps));
+    }
+}
+
+#[test]
+fn u64_bounds() {
+    let max = u64::MAX.to_string();
+    assert_eq!(amount(&max).to_raw(0).unwrap(), u64::MAX);
+    assert!(matches!(
+        amount("18446744073709551616").to_raw(0),
+        Err(ArbError::Math(_))
+    ));
+    assert_eq!(
+        amount("18.446744073709551615").to_raw(18).unwrap(),
+        u64::MAX
+    );
+    assert!(matches!(
+        amount("18.446744073709551616").to_raw(18),
+        Err(ArbError::Math(_))
+    ));
+    assert!(matches!(amount(&max).to_raw(1), Err(ArbError::Math(_))));
+    // Digits beyond u128 are refused while parsing
+    assert!("1".repeat(40).parse::<DecimalAmount>().is_err());
+}
+
+#[test]
+fn edge_decimals() {
+    assert_eq!(amount("0.00001").to_raw(9).unwrap(), 10_000);
+    assert_eq!(amount("0.000000001").to_raw(9).unwrap(), 1);
+    assert_eq!(amount(".5").to_raw(6).unwrap(), 500_000);
+    assert_eq!(amount("5.").to_raw(0).unwrap(), 5);
+    assert_eq!(amount("0.0000000000").to_raw(0).unwrap(), 0);
+    assert_eq!(amount("1.500").to_string(), "1.5");
+    assert_eq!(amount("000.050").to_string(), "0.05");
+    assert!(matches!(
+        amount("0.0000000001").to_raw(9),
+        Err(ArbError::Config(_))
+    ));
+    for bad in ["", ".", "-1", "+1", "1e-5", "1.2.3", "1,5", "NaN", "inf"] {
+        assert!(bad.parse::<DecimalAmount>().is_err(), "{bad:?} parsed");
+    }
+}
+
+#[test]
+fn slippage_bounds() {
+    assert_eq!(calculate_min_out(u64::MAX, 0), u64::MAX);
+    assert_eq!(calculate_min_out(u64::MAX, 10_000), 0);
+    assert_eq!(calculate_min_out(u64::MAX, u32::MAX), 0);
+    assert_eq!(calculate_min_out(10_000, 1), 9_999);
+    assert_eq!(calculate_min_out(9_999, 1), 9_998); // 9_998.0001 rounds down
+}
+
+#[test]
+fn serde_accepts_strings_and_legacy_numbers() {
+    let a: DecimalAmount = serde_json::from_str("\"0.00001\"").unwrap();
+    assert_eq!(a, amount("0.00001"));
+    let a: DecimalAmount = serde_json::from_str("0.00001").unwrap();
+    assert_eq!(a, amount("0.00001"));
+    let a: DecimalAmount = serde_json::from_str("3").unwrap();
+    assert_eq!(a, amount("3"));
+    assert!(serde_json::from_str::<DecimalAmount>("-1").is_err());
+    assert_eq!(serde_json::to_string(&amount("2.50")).unwrap(), "\"2.5\"");
+}
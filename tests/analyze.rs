@@ -0,0 +1,142 @@
+use solana_amm_arb_cli::{
+    amount::DecimalAmount,
+    analyze::{Analysis, AnalyzeFormat, IMPACT_LEVELS_BPS, ladder_sizes, pool_depth, round_trip},
+    arbitrage::{FeeConversion, calculate_pnl},
+    pool::PoolValues,
+};
+use solana_sdk::pubkey::Pubkey;
+
+const AMOUNT_IN: u64 = 1_000_000_000;
+
+fn pool(reserve0: u64, reserve1: u64) -> PoolValues {
+    PoolValues {
+        mint0: Pubkey::new_from_array([1; 32]),
+        mint1: Pubkey::new_from_array([2; 32]),
+        vault_amount0: reserve0,
+        vault_amount1: reserve1,
+        protocol_fees_token0: 0,
+        protocol_fees_token1: 0,
+        fund_fees_token0: 0,
+        fund_fees_token1: 0,
+        creator_fees_token0: 0,
+        creator_fees_token1: 0,
+        reserve0,
+        reserve1,
+        token0_decimals: 9,
+        token1_decimals: 6,
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 0,
+        fund_fee_rate: 0,
+        creator_fee_rate: 0,
+        creator_fee_on: 0,
+        enable_creator_fee: false,
+    }
+}
+
+// A cheap pool and a dear one, fixed so every figure below is reproducible
+fn pools() -> (PoolValues, PoolValues) {
+    (
+        pool(1_000_000_000_000, 2_000_000_000),
+        pool(1_000_000_000_000, 1_900_000_000),
+    )
+}
+
+#[test]
+fn the_ladder_scales_amount_in() {
+    assert_eq!(
+        ladder_sizes(1_000),
+        [100, 500, 1_000, 2_000, 5_000, 10_000, 50_000, 100_000]
+    );
+    // Fractions that round to zero are dropped
+    assert_eq!(ladder_sizes(1), [1, 2, 5, 10, 50, 100]);
+    assert_eq!(ladder_sizes(u64::MAX).last(), Some(&u64::MAX));
+}
+
+#[test]
+fn impact_limits_are_the_largest_size_within_each_level() {
+    let (pool_a, _) = pools();
+    let depth = pool_depth("A", "PoolA", &pool_a, &ladder_sizes(AMOUNT_IN)).unwrap();
+    assert_eq!(depth.sides.len(), 2);
+    // Only the mint_in side is laddered
+    assert_eq!(depth.sides[0].ladder.len(), 8);
+    assert!(depth.sides[1].ladder.is_empty());
+
+    for side in &depth.sides {
+        let levels: Vec<_> = side.limits.iter().map(|l| l.level_bps).collect();
+        assert_eq!(levels, IMPACT_LEVELS_BPS);
+        for pair in side.limits.windows(2) {
+            assert!(pair[0].fill.amount_in_raw < pair[1].fill.amount_in_raw);
+        }
+    }
+
+    let side = &depth.sides[0];
+    for limit in &side.limits {
+        assert!(limit.fill.impact_bps <= limit.level_bps as f64);
+        // One more raw unit crosses the level
+        let above = pool_depth("A", "PoolA", &pool_a, &[limit.fill.amount_in_raw + 1]).unwrap();
+        assert!(above.sides[0].ladder[0].impact_bps > limit.level_bps as f64);
+    }
+    // Impact grows along the ladder
+    for pair in side.ladder.windows(2) {
+        assert!(pair[0].impact_bps <= pair[1].impact_bps);
+    }
+}
+
+#[test]
+fn round_trips_match_the_quote() {
+    let (pool_a, pool_b) = pools();
+    let conversion = FeeConversion::identity();
+    let sizes = ladder_sizes(AMOUNT_IN);
+    let rt = round_trip("A→B", &pool_a, &pool_b, &sizes, 1_000, Some(&conversion)).unwrap();
+    assert_eq!(rt.points.len(), sizes.len());
+
+    for (point, size) in rt.points.iter().zip(&sizes) {
+        let amount = DecimalAmount::from_raw(*size, 9);
+        let arb = calculate_pnl(&amount, &pool_a, &pool_b, 0, 1_000, Some(&conversion)).unwrap();
+        assert_eq!(point.amount_in_raw, *size);
+        assert_eq!(point.amount_out_1_raw, arb.amount_out_1_raw);
+        assert_eq!(point.amount_out_2_raw, arb.amount_out_2_raw);
+        assert_eq!(point.gross_profit_raw, arb.gross_profit_raw);
+        assert_eq!(point.pnl_raw, arb.pnl_raw);
+    }
+    // Profitable at the base size, eaten by impact at 100x
+    assert!(rt.points[2].gross_profit_raw > 0);
+    assert!(rt.points.last().unwrap().gross_profit_raw < 0);
+
+    // Without a fee price PnL is unknown
+    let rt = round_trip("A→B", &pool_a, &pool_b, &sizes, 1_000, None).unwrap();
+    assert!(rt.points.iter().all(|p| p.pnl_raw.is_none()));
+}
+
+#[test]
+fn csv_has_a_row_per_figure() {
+    let (pool_a, pool_b) = pools();
+    let sizes = [AMOUNT_IN];
+    let analysis = Analysis {
+        pools: vec![
+            pool_depth("A", "PoolA", &pool_a, &sizes).unwrap(),
+            pool_depth("B", "PoolB", &pool_b, &sizes).unwrap(),
+        ],
+        round_trips: vec![round_trip("A→B", &pool_a, &pool_b, &sizes, 0, None).unwrap()],
+    };
+    let csv = analysis.render(AnalyzeFormat::Csv);
+    let rows: Vec<&str> = csv.lines().collect();
+
+    assert!(rows[0].starts_with("section,pool,direction,"));
+    let count = |section: &str| rows.iter().filter(|r| r.starts_with(section)).count();
+    // Four levels on both sides of both pools
+    assert_eq!(count("impact,"), 2 * 2 * IMPACT_LEVELS_BPS.len());
+    assert_eq!(count("ladder,"), 2);
+    assert_eq!(count("round_trip,"), 1);
+    let round_trip_row = rows.last().unwrap();
+    assert!(
+        round_trip_row.starts_with(&format!("round_trip,PoolA-PoolB,A→B,,{AMOUNT_IN},")),
+        "{round_trip_row}"
+    );
+    // Unknown PnL is an empty field
+    assert!(round_trip_row.ends_with(','), "{round_trip_row}");
+
+    let table = analysis.render(AnalyzeFormat::Table);
+    assert!(table.contains("Pool A PoolA (mint_in→mint_out)"));
+    assert!(table.contains("Round trip A→B"));
+}
@@ -0,0 +1,259 @@
+use serde_json::{Value, json};
+use solana_amm_arb_cli::{
+    endpoints::{EndpointRole, Endpoints, RpcEndpoint, endpoint_fault, slot_lag},
+    error::{ArbError, classify},
+    sender::send_error,
+};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    thread,
+    time::Duration,
+};
+
+// Nothing listens on port 1, so every call fails at the transport
+const DEAD: &str = "http://127.0.0.1:1";
+
+// Minimal JSON-RPC node: `getHealth` is "ok", `getSlot` is `slot`, after `delay`
+fn stub_node(slot: u64, delay: Duration) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            thread::spawn(move || {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    match header.split_once(':') {
+                        Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                let request: Value = serde_json::from_slice(&body).unwrap();
+                let result = match request["method"].as_str() {
+                    Some("getHealth") => json!("ok"),
+                    Some("getSlot") => json!(slot),
+                    other => panic!("unexpected method {other:?}"),
+                };
+                let body =
+                    json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string();
+                thread::sleep(delay);
+                // The client may have given up on a slow answer already
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+            });
+        }
+    });
+    url
+}
+
+fn read_only(url: &str) -> RpcEndpoint {
+    RpcEndpoint {
+        url: url.to_string(),
+        roles: vec![EndpointRole::Read],
+    }
+}
+
+fn get_slot(rpc: &solana_client::rpc_client::RpcClient) -> anyhow::Result<u64> {
+    rpc.get_slot()
+        .map_err(|e| ArbError::Rpc(format!("get slot: {e}")).into())
+}
+
+#[test]
+fn endpoints_parse_with_and_without_roles() {
+    let parse = |s: &str| s.parse::<RpcEndpoint>();
+
+    let plain = parse("https://rpc.example").unwrap();
+    assert_eq!(plain.roles, [EndpointRole::Read, EndpointRole::Send]);
+    assert_eq!(plain.to_string(), "read,send=https://rpc.example");
+
+    let relay = parse(" send=https://relay.example ").unwrap();
+    assert_eq!(relay.url, "https://relay.example");
+    assert_eq!(relay.roles, [EndpointRole::Send]);
+
+    let ws = parse("ws=wss://ws.example").unwrap();
+    assert_eq!(ws.roles, [EndpointRole::Websocket]);
+    assert_eq!(ws.to_string(), "websocket=wss://ws.example");
+
+    // `=` in the URL is not a role list
+    let keyed = parse("https://rpc.example/?api-key=abc").unwrap();
+    assert_eq!(keyed.url, "https://rpc.example/?api-key=abc");
+    assert_eq!(keyed.roles, [EndpointRole::Read, EndpointRole::Send]);
+
+    // Display output parses back to the same endpoint
+    assert_eq!(parse(&keyed.to_string()).unwrap(), keyed);
+}
+
+#[test]
+fn invalid_endpoints_are_rejected() {
+    for bad in [
+        "",
+        "rpc.example",
+        "read=",
+        // Not a role, so the whole thing is taken as a URL
+        "reed=https://rpc.example",
+        // Scheme does not match the role
+        "read=wss://ws.example",
+        "websocket=https://rpc.example",
+        "read,websocket=wss://ws.example",
+    ] {
+        assert!(bad.parse::<RpcEndpoint>().is_err(), "{bad:?} parsed");
+    }
+    assert_eq!(
+        "reed".parse::<EndpointRole>().unwrap_err(),
+        "unknown endpoint role `reed` (read, send, websocket)"
+    );
+    let no_roles = RpcEndpoint {
+        url: "https://rpc.example".to_string(),
+        roles: vec![],
+    };
+    assert!(no_roles.check().is_err());
+}
+
+#[test]
+fn slot_lag_is_measured_against_the_best_endpoint() {
+    assert_eq!(slot_lag(1_000, Some(1_000), 50), (0, true));
+    assert_eq!(slot_lag(950, Some(1_000), 50), (50, true));
+    assert_eq!(slot_lag(949, Some(1_000), 50), (51, false));
+    // Nobody else answered, or this endpoint is ahead of them
+    assert_eq!(slot_lag(900, None, 50), (0, true));
+    assert_eq!(slot_lag(1_100, Some(1_000), 50), (0, true));
+}
+
+#[test]
+fn reads_fail_over_to_the_next_endpoint() {
+    let node = stub_node(42, Duration::ZERO);
+    let rpcs = Endpoints::new(DEAD, &[read_only(&node)], None, 50);
+
+    assert_eq!(rpcs.call("get_slot", get_slot).unwrap(), 42);
+    let stats = rpcs.stats();
+    assert_eq!(
+        (stats[0].calls, stats[0].errors, stats[0].healthy),
+        (1, 1, false)
+    );
+    assert_eq!(
+        (stats[1].calls, stats[1].errors, stats[1].healthy),
+        (1, 0, true)
+    );
+
+    // The failed endpoint is now tried last
+    assert_eq!(rpcs.call("get_slot", get_slot).unwrap(), 42);
+    assert_eq!(rpcs.stats()[0].calls, 1);
+}
+
+#[test]
+fn decode_errors_do_not_fail_over() {
+    let node = stub_node(42, Duration::ZERO);
+    let rpcs = Endpoints::new(&node, &[read_only(DEAD)], None, 50);
+
+    let err = rpcs
+        .call("decode", |_| -> anyhow::Result<()> {
+            Err(ArbError::Decode("bad pool account".into()).into())
+        })
+        .unwrap_err();
+    assert!(matches!(classify(&err), Some(ArbError::Decode(_))), "{err}");
+    let stats = rpcs.stats();
+    // Counted, but the endpoint stays healthy and the next one is never asked
+    assert_eq!((stats[0].errors, stats[0].healthy), (1, true));
+    assert_eq!(stats[1].calls, 0);
+}
+
+#[test]
+fn every_endpoint_failing_returns_the_last_error() {
+    let rpcs = Endpoints::new(DEAD, &[read_only(DEAD)], None, 50);
+    let err = rpcs.call("get_slot", get_slot).unwrap_err();
+    assert!(matches!(classify(&err), Some(ArbError::Rpc(_))), "{err}");
+    assert!(rpcs.stats().iter().all(|s| s.calls == 1 && !s.healthy));
+}
+
+#[test]
+fn hedged_reads_take_the_first_answer() {
+    let slow = stub_node(1, Duration::from_secs(3));
+    let fast = stub_node(2, Duration::ZERO);
+    let rpcs = Endpoints::new(
+        &slow,
+        &[read_only(&fast)],
+        Some(Duration::from_millis(100)),
+        50,
+    );
+    assert_eq!(rpcs.hedged("get_slot", get_slot).unwrap(), 2);
+
+    // A failing first endpoint is replaced right away, without waiting for the delay
+    let rpcs = Endpoints::new(DEAD, &[read_only(&fast)], Some(Duration::from_secs(30)), 50);
+    assert_eq!(rpcs.hedged("get_slot", get_slot).unwrap(), 2);
+}
+
+#[test]
+fn health_checks_mark_lagging_and_dead_endpoints() {
+    let ahead = stub_node(1_000, Duration::ZERO);
+    let behind = stub_node(900, Duration::ZERO);
+    let rpcs = Endpoints::new(&ahead, &[read_only(&behind), read_only(DEAD)], None, 50);
+    rpcs.check_health();
+
+    let stats = rpcs.stats();
+    assert_eq!(
+        (stats[0].slot, stats[0].slot_lag, stats[0].healthy),
+        (Some(1_000), Some(0), true)
+    );
+    assert_eq!(
+        (stats[1].slot, stats[1].slot_lag, stats[1].healthy),
+        (Some(900), Some(100), false)
+    );
+    assert_eq!(stats[1].last_error.as_deref(), Some("100 slots behind"));
+    assert!(!stats[2].healthy);
+    assert_eq!(stats[2].slot, None);
+}
+
+#[test]
+fn rejected_transactions_do_not_mark_send_endpoints_unhealthy() {
+    let rejected = send_error(ClientError::from(ClientErrorKind::RpcError(
+        RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed: Blockhash not found".into(),
+            data: RpcResponseErrorData::Empty,
+        },
+    )));
+    assert!(matches!(classify(&rejected), Some(ArbError::Send(_))));
+    assert!(!endpoint_fault(&rejected));
+
+    let unhealthy = send_error(ClientError::from(ClientErrorKind::RpcError(
+        RpcError::RpcResponseError {
+            code: -32005,
+            message: "Node is behind by 120 slots".into(),
+            data: RpcResponseErrorData::NodeUnhealthy {
+                num_slots_behind: Some(120),
+            },
+        },
+    )));
+    assert!(endpoint_fault(&unhealthy));
+    let transport = send_error(ClientError::from(ClientErrorKind::Io(
+        std::io::ErrorKind::ConnectionRefused.into(),
+    )));
+    assert!(endpoint_fault(&transport));
+
+    let rpcs = Endpoints::single(DEAD);
+    rpcs.record(0, Duration::from_millis(5), Some(&rejected));
+    let s = &rpcs.stats()[0];
+    assert_eq!((s.calls, s.errors, s.healthy), (1, 1, true));
+    rpcs.record(0, Duration::from_millis(5), Some(&transport));
+    assert!(!rpcs.stats()[0].healthy);
+}
@@ -0,0 +1,75 @@
+use anyhow::Context;
+use solana_amm_arb_cli::error::{
+    ArbError, EXIT_CONFIG, EXIT_DECODE, EXIT_INTERNAL, EXIT_MATH, EXIT_RISK, EXIT_RPC, EXIT_SEND,
+    EXIT_SIMULATION, ErrorReport, classify, exit_code,
+};
+
+fn all_kinds() -> Vec<(ArbError, &'static str, u8)> {
+    vec![
+        (ArbError::Config("c".into()), "config", EXIT_CONFIG),
+        (ArbError::Rpc("r".into()), "rpc", EXIT_RPC),
+        (ArbError::Decode("d".into()), "decode", EXIT_DECODE),
+        (ArbError::math("m"), "math", EXIT_MATH),
+        (
+            ArbError::Simulation("s".into()),
+            "simulation",
+            EXIT_SIMULATION,
+        ),
+        (ArbError::Send("s".into()), "send", EXIT_SEND),
+        (ArbError::Risk("r".into()), "risk", EXIT_RISK),
+    ]
+}
+
+#[test]
+fn every_kind_has_its_own_exit_code() {
+    let mut codes: Vec<u8> = all_kinds().iter().map(|(_, _, code)| *code).collect();
+    codes.push(EXIT_INTERNAL);
+    codes.sort_unstable();
+    codes.dedup();
+    assert_eq!(codes.len(), all_kinds().len() + 1);
+}
+
+#[test]
+fn exit_code_follows_the_first_arb_error_in_the_chain() {
+    for (err, kind, code) in all_kinds() {
+        assert_eq!(err.kind(), kind);
+        assert_eq!(err.exit_code(), code);
+
+        let wrapped = Err::<(), _>(err)
+            .context("quote pool A")
+            .context("evaluate")
+            .unwrap_err();
+        assert_eq!(exit_code(&wrapped), code, "{wrapped:#}");
+        assert_eq!(classify(&wrapped).map(ArbError::kind), Some(kind));
+    }
+
+    let plain = anyhow::anyhow!("unexpected");
+    assert_eq!(exit_code(&plain), EXIT_INTERNAL);
+    assert!(classify(&plain).is_none());
+}
+
+#[test]
+fn errors_serialize_into_reports() {
+    let err = Err::<(), _>(ArbError::Rpc("getAccountInfo timed out".into()))
+        .context("load pool A")
+        .unwrap_err();
+    let report = serde_json::to_value(ErrorReport::from(&err)).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "kind": "rpc",
+            "exit_code": EXIT_RPC,
+            "message": "load pool A: rpc error: getAccountInfo timed out",
+        })
+    );
+
+    let report = serde_json::to_value(ErrorReport::from(&anyhow::anyhow!("boom"))).unwrap();
+    assert_eq!(report["kind"], "internal");
+    assert_eq!(report["exit_code"], EXIT_INTERNAL);
+    assert_eq!(report["message"], "boom");
+
+    let report = ErrorReport::from(&ArbError::Risk("pnl below floor".into()));
+    assert_eq!(report.kind, "risk");
+    assert_eq!(report.exit_code, EXIT_RISK);
+    assert_eq!(report.message, "risk limit: pnl below floor");
+}
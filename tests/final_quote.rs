@@ -0,0 +1,155 @@
+use solana_amm_arb_cli::{
+    amount::DecimalAmount,
+    arbitrage::{Arbitrage, FeeConversion, QuoteGuard, calculate_pnl, mid_price},
+    policy::Rule,
+    pool::PoolValues,
+    report::tx_mode,
+    twap::{PoolTwap, TwapAction, TwapBaseline, TwapGuard},
+};
+use solana_sdk::pubkey::Pubkey;
+
+fn pool(reserve0: u64, reserve1: u64) -> PoolValues {
+    PoolValues {
+        mint0: Pubkey::new_from_array([1; 32]),
+        mint1: Pubkey::new_from_array([2; 32]),
+        vault_amount0: reserve0,
+        vault_amount1: reserve1,
+        protocol_fees_token0: 0,
+        protocol_fees_token1: 0,
+        fund_fees_token0: 0,
+        fund_fees_token1: 0,
+        creator_fees_token0: 0,
+        creator_fees_token1: 0,
+        reserve0,
+        reserve1,
+        token0_decimals: 9,
+        token1_decimals: 6,
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 0,
+        fund_fee_rate: 0,
+        creator_fee_rate: 0,
+        creator_fee_on: 0,
+        enable_creator_fee: false,
+    }
+}
+
+fn quote(first: &PoolValues, second: &PoolValues) -> Arbitrage {
+    // Fees priced at one raw mint_in per lamport
+    let conversion = FeeConversion::identity();
+    let amount_in = DecimalAmount::from_raw(1_000_000_000, 9);
+    calculate_pnl(&amount_in, first, second, 0, 0, Some(&conversion)).unwrap()
+}
+
+// The quote the run decided on
+fn chosen() -> (PoolValues, PoolValues) {
+    (
+        pool(1_000_000_000_000, 2_000_000_000),
+        pool(1_000_000_000_000, 1_900_000_000),
+    )
+}
+
+// As `chosen`, but someone traded the second pool back towards the first in between
+fn moved() -> (PoolValues, PoolValues) {
+    (
+        pool(1_000_000_000_000, 2_000_000_000),
+        pool(1_000_000_000_000, 1_995_000_000),
+    )
+}
+
+fn mode(aborted: Option<&String>) -> &'static str {
+    tx_mode(aborted.is_some(), true, false, false, true)
+}
+
+#[test]
+fn an_unchanged_final_quote_is_sent() {
+    let policy = Rule::PnlRaw(1);
+    let guard = QuoteGuard {
+        min_pnl_raw: 1_000,
+        policy: &policy,
+        twap: None,
+    };
+    let (first, second) = chosen();
+    let arb = quote(&first, &second);
+    assert!(arb.pnl_raw.unwrap() > 1_000);
+
+    let aborted = guard.violation(&arb, &first, &second);
+    assert_eq!(aborted, None);
+    assert_eq!(mode(aborted.as_ref()), "send");
+}
+
+#[test]
+fn a_worse_final_quote_aborts() {
+    let policy = Rule::PnlRaw(1);
+    let guard = QuoteGuard {
+        min_pnl_raw: 1_000,
+        policy: &policy,
+        twap: None,
+    };
+    let (first, second) = moved();
+    let arb = quote(&first, &second);
+    assert!(arb.pnl_raw.unwrap() < quote(&chosen().0, &chosen().1).pnl_raw.unwrap());
+
+    let aborted = guard.violation(&arb, &first, &second);
+    let reason = aborted.as_ref().expect("a worse quote aborts");
+    assert!(reason.contains("below min_pnl_raw 1000"), "{reason}");
+    assert_eq!(mode(aborted.as_ref()), "aborted");
+    // An abort wins over every other mode
+    assert_eq!(tx_mode(true, false, true, true, false), "aborted");
+}
+
+#[test]
+fn the_decision_policy_is_checked_again() {
+    // Still profitable, but the spread no longer reaches the policy's threshold
+    let (first, second) = chosen();
+    let arb = quote(&first, &second);
+    let policy = Rule::MidSpreadBps(1_000_000.0);
+    let guard = QuoteGuard {
+        min_pnl_raw: 0,
+        policy: &policy,
+        twap: None,
+    };
+    let reason = guard.violation(&arb, &first, &second).unwrap();
+    assert!(reason.starts_with("decision policy not met"), "{reason}");
+}
+
+#[test]
+fn spot_prices_far_from_their_twaps_abort() {
+    let (first, second) = chosen();
+    let arb = quote(&first, &second);
+    let policy = Rule::PnlRaw(1);
+    let twap = |pool: &str, twap_price: f64| PoolTwap {
+        pool: pool.to_string(),
+        twap_price: Some(twap_price),
+        covered_secs: 1_800,
+    };
+    let baseline = |action| TwapBaseline {
+        guard: TwapGuard {
+            window_secs: 1_800,
+            max_deviation_bps: 100,
+            action,
+        },
+        first: twap("first", mid_price(&first)),
+        // The second pool's spot price is well away from where it traded recently
+        second: twap("second", mid_price(&second) * 1.1),
+    };
+
+    let blocking = baseline(TwapAction::Block);
+    let guard = QuoteGuard {
+        min_pnl_raw: 0,
+        policy: &policy,
+        twap: Some(&blocking),
+    };
+    let reason = guard.violation(&arb, &first, &second).unwrap();
+    assert!(
+        reason.starts_with("spot deviates from TWAP: second"),
+        "{reason}"
+    );
+
+    // `flag` only warns
+    let flagging = baseline(TwapAction::Flag);
+    let guard = QuoteGuard {
+        twap: Some(&flagging),
+        ..guard
+    };
+    assert_eq!(guard.violation(&arb, &first, &second), None);
+}
@@ -0,0 +1,42 @@
+use solana_amm_arb_cli::{
+    error::{ArbError, classify},
+    keystore::{decrypt, encrypt},
+};
+use solana_sdk::signer::{Signer, keypair::Keypair};
+
+fn config_error(err: &anyhow::Error) -> bool {
+    matches!(classify(err), Some(ArbError::Config(_)))
+}
+
+#[test]
+fn round_trips_with_the_right_password_only() {
+    let keypair = Keypair::new();
+    let ks = encrypt(&keypair, "correct horse").unwrap();
+    assert_eq!(ks.pubkey, keypair.pubkey().to_string());
+
+    let opened = decrypt(&ks, "correct horse").unwrap();
+    assert_eq!(opened.to_bytes(), keypair.to_bytes());
+
+    let err = decrypt(&ks, "battery staple").unwrap_err();
+    assert!(config_error(&err), "{err:#}");
+    assert!(err.to_string().contains("wrong keystore password"));
+}
+
+#[test]
+fn tampered_pubkey_fails_authentication() {
+    let mut ks = encrypt(&Keypair::new(), "pw").unwrap();
+    ks.pubkey = Keypair::new().pubkey().to_string();
+    let err = decrypt(&ks, "pw").unwrap_err();
+    assert!(config_error(&err), "{err:#}");
+}
+
+#[test]
+fn out_of_range_scrypt_cost_is_rejected() {
+    let mut ks = encrypt(&Keypair::new(), "pw").unwrap();
+    for log_n in [0, 9, 21, 63] {
+        ks.kdf.log_n = log_n;
+        let err = decrypt(&ks, "pw").unwrap_err();
+        assert!(config_error(&err), "log_n {log_n}: {err:#}");
+        assert!(err.to_string().contains("log_n"), "{err:#}");
+    }
+}
@@ -0,0 +1,107 @@
+use solana_amm_arb_cli::metrics::Metrics;
+use std::time::Duration;
+
+fn pair() -> Vec<(&'static str, String)> {
+    vec![("pair", "A-B".to_string())]
+}
+
+#[test]
+fn counters_and_gauges() {
+    let metrics = Metrics::new();
+    metrics.inc("arb_evaluations_total", pair());
+    metrics.inc("arb_evaluations_total", pair());
+    metrics.add("arb_sends_total", vec![("result", "ok".to_string())], 3.0);
+    metrics.set_gauge("arb_expected_pnl_raw", pair(), 120.0);
+    metrics.set_gauge("arb_expected_pnl_raw", pair(), -40.0);
+    metrics.add_gauge("arb_realized_pnl_raw", vec![], 25.0);
+    metrics.add_gauge("arb_realized_pnl_raw", vec![], 5.0);
+
+    let text = metrics.render();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "# HELP arb_evaluations_total Pool pair evaluations performed",
+            "# TYPE arb_evaluations_total counter",
+            "arb_evaluations_total{pair=\"A-B\"} 2",
+            "# HELP arb_sends_total Transaction sends by result",
+            "# TYPE arb_sends_total counter",
+            "arb_sends_total{result=\"ok\"} 3",
+            "# HELP arb_expected_pnl_raw Expected PnL of the last evaluation, raw mint_in units",
+            "# TYPE arb_expected_pnl_raw gauge",
+            "arb_expected_pnl_raw{pair=\"A-B\"} -40",
+            "# HELP arb_realized_pnl_raw Cumulative realized PnL of reconciled trades, raw mint_in units",
+            "# TYPE arb_realized_pnl_raw gauge",
+            "arb_realized_pnl_raw 30",
+        ]
+    );
+}
+
+#[test]
+fn one_header_per_metric_and_escaped_labels() {
+    let metrics = Metrics::new();
+    metrics.inc("arb_simulations_total", vec![("result", "ok".to_string())]);
+    metrics.inc(
+        "arb_simulations_total",
+        vec![("result", "err \"custom\" \\ 1".to_string())],
+    );
+
+    let text = metrics.render();
+    assert_eq!(
+        text.matches("# TYPE arb_simulations_total counter").count(),
+        1
+    );
+    assert!(text.contains("arb_simulations_total{result=\"ok\"} 1\n"));
+    assert!(text.contains("arb_simulations_total{result=\"err \\\"custom\\\" \\\\ 1\"} 1\n"));
+}
+
+#[test]
+fn histograms_are_cumulative() {
+    let metrics = Metrics::new();
+    for bps in [3.0, 30.0, 30.0, 5_000.0] {
+        metrics.observe_spread(pair(), bps);
+    }
+    metrics.observe_latency(
+        "arb_rpc_latency_seconds",
+        vec![("method", "getMultipleAccounts".to_string())],
+        Duration::from_millis(20),
+    );
+
+    let text = metrics.render();
+    let lines: Vec<&str> = text.lines().collect();
+    let spread = lines
+        .iter()
+        .position(|l| *l == "# TYPE arb_spread_bps histogram")
+        .unwrap();
+    assert_eq!(
+        lines[spread - 1],
+        "# HELP arb_spread_bps Mid-price spread observed between the two pools, in bps"
+    );
+    assert_eq!(
+        &lines[spread + 1..spread + 13],
+        [
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"1\"} 0",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"5\"} 1",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"10\"} 1",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"25\"} 1",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"50\"} 3",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"100\"} 3",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"250\"} 3",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"500\"} 3",
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"1000\"} 3",
+            // Above the last bucket: only +Inf and the count include it
+            "arb_spread_bps_bucket{pair=\"A-B\",le=\"+Inf\"} 4",
+            "arb_spread_bps_sum{pair=\"A-B\"} 5063",
+            "arb_spread_bps_count{pair=\"A-B\"} 4",
+        ]
+    );
+
+    assert!(text.contains("# TYPE arb_rpc_latency_seconds histogram\n"));
+    assert!(text.contains(
+        "arb_rpc_latency_seconds_bucket{method=\"getMultipleAccounts\",le=\"0.01\"} 0\n"
+    ));
+    assert!(text.contains(
+        "arb_rpc_latency_seconds_bucket{method=\"getMultipleAccounts\",le=\"0.025\"} 1\n"
+    ));
+    assert!(text.contains("arb_rpc_latency_seconds_count{method=\"getMultipleAccounts\"} 1\n"));
+}
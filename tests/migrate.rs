@@ -0,0 +1,125 @@
+use serde_json::{Value, json};
+use solana_amm_arb_cli::{
+    amount::DecimalAmount,
+    cli::{AppState, load_raw_state},
+    error::{ArbError, classify},
+    migrate::{STATE_SCHEMA_VERSION, parse_state, to_stored_json},
+};
+use std::{fs, path::Path};
+
+fn parse(value: Value) -> anyhow::Result<(AppState, Option<u32>)> {
+    parse_state(
+        &serde_json::to_vec(&value).unwrap(),
+        Path::new("state.json"),
+    )
+}
+
+fn amount(s: &str) -> Option<DecimalAmount> {
+    Some(s.parse().unwrap())
+}
+
+#[test]
+fn unversioned_files_migrate_to_the_current_layout() {
+    let (st, from) = parse(json!({
+        "pool_a": "A",
+        "pool_b": "B",
+        "mint_in": "So11111111111111111111111111111111111111112",
+        "mint_out": null,
+        "amount_in": 0.00001,
+        "spread_threshold_bps": 50,
+        "slippage_bps": 100,
+        "priority_fee_microlamports": 0,
+        "simulate_only": true,
+        "rpc_url": "https://api.mainnet-beta.solana.com",
+        "keypair_path": null,
+    }))
+    .unwrap();
+    assert_eq!(from, Some(0));
+    assert_eq!(st.pool_a.as_deref(), Some("A"));
+    assert_eq!(st.amount_in, amount("0.00001"));
+    assert_eq!(st.reference_pool, None);
+    assert!(st.notifiers.is_empty());
+    assert!(st.rpc_endpoints.is_empty());
+    assert_eq!(st.min_pnl_raw, None);
+    assert_eq!(st.decision_policy, None);
+    assert_eq!(st.twap_guard, None);
+
+    let stored: Value = serde_json::from_str(&to_stored_json(&st).unwrap()).unwrap();
+    assert_eq!(stored["schema_version"], STATE_SCHEMA_VERSION);
+    assert_eq!(stored["amount_in"], "0.00001");
+}
+
+#[test]
+fn v6_amounts_become_exact_strings() {
+    for (number, exact) in [
+        (json!(1.5), "1.5"),
+        (json!(3), "3"),
+        (json!(0.1), "0.1"),
+        (json!(123.456789), "123.456789"),
+    ] {
+        let (st, from) = parse(json!({ "schema_version": 6, "amount_in": number })).unwrap();
+        assert_eq!(from, Some(6));
+        assert_eq!(st.amount_in, amount(exact), "{number}");
+    }
+    // Already a string from v7 on; nothing to migrate
+    let (st, from) = parse(json!({ "schema_version": 7, "amount_in": "0.1" })).unwrap();
+    assert_eq!(from, Some(7));
+    assert_eq!(st.amount_in, amount("0.1"));
+}
+
+#[test]
+fn current_files_are_not_migrated() {
+    let (_, from) = parse(json!({ "schema_version": STATE_SCHEMA_VERSION })).unwrap();
+    assert_eq!(from, None);
+}
+
+#[test]
+fn newer_and_broken_files_are_refused() {
+    for value in [
+        json!({ "schema_version": STATE_SCHEMA_VERSION + 1 }),
+        json!({ "schema_version": "9" }),
+        json!({ "schema_version": STATE_SCHEMA_VERSION, "amount_in": -1 }),
+        json!([]),
+    ] {
+        let err = parse(value.clone()).unwrap_err();
+        assert!(
+            matches!(classify(&err), Some(ArbError::Config(_))),
+            "{value}: {err:#}"
+        );
+    }
+    let err = parse(json!({ "schema_version": STATE_SCHEMA_VERSION + 1 })).unwrap_err();
+    assert!(err.to_string().contains("newer than this build supports"));
+}
+
+#[test]
+fn loading_an_old_file_keeps_a_backup() {
+    let dir = std::env::temp_dir().join(format!("arb-migrate-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("state.json");
+    let original = br#"{"schema_version": 6, "amount_in": 2.25, "notifiers": []}"#;
+    fs::write(&path, original).unwrap();
+
+    let st = load_raw_state(&path).unwrap();
+    assert_eq!(st.amount_in, amount("2.25"));
+
+    let backups: Vec<_> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().path())
+        .filter(|p| {
+            let name = p.file_name().unwrap().to_string_lossy();
+            name.starts_with("state.json.v6-") && name.ends_with(".bak")
+        })
+        .collect();
+    assert_eq!(backups.len(), 1, "{backups:?}");
+    assert_eq!(fs::read(&backups[0]).unwrap(), original);
+
+    let rewritten: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+    assert_eq!(rewritten["schema_version"], STATE_SCHEMA_VERSION);
+    assert_eq!(rewritten["amount_in"], "2.25");
+
+    // Already current: loads without another backup
+    load_raw_state(&path).unwrap();
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+    fs::remove_dir_all(dir).ok();
+}
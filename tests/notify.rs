@@ -0,0 +1,143 @@
+use serde_json::{Value, json};
+use solana_amm_arb_cli::notify::{Event, EventKind, Notifier, NotifierConfig, SinkConfig};
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc::{self, Receiver},
+    thread,
+    time::Duration,
+};
+
+// Minimal HTTP endpoint: answers every request with 200 and forwards "<request line>" and
+// the JSON body before replying, so a delivered notification is queued once `notify` returns
+fn stub_server() -> (String, Receiver<(String, Value)>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                match header.split_once(':') {
+                    Some((name, value)) if name.eq_ignore_ascii_case("content-length") => {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            let body = serde_json::from_slice(&body).unwrap();
+            tx.send((request_line.trim_end().to_string(), body))
+                .unwrap();
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                .unwrap();
+        }
+    });
+    (url, rx)
+}
+
+fn sink(sink: SinkConfig, events: &[EventKind], template: Option<&str>) -> NotifierConfig {
+    NotifierConfig {
+        sink,
+        events: events.to_vec(),
+        template: template.map(str::to_string),
+        min_interval_secs: 0,
+    }
+}
+
+fn landed() -> Event {
+    Event {
+        kind: EventKind::TradeLanded,
+        direction: "PoolA→PoolB".to_string(),
+        pool_a: "poolA".to_string(),
+        pool_b: "poolB".to_string(),
+        spread_bps: 42.5,
+        pnl: Some(0.5),
+        signature: Some("5igSig".to_string()),
+        detail: String::new(),
+    }
+}
+
+fn received(rx: &Receiver<(String, Value)>) -> (String, Value) {
+    rx.recv_timeout(Duration::from_secs(5))
+        .expect("notification delivered")
+}
+
+#[test]
+fn webhook_posts_the_event_with_rendered_text() {
+    let (url, rx) = stub_server();
+    let notifier = Notifier::new(vec![sink(
+        SinkConfig::Webhook { url },
+        &[EventKind::TradeLanded],
+        Some("{event} {direction} on {pool_a}/{pool_b}: {spread_bps} bps, pnl {pnl} ({signature})"),
+    )]);
+    notifier.notify(&landed());
+
+    let (request_line, body) = received(&rx);
+    assert!(request_line.starts_with("POST /hook "), "{request_line}");
+    assert_eq!(
+        body,
+        json!({
+            "kind": "trade_landed",
+            "direction": "PoolA→PoolB",
+            "pool_a": "poolA",
+            "pool_b": "poolB",
+            "spread_bps": 42.5,
+            "pnl": 0.5,
+            "signature": "5igSig",
+            "detail": "",
+            "text": "trade_landed PoolA→PoolB on poolA/poolB: 42.50 bps, pnl 0.500000000 (5igSig)",
+        })
+    );
+}
+
+#[test]
+fn slack_and_discord_get_their_own_shapes() {
+    let (url, rx) = stub_server();
+    let notifier = Notifier::new(vec![
+        sink(
+            SinkConfig::Slack { url: url.clone() },
+            &[EventKind::TradeLanded],
+            None,
+        ),
+        sink(SinkConfig::Discord { url }, &[EventKind::TradeLanded], None),
+    ]);
+    notifier.notify(&landed());
+
+    let text = "Trade landed PoolA→PoolB: spread 42.50 bps, pnl 0.500000000 (5igSig)";
+    assert_eq!(received(&rx).1, json!({ "text": text }));
+    assert_eq!(received(&rx).1, json!({ "content": text }));
+}
+
+#[test]
+fn filtered_and_rate_limited_events_are_dropped() {
+    let (url, rx) = stub_server();
+    let notifier = Notifier::new(vec![NotifierConfig {
+        min_interval_secs: 3600,
+        ..sink(SinkConfig::Webhook { url }, &[EventKind::TradeLanded], None)
+    }]);
+
+    // Not subscribed
+    notifier.notify(&Event {
+        kind: EventKind::Opportunity,
+        ..landed()
+    });
+    notifier.notify(&landed());
+    // Within `min_interval_secs` of the previous message
+    notifier.notify(&landed());
+
+    assert_eq!(received(&rx).1["kind"], "trade_landed");
+    assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,176 @@
+use solana_amm_arb_cli::policy::{Rule, Signals};
+
+// 100 bps mid spread, 60 bps after pool fees, +5_000 raw (50 bps of 1_000_000 in), 1_000 fee
+fn signals() -> Signals {
+    Signals {
+        mid_spread_bps: 100.0,
+        effective_spread_bps: Some(60.0),
+        pnl_raw: Some(5_000),
+        pnl_bps: Some(50.0),
+        failure_cost_raw: Some(1_000),
+    }
+}
+
+fn passes(rule: &Rule, s: &Signals) -> bool {
+    rule.evaluate(s).passed
+}
+
+fn rule(json: &str) -> Rule {
+    json.parse().unwrap()
+}
+
+#[test]
+fn mid_spread_bps() {
+    assert!(passes(&Rule::MidSpreadBps(100.0), &signals()));
+    assert!(!passes(&Rule::MidSpreadBps(100.5), &signals()));
+    assert_eq!(Rule::from_spread_threshold(100), Rule::MidSpreadBps(100.0));
+}
+
+#[test]
+fn effective_spread_bps() {
+    assert!(passes(&Rule::EffectiveSpreadBps(60.0), &signals()));
+    assert!(!passes(&Rule::EffectiveSpreadBps(61.0), &signals()));
+    let unknown = Signals {
+        effective_spread_bps: None,
+        ..signals()
+    };
+    assert!(!passes(&Rule::EffectiveSpreadBps(-100.0), &unknown));
+}
+
+#[test]
+fn pnl_raw() {
+    assert!(passes(&Rule::PnlRaw(5_000), &signals()));
+    assert!(!passes(&Rule::PnlRaw(5_001), &signals()));
+    // A zero minimum still needs a profit
+    let flat = Signals {
+        pnl_raw: Some(0),
+        ..signals()
+    };
+    assert!(!passes(&Rule::PnlRaw(0), &flat));
+    let unknown = Signals {
+        pnl_raw: None,
+        ..signals()
+    };
+    assert!(!passes(&Rule::PnlRaw(0), &unknown));
+}
+
+#[test]
+fn pnl_bps() {
+    assert!(passes(&Rule::PnlBps(50.0), &signals()));
+    assert!(!passes(&Rule::PnlBps(50.1), &signals()));
+    // A negative minimum does not let a loss through
+    let loss = Signals {
+        pnl_raw: Some(-10),
+        pnl_bps: Some(-0.1),
+        ..signals()
+    };
+    assert!(!passes(&Rule::PnlBps(-1.0), &loss));
+}
+
+#[test]
+fn expected_value_raw() {
+    // 0.5 * 5_000 - 0.5 * 1_000 = 2_000
+    let ev = |min| Rule::ExpectedValueRaw {
+        land_probability: 0.5,
+        min,
+    };
+    assert_eq!(signals().expected_value_raw(0.5), Some(2_000.0));
+    assert!(passes(&ev(2_000), &signals()));
+    assert!(!passes(&ev(2_001), &signals()));
+    // Never landing only ever costs the fee
+    let never = Rule::ExpectedValueRaw {
+        land_probability: 0.0,
+        min: -1_000,
+    };
+    assert!(passes(&never, &signals()));
+    let no_fee_price = Signals {
+        failure_cost_raw: None,
+        ..signals()
+    };
+    assert!(!passes(&ev(i64::MIN), &no_fee_price));
+}
+
+#[test]
+fn all_and_any() {
+    let pass = Rule::MidSpreadBps(50.0);
+    let fail = Rule::PnlRaw(1_000_000);
+    let all = Rule::All(vec![pass.clone(), fail.clone()]);
+    let any = Rule::Any(vec![fail.clone(), pass.clone()]);
+    assert!(!passes(&all, &signals()));
+    assert!(passes(&any, &signals()));
+    assert!(passes(&Rule::All(vec![pass.clone(), pass]), &signals()));
+    assert!(!passes(&Rule::Any(vec![fail.clone(), fail]), &signals()));
+
+    // Only the failing leaves are reported, with the value they saw
+    let eval = all.evaluate(&signals());
+    assert_eq!(eval.children.len(), 2);
+    assert_eq!(eval.failures(), vec!["pnl_raw 5000.0000 (min 1000000)"]);
+    assert!(any.evaluate(&signals()).failures().is_empty());
+}
+
+#[test]
+fn nested_json_policies_round_trip() {
+    let text = r#"{"any":[{"pnl_raw":5000},{"all":[{"mid_spread_bps":80.0},{"pnl_bps":5.0}]}]}"#;
+    let policy = rule(text);
+    assert_eq!(
+        policy,
+        Rule::Any(vec![
+            Rule::PnlRaw(5_000),
+            Rule::All(vec![Rule::MidSpreadBps(80.0), Rule::PnlBps(5.0)]),
+        ])
+    );
+    assert_eq!(policy.to_string(), text);
+    assert!(passes(&policy, &signals()));
+    assert!(passes(
+        &rule(r#"{"expected_value_raw":{"land_probability":0.9,"min":0}}"#),
+        &signals()
+    ));
+}
+
+#[test]
+fn invalid_policies_are_rejected() {
+    for bad in [
+        Rule::All(Vec::new()),
+        Rule::Any(Vec::new()),
+        Rule::Any(vec![Rule::PnlRaw(1), Rule::All(Vec::new())]),
+        Rule::MidSpreadBps(f64::NAN),
+        Rule::EffectiveSpreadBps(f64::INFINITY),
+        Rule::PnlBps(f64::NEG_INFINITY),
+        Rule::ExpectedValueRaw {
+            land_probability: -0.1,
+            min: 0,
+        },
+        Rule::ExpectedValueRaw {
+            land_probability: 1.5,
+            min: 0,
+        },
+        Rule::ExpectedValueRaw {
+            land_probability: f64::NAN,
+            min: 0,
+        },
+    ] {
+        assert!(bad.check().is_err(), "{bad:?} accepted");
+    }
+    for good in [
+        Rule::PnlRaw(0),
+        Rule::ExpectedValueRaw {
+            land_probability: 0.0,
+            min: 0,
+        },
+        Rule::ExpectedValueRaw {
+            land_probability: 1.0,
+            min: 0,
+        },
+    ] {
+        assert!(good.check().is_ok(), "{good:?} rejected");
+    }
+    for bad in [
+        r#"{"all":[]}"#,
+        r#"{"any":[{"all":[]}]}"#,
+        r#"{"expected_value_raw":{"land_probability":2,"min":0}}"#,
+        r#"{"pnl_raw":-1}"#,
+        r#"{"spread":10}"#,
+    ] {
+        assert!(bad.parse::<Rule>().is_err(), "{bad} parsed");
+    }
+}
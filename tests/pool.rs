@@ -0,0 +1,50 @@
+use solana_amm_arb_cli::pool::{
+    POOL_STATUS_DEPOSIT_DISABLED, POOL_STATUS_SWAP_DISABLED, POOL_STATUS_WITHDRAW_DISABLED,
+    swap_blockers,
+};
+
+const OPEN_TIME: u64 = 1_700_000_000;
+const AFTER_OPEN: i64 = OPEN_TIME as i64 + 1;
+
+#[test]
+fn an_open_pool_has_no_blockers() {
+    assert!(swap_blockers(0, OPEN_TIME, AFTER_OPEN).is_empty());
+    // Deposit and withdraw switches do not affect swaps
+    let status = POOL_STATUS_DEPOSIT_DISABLED | POOL_STATUS_WITHDRAW_DISABLED;
+    assert!(swap_blockers(status, OPEN_TIME, AFTER_OPEN).is_empty());
+    assert!(swap_blockers(0, 0, 1).is_empty());
+}
+
+#[test]
+fn the_swap_disabled_bit_blocks() {
+    assert_eq!(
+        swap_blockers(POOL_STATUS_SWAP_DISABLED, OPEN_TIME, AFTER_OPEN),
+        ["swaps disabled (status 0b100)"]
+    );
+    let status = POOL_STATUS_SWAP_DISABLED | POOL_STATUS_DEPOSIT_DISABLED;
+    assert_eq!(
+        swap_blockers(status, OPEN_TIME, AFTER_OPEN),
+        ["swaps disabled (status 0b101)"]
+    );
+}
+
+#[test]
+fn swaps_need_now_past_open_time() {
+    // The program requires `now > open_time`
+    assert_eq!(
+        swap_blockers(0, OPEN_TIME, OPEN_TIME as i64),
+        [format!("not open until {OPEN_TIME} (0s from now)")]
+    );
+    assert_eq!(
+        swap_blockers(0, OPEN_TIME, OPEN_TIME as i64 - 60),
+        [format!("not open until {OPEN_TIME} (60s from now)")]
+    );
+    // A clock before the epoch does not underflow
+    assert_eq!(swap_blockers(0, 0, -5), ["not open until 0 (0s from now)"]);
+}
+
+#[test]
+fn every_blocker_is_reported() {
+    let reasons = swap_blockers(POOL_STATUS_SWAP_DISABLED, OPEN_TIME, 0);
+    assert_eq!(reasons.len(), 2, "{reasons:?}");
+}
@@ -0,0 +1,56 @@
+use solana_amm_arb_cli::{
+    cli::{
+        AppState, ConfigKey, config_set, default_state, load_raw_state, load_state, write_raw_state,
+    },
+    profile::profile_state_path,
+};
+use std::path::{Path, PathBuf};
+
+// Profiles live under the platform state dir; point it at a scratch dir for this binary
+fn scratch_state_dir() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("arb-profile-test-{}", std::process::id()));
+    // SAFETY: the only test in this binary, set before anything reads the environment
+    unsafe { std::env::set_var("XDG_STATE_HOME", &dir) };
+    dir
+}
+
+fn create(name: &str) -> PathBuf {
+    let path = profile_state_path(name).unwrap();
+    write_raw_state(&path, &default_state()).unwrap();
+    path
+}
+
+fn set_base(profile: &str, path: &Path, base: &str) -> anyhow::Result<()> {
+    let mut state = load_state(profile, path)?;
+    config_set(
+        profile,
+        path,
+        &mut state,
+        ConfigKey::Base,
+        &[base.to_string()],
+        None,
+    )
+}
+
+#[test]
+fn self_and_cyclic_bases_are_rejected_before_saving() {
+    let dir = scratch_state_dir();
+    let a = create("a");
+    let b = create("b");
+
+    let err = set_base("a", &a, "a").unwrap_err();
+    assert!(err.to_string().contains("cycle"), "{err}");
+    assert_eq!(load_raw_state(&a).unwrap().base, None);
+
+    set_base("b", &b, "a").unwrap();
+    let err = set_base("a", &a, "b").unwrap_err();
+    assert!(err.to_string().contains("a -> b -> a"), "{err}");
+    assert_eq!(load_raw_state(&a).unwrap().base, None);
+
+    // Both profiles still load, and the valid base stuck
+    load_state("a", &a).unwrap();
+    let b_state: AppState = load_state("b", &b).unwrap();
+    assert_eq!(b_state.base.as_deref(), Some("a"));
+
+    std::fs::remove_dir_all(dir).ok();
+}
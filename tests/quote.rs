@@ -0,0 +1,287 @@
+use proptest::prelude::*;
+use solana_amm_arb_cli::{
+    arbitrage::quote_swap,
+    error::ArbError,
+    pool::{
+        CREATOR_FEE_ON_BOTH, CREATOR_FEE_ON_TOKEN0, CREATOR_FEE_ON_TOKEN1, FEE_RATE_DENOMINATOR,
+        PoolValues,
+    },
+};
+use solana_sdk::pubkey::Pubkey;
+
+// Port of the CPMM program's `Fees` and `CurveCalculator::swap_base_input`, kept as close
+// to the on-chain source as possible so `quote_swap` is checked against it, not itself
+mod reference {
+    use super::FEE_RATE_DENOMINATOR;
+
+    pub struct SwapResult {
+        pub input_amount_less_fees: u128,
+        pub output_amount: u128,
+        pub trade_fee: u128,
+        pub protocol_fee: u128,
+        pub fund_fee: u128,
+        pub creator_fee: u128,
+    }
+
+    fn ceil_div(token_amount: u128, fee_numerator: u128, fee_denominator: u128) -> Option<u128> {
+        token_amount
+            .checked_mul(fee_numerator)?
+            .checked_add(fee_denominator)?
+            .checked_sub(1)?
+            .checked_div(fee_denominator)
+    }
+
+    fn floor_div(token_amount: u128, fee_numerator: u128, fee_denominator: u128) -> Option<u128> {
+        token_amount
+            .checked_mul(fee_numerator)?
+            .checked_div(fee_denominator)
+    }
+
+    struct Fees;
+
+    impl Fees {
+        fn trading_fee(amount: u128, trade_fee_rate: u64) -> Option<u128> {
+            ceil_div(amount, trade_fee_rate.into(), FEE_RATE_DENOMINATOR.into())
+        }
+
+        fn protocol_fee(amount: u128, protocol_fee_rate: u64) -> Option<u128> {
+            floor_div(
+                amount,
+                protocol_fee_rate.into(),
+                FEE_RATE_DENOMINATOR.into(),
+            )
+        }
+
+        fn fund_fee(amount: u128, fund_fee_rate: u64) -> Option<u128> {
+            floor_div(amount, fund_fee_rate.into(), FEE_RATE_DENOMINATOR.into())
+        }
+
+        fn creator_fee(amount: u128, creator_fee_rate: u64) -> Option<u128> {
+            ceil_div(amount, creator_fee_rate.into(), FEE_RATE_DENOMINATOR.into())
+        }
+
+        fn split_creator_fee(
+            total_fee: u128,
+            trade_fee_rate: u64,
+            creator_fee_rate: u64,
+        ) -> Option<u128> {
+            floor_div(
+                total_fee,
+                creator_fee_rate.into(),
+                (trade_fee_rate + creator_fee_rate).into(),
+            )
+        }
+    }
+
+    fn swap_base_input_without_fees(
+        input_amount: u128,
+        input_vault_amount: u128,
+        output_vault_amount: u128,
+    ) -> Option<u128> {
+        let numerator = input_amount.checked_mul(output_vault_amount)?;
+        let denominator = input_vault_amount.checked_add(input_amount)?;
+        numerator.checked_div(denominator)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn swap_base_input(
+        input_amount: u128,
+        input_vault_amount: u128,
+        output_vault_amount: u128,
+        trade_fee_rate: u64,
+        creator_fee_rate: u64,
+        protocol_fee_rate: u64,
+        fund_fee_rate: u64,
+        is_creator_fee_on_input: bool,
+    ) -> Option<SwapResult> {
+        let mut creator_fee = 0;
+        let trade_fee;
+        let input_amount_less_fees = if is_creator_fee_on_input {
+            let total_fee = Fees::trading_fee(input_amount, trade_fee_rate + creator_fee_rate)?;
+            creator_fee = Fees::split_creator_fee(total_fee, trade_fee_rate, creator_fee_rate)?;
+            trade_fee = total_fee - creator_fee;
+            input_amount.checked_sub(total_fee)?
+        } else {
+            trade_fee = Fees::trading_fee(input_amount, trade_fee_rate)?;
+            input_amount.checked_sub(trade_fee)?
+        };
+        let protocol_fee = Fees::protocol_fee(trade_fee, protocol_fee_rate)?;
+        let fund_fee = Fees::fund_fee(trade_fee, fund_fee_rate)?;
+
+        let output_amount_swapped = swap_base_input_without_fees(
+            input_amount_less_fees,
+            input_vault_amount,
+            output_vault_amount,
+        )?;
+        let output_amount = if is_creator_fee_on_input {
+            output_amount_swapped
+        } else {
+            creator_fee = Fees::creator_fee(output_amount_swapped, creator_fee_rate)?;
+            output_amount_swapped.checked_sub(creator_fee)?
+        };
+
+        Some(SwapResult {
+            input_amount_less_fees,
+            output_amount,
+            trade_fee,
+            protocol_fee,
+            fund_fee,
+            creator_fee,
+        })
+    }
+}
+
+struct Rates {
+    trade: u64,
+    protocol: u64,
+    fund: u64,
+    creator: u64,
+    creator_fee_on: u8,
+    enable_creator_fee: bool,
+}
+
+fn pool(reserve0: u64, reserve1: u64, rates: &Rates) -> PoolValues {
+    PoolValues {
+        mint0: Pubkey::new_unique(),
+        mint1: Pubkey::new_unique(),
+        vault_amount0: reserve0,
+        vault_amount1: reserve1,
+        protocol_fees_token0: 0,
+        protocol_fees_token1: 0,
+        fund_fees_token0: 0,
+        fund_fees_token1: 0,
+        creator_fees_token0: 0,
+        creator_fees_token1: 0,
+        reserve0,
+        reserve1,
+        token0_decimals: 9,
+        token1_decimals: 6,
+        trade_fee_rate: rates.trade,
+        protocol_fee_rate: rates.protocol,
+        fund_fee_rate: rates.fund,
+        creator_fee_rate: rates.creator,
+        creator_fee_on: rates.creator_fee_on,
+        enable_creator_fee: rates.enable_creator_fee,
+    }
+}
+
+// Rates as the program's `AmmConfig` allows them: trade + creator below the denominator,
+// protocol and fund shares of the trade fee at most all of it
+fn rates() -> impl Strategy<Value = Rates> {
+    (
+        1..=100_000u64,
+        0..=FEE_RATE_DENOMINATOR,
+        0..=FEE_RATE_DENOMINATOR,
+        0..=100_000u64,
+        prop_oneof![
+            Just(CREATOR_FEE_ON_BOTH),
+            Just(CREATOR_FEE_ON_TOKEN0),
+            Just(CREATOR_FEE_ON_TOKEN1)
+        ],
+        any::<bool>(),
+    )
+        .prop_map(
+            |(trade, protocol, fund, creator, creator_fee_on, enable_creator_fee)| Rates {
+                trade,
+                protocol,
+                fund,
+                creator,
+                creator_fee_on,
+                enable_creator_fee,
+            },
+        )
+}
+
+proptest! {
+    #[test]
+    fn quote_matches_program(
+        amount_in in any::<u64>(),
+        reserve0 in 1..=u64::MAX,
+        reserve1 in 1..=u64::MAX,
+        rates in rates(),
+        zero_for_one in any::<bool>(),
+    ) {
+        let pool = pool(reserve0, reserve1, &rates);
+        let (reserve_in, reserve_out) = if zero_for_one {
+            (reserve0, reserve1)
+        } else {
+            (reserve1, reserve0)
+        };
+        // Mirrors the program's `is_creator_fee_on_input` and disabled creator fees
+        let on_input = match rates.creator_fee_on {
+            CREATOR_FEE_ON_TOKEN0 => zero_for_one,
+            CREATOR_FEE_ON_TOKEN1 => !zero_for_one,
+            _ => true,
+        };
+        let creator_rate = if rates.enable_creator_fee { rates.creator } else { 0 };
+
+        let expected = reference::swap_base_input(
+            amount_in.into(),
+            reserve_in.into(),
+            reserve_out.into(),
+            rates.trade,
+            creator_rate,
+            rates.protocol,
+            rates.fund,
+            on_input,
+        )
+        .expect("in-range inputs never fail on chain");
+        let quote = quote_swap(amount_in, &pool, zero_for_one).unwrap();
+
+        prop_assert_eq!(quote.creator_fee_on_input, on_input);
+        prop_assert_eq!(quote.amount_out as u128, expected.output_amount);
+        prop_assert_eq!(quote.trade_fee as u128, expected.trade_fee);
+        prop_assert_eq!(quote.protocol_fee as u128, expected.protocol_fee);
+        prop_assert_eq!(quote.fund_fee as u128, expected.fund_fee);
+        prop_assert_eq!(quote.creator_fee as u128, expected.creator_fee);
+        // Everything charged on the input side comes out of `amount_in`
+        let input_fees = quote.trade_fee + if on_input { quote.creator_fee } else { 0 };
+        prop_assert_eq!((amount_in - input_fees) as u128, expected.input_amount_less_fees);
+    }
+}
+
+#[test]
+fn input_creator_fee_is_rounded_up_once() {
+    // 0.25% trade + 0.05% creator on 1 unit: one combined fee of 1, all of it trade fee.
+    // Rounding the two fees up separately would charge 2.
+    let rates = Rates {
+        trade: 2_500,
+        protocol: 0,
+        fund: 0,
+        creator: 500,
+        creator_fee_on: CREATOR_FEE_ON_BOTH,
+        enable_creator_fee: true,
+    };
+    let quote = quote_swap(1, &pool(1_000_000, 1_000_000, &rates), true).unwrap();
+    assert_eq!((quote.trade_fee, quote.creator_fee), (1, 0));
+    assert_eq!(quote.amount_out, 0);
+
+    let quote = quote_swap(1_000_000, &pool(1_000_000_000, 1_000_000_000, &rates), true).unwrap();
+    assert_eq!((quote.trade_fee, quote.creator_fee), (2_500, 500));
+}
+
+#[test]
+fn fees_above_the_amount_are_a_math_error() {
+    // A misconfigured 200% trade fee would leave less than nothing to swap
+    let rates = Rates {
+        trade: 2 * FEE_RATE_DENOMINATOR,
+        protocol: 0,
+        fund: 0,
+        creator: 0,
+        creator_fee_on: CREATOR_FEE_ON_BOTH,
+        enable_creator_fee: false,
+    };
+    let err = quote_swap(1_000, &pool(1_000_000, 1_000_000, &rates), true).unwrap_err();
+    assert!(matches!(err, ArbError::Math(_)), "{err}");
+
+    // Same for a creator fee taken from the output
+    let rates = Rates {
+        trade: 2_500,
+        creator: 2 * FEE_RATE_DENOMINATOR,
+        creator_fee_on: CREATOR_FEE_ON_TOKEN1,
+        enable_creator_fee: true,
+        ..rates
+    };
+    let err = quote_swap(1_000, &pool(1_000_000, 1_000_000, &rates), true).unwrap_err();
+    assert!(matches!(err, ArbError::Math(_)), "{err}");
+}
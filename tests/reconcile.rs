@@ -0,0 +1,268 @@
+use solana_account_decoder::parse_token::UiTokenAmount;
+use solana_amm_arb_cli::{
+    amount::DecimalAmount,
+    arbitrage::{Arbitrage, FeeConversion, SOL_MINT, calculate_pnl},
+    error::{ArbError, classify},
+    pool::PoolValues,
+    reconcile::{reconcile_transaction, wrapped_lamports},
+    utils::TokenAccount,
+};
+use solana_sdk::{message::MessageHeader, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction,
+    EncodedTransactionWithStatusMeta, UiMessage, UiRawMessage, UiTransaction,
+    UiTransactionStatusMeta, UiTransactionTokenBalance, option_serializer::OptionSerializer,
+};
+
+const RENT: u64 = 2_039_280;
+const NETWORK_FEE: u64 = 5_000;
+
+fn pool(mint0: Pubkey, mint1: Pubkey, reserve0: u64, reserve1: u64) -> PoolValues {
+    PoolValues {
+        mint0,
+        mint1,
+        vault_amount0: reserve0,
+        vault_amount1: reserve1,
+        protocol_fees_token0: 0,
+        protocol_fees_token1: 0,
+        fund_fees_token0: 0,
+        fund_fees_token1: 0,
+        creator_fees_token0: 0,
+        creator_fees_token1: 0,
+        reserve0,
+        reserve1,
+        token0_decimals: 9,
+        token1_decimals: 6,
+        trade_fee_rate: 2_500,
+        protocol_fee_rate: 0,
+        fund_fee_rate: 0,
+        creator_fee_rate: 0,
+        creator_fee_on: 0,
+        enable_creator_fee: false,
+    }
+}
+
+// mint_in -> mint_out on a cheap pool, back on a dear one: a profitable round trip
+fn arbitrage(mint_in: Pubkey, reference: Option<&FeeConversion>) -> Arbitrage {
+    let mint_out = Pubkey::new_unique();
+    let pool_in = pool(mint_in, mint_out, 1_000_000_000_000, 2_000_000_000);
+    let pool_out = pool(mint_in, mint_out, 1_000_000_000_000, 1_900_000_000);
+    let amount_in = DecimalAmount::from_raw(1_000_000_000, 9);
+    calculate_pnl(&amount_in, &pool_in, &pool_out, 0, 0, reference).unwrap()
+}
+
+fn ata(mint: Pubkey, exists: bool) -> TokenAccount {
+    TokenAccount {
+        mint,
+        ata: Pubkey::new_unique(),
+        exists,
+        amount: 0,
+    }
+}
+
+fn token_balance(account_index: u8, amount: u64) -> UiTransactionTokenBalance {
+    UiTransactionTokenBalance {
+        account_index,
+        mint: Pubkey::new_unique().to_string(),
+        ui_token_amount: UiTokenAmount {
+            ui_amount: None,
+            decimals: 0,
+            amount: amount.to_string(),
+            ui_amount_string: amount.to_string(),
+        },
+        owner: OptionSerializer::Skip,
+        program_id: OptionSerializer::Skip,
+    }
+}
+
+struct Landed {
+    keys: Vec<Pubkey>,
+    pre_balances: Vec<u64>,
+    post_balances: Vec<u64>,
+    pre_tokens: Vec<UiTransactionTokenBalance>,
+    post_tokens: Vec<UiTransactionTokenBalance>,
+}
+
+fn confirmed(landed: Landed) -> EncodedConfirmedTransactionWithStatusMeta {
+    let meta = UiTransactionStatusMeta {
+        err: None,
+        status: Ok(()),
+        fee: NETWORK_FEE,
+        pre_balances: landed.pre_balances,
+        post_balances: landed.post_balances,
+        inner_instructions: OptionSerializer::None,
+        log_messages: OptionSerializer::None,
+        pre_token_balances: OptionSerializer::Some(landed.pre_tokens),
+        post_token_balances: OptionSerializer::Some(landed.post_tokens),
+        rewards: OptionSerializer::None,
+        loaded_addresses: OptionSerializer::Skip,
+        return_data: OptionSerializer::Skip,
+        compute_units_consumed: OptionSerializer::Skip,
+    };
+    let message = UiRawMessage {
+        header: MessageHeader::default(),
+        account_keys: landed.keys.iter().map(ToString::to_string).collect(),
+        recent_blockhash: String::new(),
+        instructions: vec![],
+        address_table_lookups: None,
+    };
+    EncodedConfirmedTransactionWithStatusMeta {
+        slot: 321,
+        transaction: EncodedTransactionWithStatusMeta {
+            transaction: EncodedTransaction::Json(UiTransaction {
+                signatures: vec![],
+                message: UiMessage::Raw(message),
+            }),
+            meta: Some(meta),
+            version: None,
+        },
+        block_time: Some(1_700_000_000),
+    }
+}
+
+#[test]
+fn separate_fee_payer_and_slippage() {
+    // 2 raw mint_in per lamport
+    let conversion = FeeConversion {
+        mint_in_per: 2,
+        lamports_per: 1,
+        source: "test".to_string(),
+    };
+    let arb = arbitrage(Pubkey::new_unique(), Some(&conversion));
+    assert!(arb.gross_profit_raw > 0);
+    let owner = Pubkey::new_unique();
+    let fee_payer = Pubkey::new_unique();
+    let atas = [
+        ata(Pubkey::new_unique(), true),
+        ata(Pubkey::new_unique(), true),
+    ];
+
+    // Leg 1 paid 5 less than quoted, so the mint_out account ends 5 short; leg 2 paid 10 less
+    let out_2 = arb.amount_out_2_raw - 10;
+    let priority_fee = 2_000;
+    let landed = Landed {
+        keys: vec![fee_payer, owner, atas[0].ata, atas[1].ata],
+        pre_balances: vec![1_000_000_000, 5_000_000_000, RENT, RENT],
+        post_balances: vec![
+            1_000_000_000 - NETWORK_FEE - priority_fee,
+            5_000_000_000,
+            RENT,
+            RENT,
+        ],
+        pre_tokens: vec![token_balance(2, 1_000_000_000_000), token_balance(3, 500)],
+        post_tokens: vec![
+            token_balance(2, 1_000_000_000_000 - arb.amount_in_raw + out_2),
+            token_balance(3, 495),
+        ],
+    };
+    let r = reconcile_transaction(
+        confirmed(landed),
+        &Signature::default(),
+        &owner,
+        &fee_payer,
+        &atas,
+        &arb,
+    )
+    .unwrap();
+
+    assert_eq!(
+        (r.slot, r.block_time, r.tx_fee_lamports),
+        (321, Some(1_700_000_000), NETWORK_FEE)
+    );
+    assert_eq!(
+        r.payer_lamports_delta,
+        -((NETWORK_FEE + priority_fee) as i64)
+    );
+    assert_eq!(r.realized_fees_lamports, NETWORK_FEE + priority_fee);
+    assert_eq!(r.ata_out_delta_raw, -5);
+    assert_eq!(r.realized_amount_out_1_raw, arb.amount_out_1_raw - 5);
+    assert_eq!(r.realized_amount_out_2_raw, out_2);
+    let gross = out_2 as i64 - arb.amount_in_raw as i64;
+    assert_eq!(r.realized_gross_profit_raw, gross);
+    // Fees converted at 2 mint_in per lamport
+    assert_eq!(
+        r.realized_pnl_raw,
+        Some(gross - 2 * (NETWORK_FEE + priority_fee) as i64)
+    );
+    assert_eq!((r.slippage.leg_1_raw, r.slippage.leg_2_raw), (5, 10));
+    assert_eq!(
+        r.slippage.fees_delta_lamports,
+        (NETWORK_FEE + priority_fee) as i64
+    );
+    assert_eq!(
+        r.slippage.pnl_delta_raw,
+        Some(r.realized_pnl_raw.unwrap() - arb.pnl_raw.unwrap())
+    );
+}
+
+#[test]
+fn wrapped_sol_is_not_counted_as_a_fee() {
+    let sol = SOL_MINT.parse::<Pubkey>().unwrap();
+    // Entry pool quotes SOL: fees need no conversion
+    let arb = arbitrage(sol, None);
+    let owner = Pubkey::new_unique();
+    let atas = [ata(sol, false), ata(Pubkey::new_unique(), true)];
+    assert_eq!(
+        wrapped_lamports(&atas[0], arb.amount_in_raw),
+        arb.amount_in_raw as i64
+    );
+    assert_eq!(wrapped_lamports(&atas[1], arb.amount_in_raw), 0);
+    assert_eq!(wrapped_lamports(&ata(sol, true), arb.amount_in_raw), 0);
+
+    // The owner pays fee, rent and the wrapped amount; the new wSOL account ends with the
+    // second leg's output
+    let spent = NETWORK_FEE + RENT + arb.amount_in_raw;
+    let landed = Landed {
+        keys: vec![owner, atas[0].ata, atas[1].ata],
+        pre_balances: vec![10_000_000_000, 0, RENT],
+        post_balances: vec![10_000_000_000 - spent, RENT + arb.amount_out_2_raw, RENT],
+        pre_tokens: vec![token_balance(2, 0)],
+        post_tokens: vec![token_balance(1, arb.amount_out_2_raw), token_balance(2, 0)],
+    };
+    let r = reconcile_transaction(
+        confirmed(landed),
+        &Signature::default(),
+        &owner,
+        &owner,
+        &atas,
+        &arb,
+    )
+    .unwrap();
+
+    assert_eq!(r.ata_in_delta_raw, arb.amount_out_2_raw as i64);
+    assert_eq!(r.realized_gross_profit_raw, arb.gross_profit_raw);
+    assert_eq!(r.realized_fees_lamports, NETWORK_FEE + RENT);
+    assert_eq!(
+        r.realized_pnl_raw,
+        Some(arb.gross_profit_raw - (NETWORK_FEE + RENT) as i64)
+    );
+    assert_eq!((r.slippage.leg_1_raw, r.slippage.leg_2_raw), (0, 0));
+}
+
+#[test]
+fn truncated_balances_are_a_decode_error() {
+    let arb = arbitrage(Pubkey::new_unique(), None);
+    let owner = Pubkey::new_unique();
+    let atas = [
+        ata(Pubkey::new_unique(), true),
+        ata(Pubkey::new_unique(), true),
+    ];
+    let landed = Landed {
+        keys: vec![atas[0].ata, atas[1].ata, owner],
+        pre_balances: vec![RENT, RENT, 1_000_000_000],
+        // The owner's entry is missing
+        post_balances: vec![RENT, RENT],
+        pre_tokens: vec![],
+        post_tokens: vec![],
+    };
+    let err = reconcile_transaction(
+        confirmed(landed),
+        &Signature::default(),
+        &owner,
+        &owner,
+        &atas,
+        &arb,
+    )
+    .unwrap_err();
+    assert!(matches!(classify(&err), Some(ArbError::Decode(_))), "{err}");
+}
@@ -0,0 +1,86 @@
+use solana_amm_arb_cli::{
+    error::ArbError,
+    sender::{
+        DEFAULT_MAX_REQUOTES, DEFAULT_REBROADCAST_MS, SendOptions, next_requote, past_deadline,
+        watch_deadline,
+    },
+};
+use solana_sdk::{clock::MAX_PROCESSING_AGE, signature::Signature};
+use std::time::Duration;
+
+fn unused_height() -> anyhow::Result<u64> {
+    panic!("block height fetched although it is not needed")
+}
+
+#[test]
+fn defaults_match_the_cli() {
+    let opts = SendOptions::default();
+    assert_eq!(opts.max_requotes, DEFAULT_MAX_REQUOTES);
+    assert_eq!(
+        opts.rebroadcast_interval,
+        Duration::from_millis(DEFAULT_REBROADCAST_MS)
+    );
+    assert!(!opts.skip_preflight);
+}
+
+#[test]
+fn durable_nonce_transactions_get_a_deadline() {
+    assert_eq!(
+        watch_deadline(None, true, || Ok(1_000)).unwrap(),
+        Some(1_000 + MAX_PROCESSING_AGE as u64)
+    );
+    // A known last valid height wins, without asking for the current one
+    assert_eq!(
+        watch_deadline(Some(500), false, unused_height).unwrap(),
+        Some(500)
+    );
+    assert_eq!(
+        watch_deadline(Some(500), true, unused_height).unwrap(),
+        Some(500)
+    );
+    // A prepared transaction: nothing known, the blockhash is checked instead
+    assert_eq!(watch_deadline(None, false, unused_height).unwrap(), None);
+    // Without a height a durable transaction cannot be watched
+    assert!(watch_deadline(None, true, || Err(ArbError::Rpc("down".into()).into())).is_err());
+}
+
+#[test]
+fn transactions_land_up_to_their_last_valid_block() {
+    assert!(!past_deadline(999, 1_000));
+    assert!(!past_deadline(1_000, 1_000));
+    assert!(past_deadline(1_001, 1_000));
+}
+
+#[test]
+fn requoting_stops_at_max_requotes() {
+    let sig = Signature::default();
+    let mut requotes = 0;
+    let mut rounds = 0;
+    let err = loop {
+        match next_requote(&sig, false, requotes, 2) {
+            Ok(n) => {
+                assert_eq!(n, requotes + 1);
+                requotes = n;
+                rounds += 1;
+            }
+            Err(e) => break e,
+        }
+    };
+    assert_eq!((requotes, rounds), (2, 2));
+    assert!(
+        matches!(&err, ArbError::Send(m) if m.contains("after 2 re-quote(s)")),
+        "{err}"
+    );
+
+    // Re-quoting disabled
+    assert!(next_requote(&sig, false, 0, 0).is_err());
+}
+
+#[test]
+fn durable_nonce_transactions_are_never_requoted() {
+    let err = next_requote(&Signature::default(), true, 0, 5).unwrap_err();
+    assert!(
+        matches!(&err, ArbError::Send(m) if m.contains("until the nonce is advanced")),
+        "{err}"
+    );
+}
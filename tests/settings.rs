@@ -0,0 +1,250 @@
+use clap::Parser;
+use solana_amm_arb_cli::{
+    cli::{AppState, Cli, default_state},
+    endpoints::EndpointRole,
+    error::{ArbError, classify},
+    notify::SinkConfig,
+    settings::{ENV_PREFIX, EffectiveConfig, FileConfig, Sourced, load_config_file, resolve},
+};
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+// `resolve` reads every SOLANA_ARB_* variable, so tests take turns with the environment
+static ENV: Mutex<()> = Mutex::new(());
+
+/// `resolve` with exactly `vars` set among the SOLANA_ARB_* variables.
+fn resolve_with(
+    vars: &[(&str, &str)],
+    state: &AppState,
+    file: Option<(&Path, &FileConfig)>,
+    args: &[&str],
+) -> anyhow::Result<EffectiveConfig> {
+    let _env = ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let cli =
+        Cli::try_parse_from(std::iter::once("solana-amm-arb-cli").chain(args.iter().copied()))
+            .unwrap();
+    // SAFETY: every test in this binary touches the environment only under `ENV`
+    unsafe {
+        for (key, _) in env::vars().filter(|(k, _)| k.starts_with(ENV_PREFIX)) {
+            env::remove_var(key);
+        }
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+    }
+    let eff = resolve(state, "test", file, &cli);
+    unsafe {
+        for (key, _) in vars {
+            env::remove_var(key);
+        }
+    }
+    eff
+}
+
+fn config_file(name: &str, toml: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("arb-settings-{}-{name}.toml", std::process::id()));
+    fs::write(&path, toml).unwrap();
+    path
+}
+
+fn source<T>(v: &Option<Sourced<T>>) -> &str {
+    &v.as_ref().expect("value set").source
+}
+
+fn config_error(result: anyhow::Result<impl std::fmt::Debug>) -> String {
+    let err = result.unwrap_err();
+    assert!(
+        matches!(classify(&err), Some(ArbError::Config(_))),
+        "{err:#}"
+    );
+    format!("{err:#}")
+}
+
+#[test]
+fn each_layer_overrides_the_one_below() {
+    let state = AppState {
+        pool_a: Some("ProfilePool".to_string()),
+        spread_threshold_bps: Some(150),
+        min_pnl_raw: Some(5),
+        slippage_bps: Some(40),
+        ..default_state()
+    };
+    let path = config_file(
+        "layers",
+        "spread_threshold_bps = 200\nmin_pnl_raw = 6\nslippage_bps = 50\n",
+    );
+    let file = load_config_file(&path).unwrap();
+    let eff = resolve_with(
+        &[
+            ("SOLANA_ARB_MIN_PNL_RAW", "7"),
+            ("SOLANA_ARB_SLIPPAGE_BPS", "60"),
+        ],
+        &state,
+        Some((&path, &file)),
+        &["--slippage-bps", "75"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        eff.mint_in.as_ref().unwrap().value,
+        default_state().mint_in.unwrap()
+    );
+    assert_eq!(source(&eff.mint_in), "default");
+    assert_eq!(eff.pool_a.as_ref().unwrap().value, "ProfilePool");
+    assert_eq!(source(&eff.pool_a), "profile:test");
+    assert_eq!(eff.spread_threshold_bps.as_ref().unwrap().value, 200);
+    assert_eq!(
+        source(&eff.spread_threshold_bps),
+        format!("file:{}", path.display())
+    );
+    assert_eq!(eff.min_pnl_raw.as_ref().unwrap().value, 7);
+    assert_eq!(source(&eff.min_pnl_raw), "env:SOLANA_ARB_MIN_PNL_RAW");
+    assert_eq!(eff.slippage_bps.as_ref().unwrap().value, 75);
+    assert_eq!(source(&eff.slippage_bps), "flag:--slippage-bps");
+    // Unset everywhere
+    assert!(eff.fee_payer.is_none());
+
+    let sources = eff.sources();
+    assert_eq!(sources["slippage_bps"], "flag:--slippage-bps");
+    assert!(!sources.contains_key("fee_payer"));
+
+    fs::remove_file(path).ok();
+}
+
+#[test]
+fn endpoints_and_notifiers_come_from_the_environment() {
+    let eff = resolve_with(
+        &[
+            (
+                "SOLANA_ARB_RPC_ENDPOINTS",
+                " https://backup.example  send=https://relay.example ws=wss://ws.example",
+            ),
+            (
+                "SOLANA_ARB_NOTIFIERS",
+                r#"[{"kind": "slack", "url": "https://hooks.example/T0"}]"#,
+            ),
+        ],
+        &default_state(),
+        None,
+        &[],
+    )
+    .unwrap();
+
+    let endpoints = eff.rpc_endpoints.unwrap();
+    assert_eq!(endpoints.source, "env:SOLANA_ARB_RPC_ENDPOINTS");
+    let roles: Vec<_> = endpoints.value.iter().map(|e| e.roles.clone()).collect();
+    assert_eq!(
+        roles,
+        [
+            vec![EndpointRole::Read, EndpointRole::Send],
+            vec![EndpointRole::Send],
+            vec![EndpointRole::Websocket],
+        ]
+    );
+
+    let notifiers = eff.notifiers.unwrap();
+    assert_eq!(notifiers.source, "env:SOLANA_ARB_NOTIFIERS");
+    assert_eq!(
+        notifiers.value[0].sink,
+        SinkConfig::Slack {
+            url: "https://hooks.example/T0".to_string()
+        }
+    );
+
+    // Flags win over the environment
+    let eff = resolve_with(
+        &[("SOLANA_ARB_RPC_ENDPOINTS", "https://backup.example")],
+        &default_state(),
+        None,
+        &["--rpc-endpoint", "read=https://flag.example"],
+    )
+    .unwrap();
+    let endpoints = eff.rpc_endpoints.unwrap();
+    assert_eq!(endpoints.source, "flag:--rpc-endpoint");
+    assert_eq!(endpoints.value[0].url, "https://flag.example");
+}
+
+#[test]
+fn invalid_environment_values_are_config_errors() {
+    let state = default_state();
+    let err = config_error(resolve_with(
+        &[("SOLANA_ARB_SLIPPAGE_BPS", "lots")],
+        &state,
+        None,
+        &[],
+    ));
+    assert!(
+        err.contains("invalid SOLANA_ARB_SLIPPAGE_BPS=lots"),
+        "{err}"
+    );
+
+    let err = config_error(resolve_with(
+        &[("SOLANA_ARB_RPC_ENDPOINTS", "https://ok.example rpc.example")],
+        &state,
+        None,
+        &[],
+    ));
+    assert!(err.contains("invalid SOLANA_ARB_RPC_ENDPOINTS"), "{err}");
+
+    let err = config_error(resolve_with(
+        &[("SOLANA_ARB_NOTIFIERS", r#"{"kind": "slack"}"#)],
+        &state,
+        None,
+        &[],
+    ));
+    assert!(err.contains("SOLANA_ARB_NOTIFIERS (JSON array)"), "{err}");
+
+    let err = config_error(resolve_with(
+        &[("SOLANA_ARB_DECISION_POLICY", r#"{"all": []}"#)],
+        &state,
+        None,
+        &[],
+    ));
+    assert!(
+        err.contains("(from env:SOLANA_ARB_DECISION_POLICY)"),
+        "{err}"
+    );
+}
+
+#[test]
+fn config_files_reject_unknown_keys_and_bad_values() {
+    // A typo must not be silently ignored
+    let path = config_file("typo", "slippage_bps = 50\nslipage_bps = 10\n");
+    let err = config_error(load_config_file(&path));
+    assert!(err.contains("unknown field `slipage_bps`"), "{err}");
+    fs::remove_file(path).ok();
+
+    let path = config_file("type", "slippage_bps = \"fifty\"\n");
+    config_error(load_config_file(&path));
+    fs::remove_file(path).ok();
+
+    let path = config_file(
+        "endpoint",
+        "[[rpc_endpoints]]\nurl = \"https://a.example\"\nroles = [\"reed\"]\n",
+    );
+    config_error(load_config_file(&path));
+    fs::remove_file(path).ok();
+
+    // Well-formed, but the endpoint's URL does not fit its role
+    let path = config_file(
+        "role",
+        "[[rpc_endpoints]]\nurl = \"https://ws.example\"\nroles = [\"websocket\"]\n",
+    );
+    let file = load_config_file(&path).unwrap();
+    let err = config_error(resolve_with(
+        &[],
+        &default_state(),
+        Some((&path, &file)),
+        &[],
+    ));
+    assert!(
+        err.contains(&format!("(from file:{})", path.display())),
+        "{err}"
+    );
+    fs::remove_file(path).ok();
+
+    config_error(load_config_file(Path::new("/nonexistent/arb.toml")));
+}
@@ -0,0 +1,134 @@
+use solana_amm_arb_cli::{
+    nonce::DurableNonce,
+    transaction::{
+        BuiltTransaction, build_transaction, decode_transaction, encode_transaction,
+        missing_signers, sign_missing, uses_durable_nonce,
+    },
+};
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::{Signer, null_signer::NullSigner},
+    system_instruction,
+};
+
+const NONCE_HASH: Hash = Hash::new_from_array([7; 32]);
+
+fn nonce(authority: &Pubkey) -> DurableNonce {
+    DurableNonce {
+        account: Pubkey::new_from_array([3; 32]),
+        authority: *authority,
+        blockhash: NONCE_HASH,
+        lamports_per_signature: 5_000,
+    }
+}
+
+// Stands in for the swaps: the authority moves funds, the fee payer only pays fees
+fn instructions(authority: &Pubkey) -> Vec<Instruction> {
+    vec![
+        ComputeBudgetInstruction::set_compute_unit_price(1_000),
+        system_instruction::transfer(authority, &Pubkey::new_unique(), 1_000_000),
+    ]
+}
+
+fn no_blockhash() -> anyhow::Result<(Hash, u64)> {
+    panic!("latest blockhash fetched for a durable nonce transaction")
+}
+
+fn build_offline(fee_payer: &dyn Signer, authority: &dyn Signer) -> BuiltTransaction {
+    let nonce = nonce(&authority.pubkey());
+    build_transaction(
+        instructions(&authority.pubkey()),
+        fee_payer,
+        authority,
+        Some(&nonce),
+        no_blockhash,
+    )
+    .unwrap()
+}
+
+#[test]
+fn the_nonce_advance_is_the_first_instruction() {
+    let owner = Keypair::new();
+    let built = build_offline(&owner, &owner);
+
+    assert!(uses_durable_nonce(&built.tx));
+    assert_eq!(built.tx.message.recent_blockhash, NONCE_HASH);
+    // A durable nonce does not expire with a block height
+    assert_eq!(built.last_valid_block_height, None);
+    assert_eq!(built.tx.message.instructions.len(), 3);
+    let advance = nonce(&owner.pubkey()).advance_instruction();
+    assert_eq!(built.tx.message.instructions[0].data, advance.data);
+    assert!(built.tx.verify().is_ok());
+    assert!(missing_signers(&built.tx).is_empty());
+}
+
+#[test]
+fn a_recent_blockhash_is_used_without_a_nonce() {
+    let owner = Keypair::new();
+    let hash = Hash::new_from_array([9; 32]);
+    let built = build_transaction(instructions(&owner.pubkey()), &owner, &owner, None, || {
+        Ok((hash, 1_234))
+    })
+    .unwrap();
+
+    assert!(!uses_durable_nonce(&built.tx));
+    assert_eq!(built.tx.message.recent_blockhash, hash);
+    assert_eq!(built.last_valid_block_height, Some(1_234));
+    assert_eq!(built.tx.message.instructions.len(), 2);
+}
+
+#[test]
+fn fee_payer_and_authority_both_sign() {
+    let fee_payer = Keypair::new();
+    let authority = Keypair::new();
+    let built = build_offline(&fee_payer, &authority);
+    let message = &built.tx.message;
+
+    assert_eq!(message.header.num_required_signatures, 2);
+    // The fee payer comes first
+    assert_eq!(message.account_keys[0], fee_payer.pubkey());
+    assert_eq!(message.account_keys[1], authority.pubkey());
+    assert!(built.tx.verify().is_ok());
+
+    // Only the fee payer's key is at hand
+    let built = build_offline(&fee_payer, &NullSigner::new(&authority.pubkey()));
+    assert_eq!(missing_signers(&built.tx), [authority.pubkey()]);
+}
+
+#[test]
+fn signatures_survive_sign_only_sign_and_submit() {
+    let fee_payer = Keypair::new();
+    let authority = Keypair::new();
+
+    // `--sign-only` on the hot machine: the authority's key is offline
+    let built = build_offline(&fee_payer, &NullSigner::new(&authority.pubkey()));
+    let prepared = encode_transaction(&built.tx).unwrap();
+
+    // `sign` on the cold machine
+    let mut tx = decode_transaction(&prepared).unwrap();
+    assert_eq!(tx.signatures[0], built.tx.signatures[0]);
+    assert_eq!(missing_signers(&tx), [authority.pubkey()]);
+    let stranger: Box<dyn Signer> = Box::new(Keypair::new());
+    let signed = sign_missing(&mut tx, &[stranger, Box::new(authority.insecure_clone())]).unwrap();
+    assert_eq!(signed, [authority.pubkey()]);
+    let signed = encode_transaction(&tx).unwrap();
+
+    // `submit`
+    let tx = decode_transaction(&signed).unwrap();
+    assert!(missing_signers(&tx).is_empty());
+    assert!(tx.signatures.iter().all(|sig| *sig != Signature::default()));
+    assert_eq!(tx.signatures[0], built.tx.signatures[0]);
+    assert_eq!(tx.message.recent_blockhash, NONCE_HASH);
+    assert!(uses_durable_nonce(&tx));
+    assert!(tx.verify().is_ok());
+}
+
+#[test]
+fn garbage_is_not_a_transaction() {
+    assert!(decode_transaction("not base64!").is_err());
+    assert!(decode_transaction("aGVsbG8=").is_err());
+}
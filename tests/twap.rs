@@ -0,0 +1,185 @@
+use solana_amm_arb_cli::twap::{
+    PoolTwap, TwapAction, TwapBaseline, TwapGuard, decode_observations, twap_x32,
+};
+use solana_sdk::{account::Account, pubkey::Pubkey};
+
+const CPMM_PROGRAM: Pubkey = solana_sdk::pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+// sha256("account:ObservationState")[..8]
+const DISCRIMINATOR: [u8; 8] = [122, 174, 197, 53, 129, 9, 165, 132];
+const OBSERVATION_NUM: usize = 100;
+const X32: u128 = 1 << 32;
+
+/// (slot in the ring, block_timestamp, cumulative token0 price, cumulative token1 price)
+type Slot = (usize, u64, u128, u128);
+
+// The program's packed layout: discriminator, initialized, observation_index, pool_id,
+// 100 x (block_timestamp, cumulative_token0_price_x32, cumulative_token1_price_x32), padding
+fn observation_account(pool: &Pubkey, initialized: bool, index: u16, slots: &[Slot]) -> Account {
+    let mut ring = [(0u64, 0u128, 0u128); OBSERVATION_NUM];
+    for &(i, ts, c0, c1) in slots {
+        ring[i] = (ts, c0, c1);
+    }
+    let mut data = DISCRIMINATOR.to_vec();
+    data.push(initialized as u8);
+    data.extend(index.to_le_bytes());
+    data.extend(pool.to_bytes());
+    for (ts, c0, c1) in ring {
+        data.extend(ts.to_le_bytes());
+        data.extend(c0.to_le_bytes());
+        data.extend(c1.to_le_bytes());
+    }
+    data.extend([0u8; 4 * 8]);
+    assert_eq!(data.len(), 8 + 1 + 2 + 32 + OBSERVATION_NUM * 40 + 32);
+    Account {
+        lamports: 1,
+        data,
+        owner: CPMM_PROGRAM,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// One observation a minute from t = 1_000 at a constant 2.0 token1 per token0, starting
+// from `base` cumulative values
+fn minutely(first_slot: usize, count: usize, base: u128) -> Vec<Slot> {
+    (0..count)
+        .map(|k| {
+            let dt = 60 * k as u128;
+            (
+                (first_slot + k) % OBSERVATION_NUM,
+                1_000 + dt as u64,
+                base.wrapping_add(2 * X32 * dt),
+                base.wrapping_add(X32 / 2 * dt),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn decodes_the_account_layout() {
+    let pool = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let slots = minutely(0, 4, 0);
+    let state = decode_observations(&key, &observation_account(&pool, true, 3, &slots)).unwrap();
+    assert!(state.initialized);
+    assert_eq!(state.observation_index, 3);
+    assert_eq!(state.pool_id, pool);
+    assert_eq!(state.observations.len(), OBSERVATION_NUM);
+    let newest = &state.observations[3];
+    assert_eq!(newest.block_timestamp, 1_180);
+    assert_eq!(newest.cumulative_token0_price_x32, 2 * X32 * 180);
+    assert_eq!(newest.cumulative_token1_price_x32, X32 / 2 * 180);
+
+    let mut foreign = observation_account(&pool, true, 3, &slots);
+    foreign.owner = Pubkey::new_unique();
+    assert!(decode_observations(&key, &foreign).is_err());
+    let mut garbled = observation_account(&pool, true, 3, &slots);
+    garbled.data[0] ^= 0xff;
+    assert!(decode_observations(&key, &garbled).is_err());
+}
+
+#[test]
+fn averages_the_newest_window() {
+    let pool = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let state = decode_observations(
+        &key,
+        &observation_account(&pool, true, 3, &minutely(0, 4, 0)),
+    )
+    .unwrap();
+    // Walks back until the window is covered: 1_180 - 1_060
+    assert_eq!(twap_x32(&state, 120, true), Some((2 * X32, 120)));
+    assert_eq!(twap_x32(&state, 120, false), Some((X32 / 2, 120)));
+    assert_eq!(twap_x32(&state, 61, true), Some((2 * X32, 120)));
+    // Longer than the history: everything there is
+    assert_eq!(twap_x32(&state, 3_600, true), Some((2 * X32, 180)));
+}
+
+#[test]
+fn follows_the_ring_and_cumulative_wraparound() {
+    let pool = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    // Written into slots 98, 99, 0, 1; cumulative prices overflow u128 on the way
+    let base = u128::MAX - 2 * X32 * 30;
+    let acc = observation_account(&pool, true, 1, &minutely(98, 4, base));
+    let state = decode_observations(&key, &acc).unwrap();
+    assert_eq!(twap_x32(&state, 180, true), Some((2 * X32, 180)));
+    assert_eq!(twap_x32(&state, 180, false), Some((X32 / 2, 180)));
+}
+
+#[test]
+fn too_little_history_has_no_twap() {
+    let pool = Pubkey::new_unique();
+    let key = Pubkey::new_unique();
+    let one = observation_account(&pool, true, 0, &minutely(0, 1, 0));
+    assert_eq!(
+        twap_x32(&decode_observations(&key, &one).unwrap(), 60, true),
+        None
+    );
+    let uninitialized = observation_account(&pool, false, 3, &minutely(0, 4, 0));
+    assert_eq!(
+        twap_x32(
+            &decode_observations(&key, &uninitialized).unwrap(),
+            60,
+            true
+        ),
+        None
+    );
+    let empty = observation_account(&pool, true, 0, &[]);
+    assert_eq!(
+        twap_x32(&decode_observations(&key, &empty).unwrap(), 60, true),
+        None
+    );
+}
+
+fn baseline(max_deviation_bps: u32, action: TwapAction) -> TwapBaseline {
+    let twap = |pool: &str, twap_price| PoolTwap {
+        pool: pool.to_string(),
+        twap_price,
+        covered_secs: 300,
+    };
+    TwapBaseline {
+        guard: TwapGuard {
+            window_secs: 300,
+            max_deviation_bps,
+            action,
+        },
+        first: twap("first", Some(1.0)),
+        second: twap("second", None),
+    }
+}
+
+#[test]
+fn deviation_at_the_limit_passes() {
+    // 1.0625 and 0.9375 are exactly 625 bps from 1.0 in binary floating point
+    for spot in [1.0625, 0.9375] {
+        let report = baseline(625, TwapAction::Block).check(spot, 1.0);
+        assert_eq!(report.pools[0].deviation_bps, Some(625.0));
+        assert!(!report.pools[0].exceeded);
+        assert!(!report.blocked);
+        assert!(report.breaches().is_empty());
+
+        let report = baseline(624, TwapAction::Block).check(spot, 1.0);
+        assert!(report.pools[0].exceeded);
+        assert!(report.blocked);
+        assert_eq!(report.breaches().len(), 1);
+    }
+}
+
+#[test]
+fn flag_reports_without_blocking() {
+    let report = baseline(624, TwapAction::Flag).check(1.0625, 1.0);
+    assert!(report.pools[0].exceeded);
+    assert!(!report.blocked);
+    assert!(report.breaches()[0].starts_with("first spot 1.062500000000 vs twap 1.000000000000"));
+}
+
+#[test]
+fn unknown_twap_is_never_exceeded() {
+    // The second pool has no TWAP, however far its spot moves
+    let report = baseline(0, TwapAction::Block).check(1.0, 1_000.0);
+    assert_eq!(report.pools[1].deviation_bps, None);
+    assert!(!report.pools[1].exceeded);
+    assert!(!report.blocked);
+    assert_eq!(report.unknown(), vec!["second"]);
+}